@@ -1,8 +1,20 @@
 #!/bin/bash
 set -e
 
-cargo +stable build --target wasm32-unknown-unknown --release
+# The NEAR runtime and near-sdk-sim only load MVP wasm. Since 1.70 rustc emits sign-extension
+# ops, also in the precompiled std, so the contracts are built with the last release that doesn't.
+TOOLCHAIN=1.69.0
+export RUSTFLAGS='-C link-args=-s'
+BUILD="cargo +$TOOLCHAIN build --target wasm32-unknown-unknown --release"
+
+# Commit reported by the contracts' `version` views.
+export GIT_HASH=$(git rev-parse --short HEAD)
+
+# The factory embeds the DAO and staking contracts, build them first.
+$BUILD -p sputnik-staking -p sputnikdao2
 cp target/wasm32-unknown-unknown/release/sputnik_staking.wasm ./sputnik-staking/res/
 cp target/wasm32-unknown-unknown/release/sputnikdao2.wasm ./sputnikdao2/res/
+$BUILD
 cp target/wasm32-unknown-unknown/release/sputnikdao_factory2.wasm ./sputnikdao-factory2/res/
-cp target/wasm32-unknown-unknown/release/test_token.wasm ./test-token/res/
\ No newline at end of file
+cp target/wasm32-unknown-unknown/release/test_token.wasm ./test-token/res/
+cp target/wasm32-unknown-unknown/release/sputnik_executor.wasm ./sputnik-executor/res/
@@ -0,0 +1,5 @@
+#!/bin/bash
+set -e
+
+RUSTFLAGS='-C link-arg=-s' cargo +1.69.0 build --target wasm32-unknown-unknown --release
+cp ../target/wasm32-unknown-unknown/release/sputnik_executor.wasm ./res/
@@ -0,0 +1,100 @@
+//! Executor of function calls for a Sputnik DAO, deployed on a sub-account of the DAO.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base64VecU8, U64};
+use near_sdk::{env, near_bindgen, AccountId, Gas, PanicOnDefault, Promise};
+
+#[near_bindgen]
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+pub struct Contract {
+    /// DAO that deployed this executor, the only account that can make calls through it.
+    owner_id: AccountId,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        Self { owner_id }
+    }
+
+    /// Calls `method_name` of `receiver_id` with the attached deposit on behalf of the owner.
+    /// Returns the result of the call.
+    #[payable]
+    pub fn proxy_call(
+        &mut self,
+        receiver_id: AccountId,
+        method_name: String,
+        args: Base64VecU8,
+        gas: U64,
+    ) -> Promise {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "ERR_NOT_OWNER"
+        );
+        Promise::new(receiver_id).function_call(
+            method_name,
+            args.into(),
+            env::attached_deposit(),
+            Gas(gas.0),
+        )
+    }
+
+    pub fn get_owner_id(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_proxy_call() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(10)
+            .build());
+        let mut contract = Contract::new(accounts(1));
+        contract.proxy_call(
+            accounts(2),
+            "ping".to_string(),
+            Base64VecU8(b"{}".to_vec()),
+            U64(10_000_000_000_000),
+        );
+        let receipt = get_created_receipts().pop().unwrap();
+        assert_eq!(receipt.receiver_id, accounts(2));
+        match &receipt.actions[0] {
+            VmAction::FunctionCall {
+                function_name,
+                deposit,
+                ..
+            } => {
+                assert_eq!(function_name, "ping");
+                assert_eq!(*deposit, 10);
+            }
+            _ => panic!("Unexpected action"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_OWNER")]
+    fn test_proxy_call_not_owner() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(accounts(1));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.proxy_call(
+            accounts(3),
+            "ping".to_string(),
+            Base64VecU8(vec![]),
+            U64(10_000_000_000_000),
+        );
+    }
+}
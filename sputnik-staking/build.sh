@@ -1,5 +1,8 @@
 #!/bin/bash
 set -e
 
-RUSTFLAGS='-C link-arg=-s' cargo +stable build --target wasm32-unknown-unknown --release
+# Commit reported by the contracts' `version` views.
+export GIT_HASH=$(git rev-parse --short HEAD)
+
+RUSTFLAGS='-C link-arg=-s' cargo +1.69.0 build --target wasm32-unknown-unknown --release
 cp ../target/wasm32-unknown-unknown/release/sputnik_staking.wasm ./res/
@@ -0,0 +1,45 @@
+//! Method names and argument / return types of the staking contract for Rust clients, e.g.
+//! bots and integration tests in other repositories. Enabled with the `client` feature.
+//!
+//! Contract entry points are left out with the `client` feature, so crates using this module,
+//! including contracts built for `wasm32`, don't include or export contract code.
+
+pub use crate::{
+    DelegatorOutput, GasConfig, PendingOwner, StakingInfo, UndelegationError, User, VotingPower,
+};
+
+// Change methods.
+pub const NEW: &str = "new";
+pub const MIGRATE: &str = "migrate";
+pub const SET_GAS_CONFIG: &str = "set_gas_config";
+pub const SET_OWNER: &str = "set_owner";
+pub const FORWARD_DELEGATED_TOTALS: &str = "forward_delegated_totals";
+pub const SYNC_DELEGATED_TOTALS: &str = "sync_delegated_totals";
+pub const DELEGATE: &str = "delegate";
+pub const DELEGATE_BATCH: &str = "delegate_batch";
+pub const UNDELEGATE: &str = "undelegate";
+pub const REDELEGATE: &str = "redelegate";
+pub const UNDELEGATE_ALL: &str = "undelegate_all";
+pub const UNDELEGATE_ALL_FROM: &str = "undelegate_all_from";
+pub const WITHDRAW: &str = "withdraw";
+pub const RAGEQUIT: &str = "ragequit";
+pub const FT_ON_TRANSFER: &str = "ft_on_transfer";
+pub const STORAGE_DEPOSIT: &str = "storage_deposit";
+pub const STORAGE_WITHDRAW: &str = "storage_withdraw";
+pub const STORAGE_UNREGISTER: &str = "storage_unregister";
+
+// View methods.
+pub const VERSION: &str = "version";
+pub const GET_INFO: &str = "get_info";
+pub const GET_GAS_CONFIG: &str = "get_gas_config";
+pub const GET_PENDING_OWNER: &str = "get_pending_owner";
+pub const GET_USER: &str = "get_user";
+pub const GET_USERS: &str = "get_users";
+pub const GET_STAKER_COUNT: &str = "get_staker_count";
+pub const GET_UNDELEGATION_ERROR: &str = "get_undelegation_error";
+pub const FT_TOTAL_SUPPLY: &str = "ft_total_supply";
+pub const FT_BALANCE_OF: &str = "ft_balance_of";
+pub const VOTING_POWER_OF: &str = "voting_power_of";
+pub const DELEGATORS_OF: &str = "delegators_of";
+pub const STORAGE_BALANCE_BOUNDS: &str = "storage_balance_bounds";
+pub const STORAGE_BALANCE_OF: &str = "storage_balance_of";
@@ -1,21 +1,33 @@
+// Contract entry points are left out for clients, leaving some helpers and imports unused.
+#![cfg_attr(feature = "client", allow(dead_code, unused_imports))]
+
 use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LookupMap;
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     env, ext_contract, near_bindgen, AccountId, Balance, BorshStorageKey, Duration, Gas,
     PanicOnDefault, Promise, PromiseOrValue, PromiseResult,
 };
 
-pub use user::{User, VersionedUser};
+use user::Delegator;
+pub use user::{DelegatorOutput, UndelegationError, User, UserV1, VersionedUser, VotingPower};
 
+#[cfg(feature = "client")]
+pub mod client;
 mod storage_impl;
 mod user;
 
 #[derive(BorshStorageKey, BorshSerialize)]
 enum StorageKeys {
     Users,
+    DelegatedTotals,
+    Delegators,
+    DelegatorsOf { delegate_id: AccountId },
+    UserIds,
+    UndelegationErrors,
 }
 
 /// Amount of gas for fungible token transfers.
@@ -27,17 +39,60 @@ pub const GAS_FOR_DELEGATE: Gas = Gas(10_000_000_000_000);
 /// Amount of gas for register action.
 pub const GAS_FOR_REGISTER: Gas = Gas(10_000_000_000_000);
 
+/// Amount of gas for the callback of delegate action, not including the retry.
+/// Also used for the callback of undelegate action.
+pub const GAS_FOR_DELEGATE_CALLBACK: Gas = Gas(10_000_000_000_000);
+
 /// Amount of gas for undelegate action.
 pub const GAS_FOR_UNDELEGATE: Gas = Gas(10_000_000_000_000);
 
+/// Amount of gas for ragequit action. Covers payouts of the DAO's ragequit tokens.
+pub const GAS_FOR_RAGEQUIT: Gas = Gas(150_000_000_000_000);
+
+/// Max number of delegations in `delegate_batch`. Forwarding each takes 40 Tgas by default,
+/// so more wouldn't fit into the gas of a single transaction.
+pub const MAX_DELEGATION_BATCH: usize = 5;
+
+/// Lower bound of each configurable gas amount.
+pub const MIN_CONFIG_GAS: Gas = Gas(2_000_000_000_000);
+
+/// Upper bound of each configurable gas amount.
+pub const MAX_CONFIG_GAS: Gas = Gas(250_000_000_000_000);
+
+/// Gas attached to cross-contract calls. Defaults to the `GAS_FOR_*` constants.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GasConfig {
+    pub ft_transfer: Gas,
+    pub delegate: Gas,
+    pub delegate_callback: Gas,
+    pub register: Gas,
+    pub undelegate: Gas,
+    pub ragequit: Gas,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            ft_transfer: GAS_FOR_FT_TRANSFER,
+            delegate: GAS_FOR_DELEGATE,
+            delegate_callback: GAS_FOR_DELEGATE_CALLBACK,
+            register: GAS_FOR_REGISTER,
+            undelegate: GAS_FOR_UNDELEGATE,
+            ragequit: GAS_FOR_RAGEQUIT,
+        }
+    }
+}
+
 #[ext_contract(ext_sputnik)]
 pub trait Sputnik {
     fn register_delegation(&mut self, account_id: AccountId);
     fn delegate(&mut self, account_id: AccountId, amount: U128);
     fn undelegate(&mut self, account_id: AccountId, amount: U128);
+    fn ragequit(&mut self, account_id: AccountId, amount: U128);
 }
 
-#[near_bindgen]
+#[cfg_attr(not(feature = "client"), near_bindgen)]
 #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
 pub struct Contract {
     /// DAO owner of this staking contract.
@@ -50,13 +105,100 @@ pub struct Contract {
     total_amount: Balance,
     /// Duration of unstaking. Should be over the possible voting periods.
     unstake_period: Duration,
+    /// Total amount delegated to each account.
+    delegated_totals: UnorderedMap<AccountId, Balance>,
+    /// Sum of `delegated_totals`.
+    total_delegated: Balance,
+    /// Accounts delegating to each account.
+    delegators: LookupMap<AccountId, UnorderedMap<AccountId, Delegator>>,
+    /// Gas attached to cross-contract calls.
+    gas_config: GasConfig,
+    /// Accounts of registered users, for enumeration.
+    user_ids: UnorderedSet<AccountId>,
+    /// Owner transfer started by `set_owner`, if any.
+    pending_owner: Option<PendingOwner>,
+    /// Last undelegation of each account that the owner DAO rejected.
+    undelegation_errors: LookupMap<AccountId, UndelegationError>,
+}
+
+/// Owner transfer waiting for the delegated totals to be forwarded to the new owner DAO.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingOwner {
+    pub owner_id: AccountId,
+    /// Number of delegated totals the new owner DAO accepted.
+    pub forwarded: U64,
+    /// True while a chunk of totals is being forwarded.
+    pub forwarding: bool,
+}
+
+/// Summary of this staking contract for discovery by wallets.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingInfo {
+    /// DAO owner of this staking contract.
+    pub owner_id: AccountId,
+    pub unstake_period: U64,
+    /// Accepted tokens with the votes given per staked token unit.
+    pub tokens: Vec<(AccountId, u32)>,
+    /// Total token amount staked.
+    pub total_supply: U128,
+    /// Votes backed by all staked tokens, delegated or not.
+    pub total_voting_power: U128,
+    /// Votes counted in the delegated totals returned by `voting_power_of`. Lower than the DAO's
+    /// `delegation_total_supply` by the delegations made before the totals were tracked and not
+    /// synced yet, see `sync_delegated_totals`.
+    pub total_delegated: U128,
+    /// Version of this contract.
+    pub version: String,
+}
+
+/// Contract state of the previous release, before `delegated_totals`, `delegators`,
+/// `gas_config` and `user_ids` were added.
+#[derive(BorshDeserialize)]
+struct OldContract {
+    owner_id: AccountId,
+    vote_token_id: AccountId,
+    users: LookupMap<AccountId, VersionedUser>,
+    total_amount: Balance,
+    unstake_period: Duration,
 }
 
 #[ext_contract(ext_self)]
 pub trait Contract {
+    fn on_delegate(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: AccountId,
+        amount: U128,
+        retried: bool,
+    ) -> PromiseOrValue<()>;
+    fn on_register_delegate(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: AccountId,
+        amount: U128,
+    ) -> PromiseOrValue<()>;
+    fn on_undelegate(&mut self, sender_id: AccountId, delegate_id: AccountId, amount: U128);
+    fn on_redelegate(
+        &mut self,
+        sender_id: AccountId,
+        from_id: AccountId,
+        to_id: AccountId,
+        amount: U128,
+    ) -> PromiseOrValue<()>;
     fn exchange_callback_post_withdraw(&mut self, sender_id: AccountId, amount: U128);
+    fn exchange_callback_post_unregister(
+        &mut self,
+        sender_id: AccountId,
+        near_amount: U128,
+        amount: U128,
+    );
+    fn exchange_callback_post_ragequit(&mut self, sender_id: AccountId, amount: U128);
+    fn on_forward_delegated_totals(&mut self, from_index: U64, count: U64) -> bool;
 }
 
+#[cfg(not(feature = "client"))]
 #[near_bindgen]
 impl Contract {
     #[init]
@@ -67,7 +209,190 @@ impl Contract {
             users: LookupMap::new(StorageKeys::Users),
             total_amount: 0,
             unstake_period: unstake_period.0,
+            delegated_totals: UnorderedMap::new(StorageKeys::DelegatedTotals),
+            total_delegated: 0,
+            delegators: LookupMap::new(StorageKeys::Delegators),
+            gas_config: GasConfig::default(),
+            user_ids: UnorderedSet::new(StorageKeys::UserIds),
+            pending_owner: None,
+            undelegation_errors: LookupMap::new(StorageKeys::UndelegationErrors),
+        }
+    }
+
+    /// Migrates state from the previous release, without `delegated_totals`, `delegators`,
+    /// `gas_config` and `user_ids`.
+    /// Delegations made and users registered before the migration are not listed by
+    /// `delegators_of` and `get_users`, nor counted by `get_staker_count`. Their delegations are
+    /// added to the delegated totals on the user's next action or with `sync_delegated_totals`.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old: OldContract = env::state_read().expect("ERR_NOT_INITIALIZED");
+        Self {
+            owner_id: old.owner_id,
+            vote_token_id: old.vote_token_id,
+            users: old.users,
+            total_amount: old.total_amount,
+            unstake_period: old.unstake_period,
+            delegated_totals: UnorderedMap::new(StorageKeys::DelegatedTotals),
+            total_delegated: 0,
+            delegators: LookupMap::new(StorageKeys::Delegators),
+            gas_config: GasConfig::default(),
+            user_ids: UnorderedSet::new(StorageKeys::UserIds),
+            pending_owner: None,
+            undelegation_errors: LookupMap::new(StorageKeys::UndelegationErrors),
+        }
+    }
+
+    /// Returns semver of this contract. If `GIT_HASH` was set at build time, the commit is
+    /// added as build metadata, e.g. `1.0.0+1a2b3c4`.
+    pub fn version(&self) -> String {
+        match option_env!("GIT_HASH") {
+            Some(hash) => format!("{}+{}", env!("CARGO_PKG_VERSION"), hash),
+            None => env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Returns owner DAO, parameters and totals of this contract.
+    pub fn get_info(&self) -> StakingInfo {
+        StakingInfo {
+            owner_id: self.owner_id.clone(),
+            unstake_period: U64(self.unstake_period),
+            tokens: vec![(self.vote_token_id.clone(), 1)],
+            total_supply: U128(self.total_amount),
+            total_voting_power: U128(self.total_amount),
+            total_delegated: U128(self.total_delegated),
+            version: self.version(),
+        }
+    }
+
+    /// Returns gas attached to cross-contract calls.
+    pub fn get_gas_config(&self) -> GasConfig {
+        self.gas_config.clone()
+    }
+
+    /// Starts transferring ownership to given account, e.g. when the owner DAO moves to a new
+    /// account. Only the owner DAO can call this. The new owner DAO must already use this staking
+    /// contract. Ownership is transferred once the new owner DAO accepted all delegated totals,
+    /// see `forward_delegated_totals`, or right away if there are none.
+    /// Delegations can't change until then.
+    pub fn set_owner(&mut self, new_owner_id: AccountId) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "ERR_NOT_OWNER"
+        );
+        assert_ne!(new_owner_id, self.owner_id, "ERR_SAME_OWNER");
+        assert!(self.pending_owner.is_none(), "ERR_OWNER_TRANSFER_PENDING");
+        self.pending_owner = Some(PendingOwner {
+            owner_id: new_owner_id,
+            forwarded: U64(0),
+            forwarding: false,
+        });
+        self.internal_complete_owner_transfer();
+    }
+
+    /// Registers and delegates the next `limit` delegated totals in the new owner DAO of the
+    /// pending owner transfer. Anyone can call this, attaching the deposit for the registrations.
+    /// Ownership is transferred once all totals were accepted, see `on_forward_delegated_totals`.
+    #[payable]
+    pub fn forward_delegated_totals(&mut self, limit: u64) -> Promise {
+        let mut pending = self.pending_owner.clone().expect("ERR_NO_OWNER_TRANSFER");
+        assert!(!pending.forwarding, "ERR_FORWARDING_IN_PROGRESS");
+        let from_index = pending.forwarded.0;
+        let to_index = std::cmp::min(from_index + limit, self.delegated_totals.len());
+        assert!(to_index > from_index, "ERR_NOTHING_TO_FORWARD");
+        pending.forwarding = true;
+        let promise =
+            self.internal_forward_delegated_totals(&pending.owner_id, from_index, to_index);
+        self.pending_owner = Some(pending);
+        let count = to_index - from_index;
+        promise.then(ext_self::on_forward_delegated_totals(
+            U64(from_index),
+            U64(count),
+            env::current_account_id(),
+            0,
+            self.gas_config.delegate_callback + self.gas_config.undelegate * count,
+        ))
+    }
+
+    /// Records the forwarded totals if the new owner DAO accepted all of them, and transfers
+    /// ownership once all totals were forwarded. Otherwise undelegates the accepted ones there,
+    /// so the same chunk can be forwarded again. Returns true if the chunk was accepted.
+    #[private]
+    pub fn on_forward_delegated_totals(&mut self, from_index: U64, count: U64) -> bool {
+        assert_eq!(
+            env::promise_results_count(),
+            count.0,
+            "ERR_CALLBACK_POST_FORWARD_INVALID",
+        );
+        let mut pending = self.pending_owner.clone().expect("ERR_NO_OWNER_TRANSFER");
+        pending.forwarding = false;
+        let accepted: Vec<bool> = (0..count.0)
+            .map(|index| matches!(env::promise_result(index), PromiseResult::Successful(_)))
+            .collect();
+        let all_accepted = accepted.iter().all(|accepted| *accepted);
+        if all_accepted {
+            pending.forwarded = U64(from_index.0 + count.0);
+        } else {
+            let keys = self.delegated_totals.keys_as_vector();
+            let values = self.delegated_totals.values_as_vector();
+            for (index, _) in accepted
+                .iter()
+                .enumerate()
+                .filter(|(_, accepted)| **accepted)
+            {
+                let index = from_index.0 + index as u64;
+                ext_sputnik::undelegate(
+                    keys.get(index).unwrap(),
+                    U128(values.get(index).unwrap()),
+                    pending.owner_id.clone(),
+                    0,
+                    self.gas_config.undelegate,
+                );
+            }
+        }
+        self.pending_owner = Some(pending);
+        self.internal_complete_owner_transfer();
+        all_accepted
+    }
+
+    /// Adds delegations of given users made before delegated totals were tracked to the totals,
+    /// so they are forwarded on owner transfer and counted by `voting_power_of`, and lists users
+    /// stored before `get_users` was added. Anyone can call this. Users are synced anyway on
+    /// their next action.
+    pub fn sync_delegated_totals(&mut self, account_ids: Vec<AccountId>) {
+        for account_id in account_ids {
+            self.internal_sync_user(&account_id);
+        }
+    }
+
+    /// Owner transfer waiting for the delegated totals to be forwarded, if any.
+    pub fn get_pending_owner(&self) -> Option<PendingOwner> {
+        self.pending_owner.clone()
+    }
+
+    /// Sets gas attached to cross-contract calls. Only the owner DAO can call this.
+    pub fn set_gas_config(&mut self, gas_config: GasConfig) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "ERR_NOT_OWNER"
+        );
+        for gas in [
+            gas_config.ft_transfer,
+            gas_config.delegate,
+            gas_config.delegate_callback,
+            gas_config.register,
+            gas_config.undelegate,
+            gas_config.ragequit,
+        ] {
+            assert!(
+                gas >= MIN_CONFIG_GAS && gas <= MAX_CONFIG_GAS,
+                "ERR_INVALID_GAS_CONFIG"
+            );
         }
+        self.gas_config = gas_config;
     }
 
     /// Total number of tokens staked in this contract.
@@ -85,31 +410,309 @@ impl Contract {
         self.internal_get_user(&account_id)
     }
 
+    /// Registered users in paginated view.
+    pub fn get_users(&self, from_index: u64, limit: u64) -> Vec<(AccountId, User)> {
+        let user_ids = self.user_ids.as_vector();
+        (from_index..std::cmp::min(from_index + limit, user_ids.len()))
+            .filter_map(|index| user_ids.get(index))
+            .map(|account_id| {
+                let user = self.internal_get_user(&account_id);
+                (account_id, user)
+            })
+            .collect()
+    }
+
+    /// Last undelegation of given account that the owner DAO rejected, if any.
+    pub fn get_undelegation_error(&self, account_id: AccountId) -> Option<UndelegationError> {
+        self.undelegation_errors.get(&account_id)
+    }
+
+    /// Number of registered users.
+    pub fn get_staker_count(&self) -> U64 {
+        U64(self.user_ids.len())
+    }
+
+    /// Voting power of given account, split into votes delegated by the account to itself
+    /// and votes delegated to it by others. The total equals the DAO's `delegation_balance_of`
+    /// once delegations to the account made before the totals were tracked are synced, see
+    /// `sync_delegated_totals`. Until then it's lower, and `total_delegated` of `get_info` shows
+    /// the gap across all accounts.
+    pub fn voting_power_of(&self, account_id: AccountId) -> VotingPower {
+        let total = self.delegated_totals.get(&account_id).unwrap_or_default();
+        let self_held = self
+            .internal_get_user_opt(&account_id)
+            .and_then(|user| {
+                user.delegated_amounts
+                    .into_iter()
+                    .find(|(delegate_id, _)| *delegate_id == account_id)
+                    .map(|(_, amount)| amount.0)
+            })
+            .unwrap_or_default();
+        VotingPower {
+            total: U128(total),
+            self_held: U128(self_held),
+            delegated: U128(total.saturating_sub(self_held)),
+            tokens: vec![(self.vote_token_id.clone(), U128(total))],
+        }
+    }
+
+    /// Accounts delegating to given account, with their amounts and last delegation timestamps.
+    pub fn delegators_of(
+        &self,
+        account_id: AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<DelegatorOutput> {
+        let delegators = match self.delegators.get(&account_id) {
+            Some(delegators) => delegators,
+            None => return vec![],
+        };
+        let keys = delegators.keys_as_vector();
+        let values = delegators.values_as_vector();
+        (from_index..std::cmp::min(from_index + limit, keys.len()))
+            .map(|index| {
+                let delegator = values.get(index).unwrap();
+                DelegatorOutput {
+                    account_id: keys.get(index).unwrap(),
+                    amount: U128(delegator.amount),
+                    timestamp: U64(delegator.timestamp),
+                }
+            })
+            .collect()
+    }
+
     /// Delegate give amount of votes to given account.
     /// If enough tokens and storage, forwards this to owner account.
+    /// If the owner doesn't accept the delegation, see `on_delegate`.
     pub fn delegate(&mut self, account_id: AccountId, amount: U128) -> Promise {
         let sender_id = env::predecessor_account_id();
-        self.internal_delegate(sender_id, account_id.clone().into(), amount.0);
-        ext_sputnik::delegate(
-            account_id.into(),
-            amount,
-            self.owner_id.clone(),
-            0,
-            GAS_FOR_DELEGATE,
-        )
+        self.internal_delegate(sender_id.clone(), account_id.clone().into(), amount.0);
+        self.internal_forward_delegation(sender_id, account_id, amount)
+    }
+
+    /// Delegates given amounts of votes to given accounts in one call, like `delegate`.
+    /// Up to `MAX_DELEGATION_BATCH` delegations. Requires enough gas to forward each delegation
+    /// to the owner DAO.
+    pub fn delegate_batch(&mut self, delegations: Vec<(AccountId, U128)>) -> Promise {
+        assert!(!delegations.is_empty(), "ERR_NO_DELEGATIONS");
+        assert!(
+            delegations.len() <= MAX_DELEGATION_BATCH,
+            "ERR_TOO_MANY_DELEGATIONS"
+        );
+        let sender_id = env::predecessor_account_id();
+        let sender = self.internal_get_user(&sender_id);
+        let total = delegations
+            .iter()
+            .fold(0, |total, (_, amount)| total + amount.0);
+        assert!(
+            sender.delegated_amount() + total <= sender.vote_amount.0,
+            "ERR_NOT_ENOUGH_AMOUNT"
+        );
+        let mut promise: Option<Promise> = None;
+        for (account_id, amount) in delegations {
+            self.internal_delegate(sender_id.clone(), account_id.clone(), amount.0);
+            let delegation =
+                self.internal_forward_delegation(sender_id.clone(), account_id, amount);
+            promise = Some(match promise {
+                Some(promise) => promise.and(delegation),
+                None => delegation,
+            });
+        }
+        promise.unwrap()
+    }
+
+    /// Handles delegation rejected by the owner DAO, which happens when the delegate is not
+    /// registered there (`ERR_NOT_REGISTERED`). Failure reasons are not visible to callbacks,
+    /// so the first failure registers the delegate, paid from the sender's storage deposit,
+    /// and retries the delegation once, see `on_register_delegate`. Reverts the delegation if
+    /// that is not possible or the retry fails as well.
+    #[private]
+    pub fn on_delegate(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: AccountId,
+        amount: U128,
+        retried: bool,
+    ) -> PromiseOrValue<()> {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_CALLBACK_POST_DELEGATE_INVALID",
+        );
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {}
+            PromiseResult::Failed => {
+                if !retried {
+                    if let Some(register) =
+                        self.internal_register_delegate(&sender_id, &delegate_id)
+                    {
+                        return register
+                            .then(ext_self::on_register_delegate(
+                                sender_id,
+                                delegate_id,
+                                amount,
+                                env::current_account_id(),
+                                0,
+                                self.gas_config.delegate + self.gas_config.delegate_callback * 2,
+                            ))
+                            .into();
+                    }
+                }
+                // This reverts the changes from delegate function.
+                self.internal_revert_delegate(&sender_id, &delegate_id, amount.0);
+            }
+        };
+        PromiseOrValue::Value(())
+    }
+
+    /// Retries the delegation once the owner DAO registered the delegate. Credits the register
+    /// deposit back to the sender if the DAO refunded it, as the delegate was already registered,
+    /// or if the registration failed. Reverts the delegation in the latter case.
+    #[private]
+    pub fn on_register_delegate(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: AccountId,
+        amount: U128,
+    ) -> PromiseOrValue<()> {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_CALLBACK_POST_REGISTER_INVALID",
+        );
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(value) => {
+                // DAOs before `register_delegation` returned whether it registered return nothing.
+                if matches!(near_sdk::serde_json::from_slice(&value), Ok(false)) {
+                    self.internal_refund_register(&sender_id);
+                }
+                ext_sputnik::delegate(
+                    delegate_id.clone(),
+                    amount,
+                    self.owner_id.clone(),
+                    0,
+                    self.gas_config.delegate,
+                )
+                .then(ext_self::on_delegate(
+                    sender_id,
+                    delegate_id,
+                    amount,
+                    true,
+                    env::current_account_id(),
+                    0,
+                    self.gas_config.delegate_callback,
+                ))
+                .into()
+            }
+            PromiseResult::Failed => {
+                self.internal_refund_register(&sender_id);
+                self.internal_revert_delegate(&sender_id, &delegate_id, amount.0);
+                PromiseOrValue::Value(())
+            }
+        }
     }
 
     /// Remove given amount of delegation.
+    /// If the owner doesn't accept the undelegation, see `on_undelegate`.
     pub fn undelegate(&mut self, account_id: AccountId, amount: U128) -> Promise {
         let sender_id = env::predecessor_account_id();
-        self.internal_undelegate(sender_id, account_id.clone().into(), amount.0);
+        self.internal_undelegate(sender_id.clone(), account_id.clone(), amount.0);
+        self.internal_forward_undelegation(sender_id, account_id, amount)
+    }
+
+    /// Undelegates all votes of the caller, like `undelegate` for each delegate.
+    /// Requires enough gas to forward each undelegation to the owner DAO.
+    pub fn undelegate_all(&mut self) -> Promise {
+        self.internal_undelegate_all(env::predecessor_account_id(), None)
+    }
+
+    /// Undelegates all votes of the caller from given account, like `undelegate`.
+    pub fn undelegate_all_from(&mut self, account_id: AccountId) -> Promise {
+        self.internal_undelegate_all(env::predecessor_account_id(), Some(&account_id))
+    }
+
+    /// Moves given amount of votes from one delegate to another. Like `undelegate`, needs the
+    /// cooldown to have passed and starts a new one, as the votes are immediately usable again.
+    /// The owner DAO first undelegates from `from_id`, see `on_redelegate`.
+    pub fn redelegate(&mut self, from_id: AccountId, to_id: AccountId, amount: U128) -> Promise {
+        let sender_id = env::predecessor_account_id();
+        self.internal_redelegate(&sender_id, &from_id, &to_id, amount.0);
         ext_sputnik::undelegate(
-            account_id.into(),
+            from_id.clone(),
             amount,
             self.owner_id.clone(),
             0,
-            GAS_FOR_UNDELEGATE,
+            self.gas_config.undelegate,
         )
+        .then(ext_self::on_redelegate(
+            sender_id,
+            from_id,
+            to_id,
+            amount,
+            env::current_account_id(),
+            0,
+            self.gas_config.register
+                + self.gas_config.delegate * 2
+                + self.gas_config.delegate_callback * 4,
+        ))
+    }
+
+    /// Delegates to `to_id` in the owner DAO once it undelegated from `from_id`, like `delegate`.
+    /// Moves the votes back to `from_id` if the owner DAO rejected the undelegation.
+    #[private]
+    pub fn on_redelegate(
+        &mut self,
+        sender_id: AccountId,
+        from_id: AccountId,
+        to_id: AccountId,
+        amount: U128,
+    ) -> PromiseOrValue<()> {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_CALLBACK_POST_REDELEGATE_INVALID",
+        );
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => self
+                .internal_forward_delegation(sender_id, to_id, amount)
+                .into(),
+            PromiseResult::Failed => {
+                self.internal_revert_redelegate(&sender_id, &from_id, &to_id, amount.0);
+                PromiseOrValue::Value(())
+            }
+        }
+    }
+
+    /// Handles undelegation rejected by the owner DAO, e.g. when it ran out of gas.
+    /// Restores the delegation, unless the tokens were withdrawn or the storage deposit no longer
+    /// covers it, and records the failure for `get_undelegation_error` at the user's storage
+    /// expense. The cooldown started by the undelegation stays.
+    #[private]
+    pub fn on_undelegate(&mut self, sender_id: AccountId, delegate_id: AccountId, amount: U128) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_CALLBACK_POST_UNDELEGATE_INVALID",
+        );
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {}
+            PromiseResult::Failed => {
+                let restored = self.internal_restore_delegation(&sender_id, &delegate_id, amount.0);
+                self.internal_save_undelegation_error(
+                    &sender_id,
+                    &UndelegationError {
+                        delegate_id,
+                        amount,
+                        timestamp: U64(env::block_timestamp()),
+                        restored,
+                    },
+                );
+            }
+        };
     }
 
     /// Withdraw non delegated tokens back to the user's account.
@@ -123,17 +726,62 @@ impl Contract {
             None,
             self.vote_token_id.clone(),
             1,
-            GAS_FOR_FT_TRANSFER,
+            self.gas_config.ft_transfer,
         )
         .then(ext_self::exchange_callback_post_withdraw(
             sender_id,
             amount,
             env::current_account_id(),
             0,
-            GAS_FOR_FT_TRANSFER,
+            self.gas_config.ft_transfer,
+        ))
+    }
+
+    /// Burn given amount of tokens delegated to self and receive pro-rata share
+    /// of the DAO's ragequit tokens. Burned tokens stay locked in this contract.
+    /// If the DAO rejects the ragequit or pays out nothing, the tokens and delegation are restored.
+    pub fn ragequit(&mut self, amount: U128) -> Promise {
+        let sender_id = env::predecessor_account_id();
+        self.internal_burn(&sender_id, amount.0);
+        ext_sputnik::ragequit(
+            sender_id.clone(),
+            amount,
+            self.owner_id.clone(),
+            0,
+            self.gas_config.ragequit,
+        )
+        .then(ext_self::exchange_callback_post_ragequit(
+            sender_id,
+            amount,
+            env::current_account_id(),
+            0,
+            self.gas_config.ft_transfer,
         ))
     }
 
+    #[private]
+    pub fn exchange_callback_post_ragequit(&mut self, sender_id: AccountId, amount: U128) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_CALLBACK_POST_RAGEQUIT_INVALID",
+        );
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            // The DAO restored the delegation if none of the payouts succeeded.
+            PromiseResult::Successful(value)
+                if matches!(near_sdk::serde_json::from_slice(&value), Ok(false)) =>
+            {
+                self.internal_unburn(&sender_id, amount.0);
+            }
+            PromiseResult::Successful(_) => {}
+            PromiseResult::Failed => {
+                // This reverts the changes from ragequit function.
+                self.internal_unburn(&sender_id, amount.0);
+            }
+        };
+    }
+
     #[private]
     pub fn exchange_callback_post_withdraw(&mut self, sender_id: AccountId, amount: U128) {
         assert_eq!(
@@ -150,8 +798,41 @@ impl Contract {
             }
         };
     }
+
+    /// Refunds the storage deposit once the tokens of the forcibly unregistered user were
+    /// transferred back. If the transfer failed, the user is registered again with the tokens.
+    #[private]
+    pub fn exchange_callback_post_unregister(
+        &mut self,
+        sender_id: AccountId,
+        near_amount: U128,
+        amount: U128,
+    ) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_CALLBACK_POST_UNREGISTER_INVALID",
+        );
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Successful(_) => {
+                Promise::new(sender_id).transfer(near_amount.0);
+            }
+            PromiseResult::Failed => {
+                if self.internal_get_user_opt(&sender_id).is_some() {
+                    // Registered again in the meantime.
+                    Promise::new(sender_id.clone()).transfer(near_amount.0);
+                } else {
+                    // Registration in the owner DAO was kept.
+                    self.save_user(&sender_id, User::new(near_amount.0));
+                }
+                self.internal_deposit(&sender_id, amount.0);
+            }
+        };
+    }
 }
 
+#[cfg(not(feature = "client"))]
 #[near_bindgen]
 impl FungibleTokenReceiver for Contract {
     fn ft_on_transfer(
@@ -195,6 +876,20 @@ mod tests {
         contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
         assert_eq!(contract.ft_total_supply().0, to_yocto("100"));
         assert_eq!(contract.ft_balance_of(accounts(2)).0, to_yocto("100"));
+        assert_eq!(
+            contract.get_info(),
+            StakingInfo {
+                owner_id: accounts(0),
+                unstake_period: U64(period),
+                tokens: vec![(accounts(1), 1)],
+                total_supply: U128(to_yocto("100")),
+                total_voting_power: U128(to_yocto("100")),
+                total_delegated: U128(0),
+                version: contract.version(),
+            }
+        );
+        assert!(contract.version().starts_with("1.0.0"));
+        assert_eq!(contract.get_gas_config(), GasConfig::default());
         testing_env!(context.predecessor_account_id(accounts(2)).build());
         contract.withdraw(U128(to_yocto("50")));
         assert_eq!(contract.ft_total_supply().0, to_yocto("50"));
@@ -207,4 +902,696 @@ mod tests {
         assert_eq!(user.delegated_amount(), 0);
         assert_eq!(user.next_action_timestamp, U64(period));
     }
+
+    #[test]
+    fn test_ragequit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(2), U128(to_yocto("10")));
+        contract.ragequit(U128(to_yocto("4")));
+        assert_eq!(contract.ft_total_supply().0, to_yocto("96"));
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), to_yocto("6"));
+        assert_eq!(user.vote_amount.0, to_yocto("96"));
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.exchange_callback_post_ragequit(accounts(2), U128(to_yocto("4")));
+        assert_eq!(contract.ft_total_supply().0, to_yocto("100"));
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), to_yocto("10"));
+
+        // DAO paid out nothing.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.ragequit(U128(to_yocto("4")));
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"false".to_vec())],
+        );
+        contract.exchange_callback_post_ragequit(accounts(2), U128(to_yocto("4")));
+        assert_eq!(contract.ft_total_supply().0, to_yocto("100"));
+    }
+
+    #[test]
+    fn test_undelegate_failed() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        let storage_used = contract.get_user(accounts(2)).storage_used.0;
+        contract.undelegate(accounts(3), U128(to_yocto("10")));
+        assert_eq!(contract.voting_power_of(accounts(3)).total, U128(0));
+
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .block_timestamp(5)
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_undelegate(accounts(2), accounts(3), U128(to_yocto("10")));
+        assert_eq!(
+            contract.get_user(accounts(2)).delegated_amount(),
+            to_yocto("10")
+        );
+        assert_eq!(
+            contract.voting_power_of(accounts(3)).total,
+            U128(to_yocto("10"))
+        );
+        assert_eq!(
+            contract.get_undelegation_error(accounts(2)),
+            Some(UndelegationError {
+                delegate_id: accounts(3),
+                amount: U128(to_yocto("10")),
+                timestamp: U64(5),
+                restored: true,
+            })
+        );
+        // The error record is charged to the user.
+        assert!(contract.get_user(accounts(2)).storage_used.0 > storage_used);
+    }
+
+    #[test]
+    fn test_undelegate_failed_without_storage() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        // Storage used by a single delegation, measured on another user.
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(4)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(4), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        let storage_used = contract.get_user(accounts(4)).storage_used.0 as Balance;
+
+        testing_env!(context
+            .attached_deposit(storage_used * env::storage_byte_cost())
+            .build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        contract.undelegate(accounts(3), U128(to_yocto("10")));
+        // Storage released by the undelegation is used by another delegation.
+        testing_env!(context.block_timestamp(2000).build());
+        contract.delegate(accounts(5), U128(to_yocto("10")));
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_undelegate(accounts(2), accounts(3), U128(to_yocto("10")));
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), to_yocto("10"));
+        assert_eq!(user.storage_used.0 as Balance, storage_used);
+        // Only the delegation of the other user.
+        assert_eq!(
+            contract.voting_power_of(accounts(3)).total,
+            U128(to_yocto("10"))
+        );
+        assert_eq!(contract.get_undelegation_error(accounts(2)), None);
+    }
+
+    #[test]
+    fn test_storage_unregister_force() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+
+        testing_env!(context.attached_deposit(1).build());
+        assert!(!contract.storage_unregister(Some(true)));
+        assert_eq!(contract.get_user(accounts(2)).delegated_amount(), 0);
+        assert_eq!(contract.voting_power_of(accounts(3)).total, U128(0));
+
+        testing_env!(context.block_timestamp(1000).build());
+        assert!(contract.storage_unregister(Some(true)));
+        assert!(contract.storage_balance_of(accounts(2)).is_none());
+        assert_eq!(contract.ft_total_supply(), U128(0));
+
+        testing_env!(
+            context
+                .predecessor_account_id(accounts(0))
+                .attached_deposit(0)
+                .build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.exchange_callback_post_unregister(
+            accounts(2),
+            U128(to_yocto("1")),
+            U128(to_yocto("100")),
+        );
+        assert_eq!(contract.ft_balance_of(accounts(2)), U128(to_yocto("100")));
+        assert_eq!(contract.ft_total_supply(), U128(to_yocto("100")));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_STORAGE_UNREGISTER_NOT_EMPTY")]
+    fn test_storage_unregister_not_empty() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(0)
+            .build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(1)
+            .build());
+        contract.storage_unregister(None);
+    }
+
+    #[test]
+    fn test_delegate_batch() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate_batch(vec![
+            (accounts(3), U128(to_yocto("10"))),
+            (accounts(4), U128(to_yocto("20"))),
+        ]);
+        assert_eq!(
+            contract.get_user(accounts(2)).delegated_amount(),
+            to_yocto("30")
+        );
+        assert_eq!(
+            contract.voting_power_of(accounts(4)).total,
+            U128(to_yocto("20"))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_AMOUNT")]
+    fn test_delegate_batch_not_enough() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate_batch(vec![
+            (accounts(3), U128(to_yocto("60"))),
+            (accounts(4), U128(to_yocto("60"))),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_DELEGATIONS")]
+    fn test_delegate_batch_empty() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        contract.delegate_batch(vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOO_MANY_DELEGATIONS")]
+    fn test_delegate_batch_too_many() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        contract.delegate_batch(vec![(accounts(3), U128(1)); MAX_DELEGATION_BATCH + 1]);
+    }
+
+    #[test]
+    fn test_undelegate_all() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        contract.delegate(accounts(4), U128(to_yocto("20")));
+        contract.delegate(accounts(3), U128(to_yocto("5")));
+
+        testing_env!(context.build());
+        contract.undelegate_all_from(accounts(3));
+        assert_eq!(contract.voting_power_of(accounts(3)).total, U128(0));
+        assert_eq!(
+            contract.get_user(accounts(2)).delegated_amount(),
+            to_yocto("20")
+        );
+
+        contract.undelegate_all();
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), 0);
+        assert_eq!(user.next_action_timestamp, U64(1000));
+        assert_eq!(contract.voting_power_of(accounts(4)).total, U128(0));
+    }
+
+    #[test]
+    fn test_redelegate() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        contract.redelegate(accounts(3), accounts(4), U128(to_yocto("6")));
+        assert_eq!(
+            contract.voting_power_of(accounts(3)).total,
+            U128(to_yocto("4"))
+        );
+        assert_eq!(
+            contract.voting_power_of(accounts(4)).total,
+            U128(to_yocto("6"))
+        );
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), to_yocto("10"));
+        assert_eq!(user.next_action_timestamp, U64(1000));
+
+        // Undelegation rejected by the DAO moves the votes back.
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_redelegate(accounts(2), accounts(3), accounts(4), U128(to_yocto("6")));
+        assert_eq!(
+            contract.voting_power_of(accounts(3)).total,
+            U128(to_yocto("10"))
+        );
+        assert_eq!(contract.voting_power_of(accounts(4)).total, U128(0));
+        assert_eq!(
+            contract.get_user(accounts(2)).next_action_timestamp,
+            U64(1000)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_TIME_PASSED")]
+    fn test_redelegate_cooldown() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        contract.redelegate(accounts(3), accounts(4), U128(to_yocto("6")));
+        testing_env!(context.block_timestamp(999).build());
+        contract.redelegate(accounts(4), accounts(3), U128(to_yocto("6")));
+    }
+
+    #[test]
+    fn test_set_owner() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        contract.set_owner(accounts(5));
+        assert_eq!(contract.get_info().owner_id, accounts(5));
+        assert_eq!(contract.get_pending_owner(), None);
+    }
+
+    fn setup_owner_transfer(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        contract.delegate(accounts(4), U128(to_yocto("5")));
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract.set_owner(accounts(5));
+        contract
+    }
+
+    #[test]
+    fn test_set_owner_forward_totals() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_owner_transfer(&mut context);
+        assert_eq!(contract.get_info().owner_id, accounts(0));
+
+        let forwarded = |context: &mut VMContextBuilder, results: Vec<PromiseResult>| {
+            testing_env!(
+                context.predecessor_account_id(accounts(0)).build(),
+                near_sdk::VMConfig::test(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                results,
+            );
+        };
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.forward_delegated_totals(10);
+        assert!(contract.get_pending_owner().unwrap().forwarding);
+        forwarded(
+            &mut context,
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed],
+        );
+        assert!(!contract.on_forward_delegated_totals(U64(0), U64(2)));
+        assert_eq!(
+            contract.get_pending_owner(),
+            Some(PendingOwner {
+                owner_id: accounts(5),
+                forwarded: U64(0),
+                forwarding: false,
+            })
+        );
+        assert_eq!(contract.get_info().owner_id, accounts(0));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.forward_delegated_totals(1);
+        forwarded(&mut context, vec![PromiseResult::Successful(vec![])]);
+        assert!(contract.on_forward_delegated_totals(U64(0), U64(1)));
+        assert_eq!(contract.get_pending_owner().unwrap().forwarded, U64(1));
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.forward_delegated_totals(10);
+        forwarded(&mut context, vec![PromiseResult::Successful(vec![])]);
+        assert!(contract.on_forward_delegated_totals(U64(1), U64(1)));
+        assert_eq!(contract.get_info().owner_id, accounts(5));
+        assert_eq!(contract.get_pending_owner(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OWNER_TRANSFER_PENDING")]
+    fn test_delegate_during_owner_transfer() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_owner_transfer(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("1")));
+    }
+
+    #[test]
+    fn test_sync_delegated_totals() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        contract.users.insert(
+            &accounts(2),
+            &VersionedUser::Default(UserV1 {
+                storage_used: U64(User::min_storage()),
+                near_amount: U128(to_yocto("1")),
+                vote_amount: U128(to_yocto("100")),
+                next_action_timestamp: U64(0),
+                delegated_amounts: vec![(accounts(3), U128(to_yocto("10")))],
+            }),
+        );
+        assert_eq!(contract.voting_power_of(accounts(3)).total, U128(0));
+        assert_eq!(contract.get_info().total_delegated, U128(0));
+        contract.sync_delegated_totals(vec![accounts(2), accounts(4)]);
+        assert_eq!(
+            contract.voting_power_of(accounts(3)).total,
+            U128(to_yocto("10"))
+        );
+        assert_eq!(contract.get_info().total_delegated, U128(to_yocto("10")));
+        // Already synced.
+        contract.sync_delegated_totals(vec![accounts(2)]);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.undelegate(accounts(3), U128(to_yocto("4")));
+        assert_eq!(
+            contract.voting_power_of(accounts(3)).total,
+            U128(to_yocto("6"))
+        );
+        assert_eq!(contract.get_info().total_delegated, U128(to_yocto("6")));
+    }
+
+    #[test]
+    fn test_list_users_stored_before_user_ids() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        for account_id in [accounts(2), accounts(3)] {
+            contract
+                .users
+                .insert(&account_id, &VersionedUser::V2(User::new(to_yocto("1"))));
+        }
+        assert_eq!(contract.get_staker_count(), U64(0));
+        contract.internal_deposit(&accounts(2), 100);
+        contract.sync_delegated_totals(vec![accounts(3), accounts(4)]);
+        assert_eq!(contract.get_staker_count(), U64(2));
+        assert_eq!(contract.get_users(0, 10).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_OWNER")]
+    fn test_set_owner_not_owner() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.set_owner(accounts(2));
+    }
+
+    #[test]
+    fn test_delegate_retry() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        let near_amount = contract.get_user(accounts(2)).near_amount.0;
+
+        let failed = |context: &mut VMContextBuilder| {
+            testing_env!(
+                context.predecessor_account_id(accounts(0)).build(),
+                near_sdk::VMConfig::test(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                vec![PromiseResult::Failed],
+            );
+        };
+        // First failure registers the delegate, paid by the delegator, and retries.
+        failed(&mut context);
+        contract.on_delegate(accounts(2), accounts(3), U128(to_yocto("10")), false);
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), to_yocto("10"));
+        assert!(user.near_amount.0 < near_amount);
+
+        // Delegate was already registered, the DAO refunded the deposit.
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"false".to_vec())],
+        );
+        contract.on_register_delegate(accounts(2), accounts(3), U128(to_yocto("10")));
+        assert_eq!(contract.get_user(accounts(2)).near_amount.0, near_amount);
+
+        // Failed retry reverts the delegation without delaying the next action.
+        failed(&mut context);
+        contract.on_delegate(accounts(2), accounts(3), U128(to_yocto("10")), true);
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), 0);
+        assert_eq!(user.next_action_timestamp, U64(0));
+        assert_eq!(contract.voting_power_of(accounts(3)).total, U128(0));
+
+        // Failed registration refunds the deposit and reverts the delegation.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(3), U128(to_yocto("10")));
+        failed(&mut context);
+        contract.on_delegate(accounts(2), accounts(3), U128(to_yocto("10")), false);
+        failed(&mut context);
+        contract.on_register_delegate(accounts(2), accounts(3), U128(to_yocto("10")));
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.delegated_amount(), 0);
+        assert_eq!(user.near_amount.0, near_amount);
+    }
+
+    #[test]
+    fn test_user_json() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        let user = near_sdk::serde_json::to_value(contract.get_user(accounts(2))).unwrap();
+        assert_eq!(
+            user["storage_used"],
+            User::min_storage().to_string().as_str()
+        );
+        assert_eq!(user["near_amount"], to_yocto("1").to_string().as_str());
+        assert_eq!(user["next_action_timestamp"], "0");
+        let users = contract.get_users(0, 10);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].0, accounts(2));
+        assert_eq!(contract.get_staker_count(), U64(1));
+    }
+
+    #[test]
+    fn test_upgrade_user() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        // User record in the layout before `deposit_timestamp`.
+        let old_user = VersionedUser::Default(UserV1 {
+            storage_used: U64(User::min_storage()),
+            near_amount: U128(to_yocto("1")),
+            vote_amount: U128(to_yocto("10")),
+            next_action_timestamp: U64(5),
+            delegated_amounts: vec![(accounts(3), U128(to_yocto("4")))],
+        });
+        let old_user: VersionedUser =
+            BorshDeserialize::try_from_slice(&old_user.try_to_vec().unwrap()).unwrap();
+        contract.users.insert(&accounts(2), &old_user);
+
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.vote_amount, U128(to_yocto("10")));
+        assert_eq!(user.next_action_timestamp, U64(5));
+        assert_eq!(user.delegated_amount(), to_yocto("4"));
+        assert_eq!(user.deposit_timestamp, U64(0));
+
+        // Saved in the latest layout with the next deposit.
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(100)
+            .build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("1")), "".to_string());
+        assert!(matches!(
+            contract.users.get(&accounts(2)),
+            Some(VersionedUser::V2(_))
+        ));
+        let user = contract.get_user(accounts(2));
+        assert_eq!(user.vote_amount, U128(to_yocto("11")));
+        assert_eq!(user.deposit_timestamp, U64(100));
+    }
+
+    #[test]
+    fn test_migrate() {
+        /// State layout of the previous release.
+        #[derive(BorshSerialize)]
+        struct BaselineContract {
+            owner_id: AccountId,
+            vote_token_id: AccountId,
+            users: LookupMap<AccountId, VersionedUser>,
+            total_amount: Balance,
+            unstake_period: Duration,
+        }
+
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+        env::state_write(&BaselineContract {
+            owner_id: accounts(0),
+            vote_token_id: accounts(1),
+            users: LookupMap::new(StorageKeys::Users),
+            total_amount: to_yocto("10"),
+            unstake_period: 1000,
+        });
+        let contract = Contract::migrate();
+        assert_eq!(contract.get_info().owner_id, accounts(0));
+        assert_eq!(contract.ft_total_supply(), U128(to_yocto("10")));
+        assert_eq!(contract.voting_power_of(accounts(2)).total, U128(0));
+    }
+
+    #[test]
+    fn test_voting_power_of() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), U64(1000));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.storage_deposit(Some(accounts(2)), None);
+        contract.storage_deposit(Some(accounts(3)), None);
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.ft_on_transfer(accounts(2), U128(to_yocto("100")), "".to_string());
+        contract.ft_on_transfer(accounts(3), U128(to_yocto("100")), "".to_string());
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.delegate(accounts(2), U128(to_yocto("10")));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.delegate(accounts(2), U128(to_yocto("5")));
+        assert_eq!(
+            contract.voting_power_of(accounts(2)),
+            VotingPower {
+                total: U128(to_yocto("15")),
+                self_held: U128(to_yocto("10")),
+                delegated: U128(to_yocto("5")),
+                tokens: vec![(accounts(1), U128(to_yocto("15")))],
+            }
+        );
+        assert_eq!(
+            contract.delegators_of(accounts(2), 0, 10),
+            vec![
+                DelegatorOutput {
+                    account_id: accounts(2),
+                    amount: U128(to_yocto("10")),
+                    timestamp: U64(0),
+                },
+                DelegatorOutput {
+                    account_id: accounts(3),
+                    amount: U128(to_yocto("5")),
+                    timestamp: U64(0),
+                }
+            ]
+        );
+        contract.undelegate(accounts(2), U128(to_yocto("5")));
+        assert_eq!(contract.delegators_of(accounts(2), 0, 10).len(), 1);
+        assert_eq!(
+            contract.voting_power_of(accounts(2)).total,
+            U128(to_yocto("10"))
+        );
+        assert_eq!(contract.voting_power_of(accounts(3)).total, U128(0));
+    }
 }
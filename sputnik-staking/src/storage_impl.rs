@@ -8,6 +8,7 @@ use near_sdk::{assert_one_yocto, log};
 use crate::*;
 
 /// Implements users storage management for the pool.
+#[cfg(not(feature = "client"))]
 #[near_bindgen]
 impl StorageManagement for Contract {
     #[payable]
@@ -58,20 +59,56 @@ impl StorageManagement for Contract {
             .unwrap()
     }
 
-    #[allow(unused_variables)]
+    /// Unregisters the caller and refunds the storage deposit. Staked tokens must be withdrawn
+    /// first, unless `force` is set: then the tokens are transferred back, and the deposit is
+    /// refunded once the transfer settles. Tokens are only returned after the unstake period,
+    /// so with `force` set, delegations are undelegated first and `false` is returned.
+    /// Call again once the unstake period has passed.
     #[payable]
     fn storage_unregister(&mut self, force: Option<bool>) -> bool {
         assert_one_yocto();
         let account_id = env::predecessor_account_id();
-        if let Some(user) = self.internal_get_user_opt(&account_id) {
-            // TODO: figure out force option logic.
-            assert!(user.vote_amount.0 > 0, "ERR_STORAGE_UNREGISTER_NOT_EMPTY");
-            self.users.remove(&account_id);
-            Promise::new(account_id.clone()).transfer(user.near_amount.0);
-            true
+        let user = match self.internal_get_user_opt(&account_id) {
+            Some(user) => user,
+            None => return false,
+        };
+        if force.unwrap_or(false) {
+            if !user.delegated_amounts.is_empty() {
+                self.internal_undelegate_all(account_id, None);
+                return false;
+            }
+            assert!(
+                env::block_timestamp() >= user.next_action_timestamp.0,
+                "ERR_NOT_ENOUGH_TIME_PASSED"
+            );
+        } else {
+            assert_eq!(user.vote_amount.0, 0, "ERR_STORAGE_UNREGISTER_NOT_EMPTY");
+        }
+        self.users.remove(&account_id);
+        self.user_ids.remove(&account_id);
+        self.undelegation_errors.remove(&account_id);
+        if user.vote_amount.0 > 0 {
+            self.total_amount -= user.vote_amount.0;
+            ext_fungible_token::ft_transfer(
+                account_id.clone(),
+                user.vote_amount,
+                None,
+                self.vote_token_id.clone(),
+                1,
+                self.gas_config.ft_transfer,
+            )
+            .then(ext_self::exchange_callback_post_unregister(
+                account_id,
+                user.near_amount,
+                user.vote_amount,
+                env::current_account_id(),
+                0,
+                self.gas_config.ft_transfer,
+            ));
         } else {
-            false
+            Promise::new(account_id).transfer(user.near_amount.0);
         }
+        true
     }
 
     fn storage_balance_bounds(&self) -> StorageBalanceBounds {
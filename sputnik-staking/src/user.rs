@@ -1,4 +1,5 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
 use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, AccountId, Balance, Duration, StorageUsage};
@@ -8,6 +9,15 @@ use crate::*;
 const U64_LEN: StorageUsage = 8;
 const U128_LEN: StorageUsage = 16;
 const ACCOUNT_MAX_LENGTH: StorageUsage = 64;
+/// Storage of a delegate registration in the owner DAO: the delegated amount and the entry in
+/// its index of delegates, which stores the account id and its index twice.
+const REGISTER_DELEGATION_STORAGE: StorageUsage = U128_LEN + 2 * (ACCOUNT_MAX_LENGTH + U64_LEN);
+/// Storage of a single entry in the delegators index of a delegate.
+/// Covers the delegator id stored as key and in the keys vector, the map prefix and the record.
+const DELEGATOR_STORAGE: StorageUsage = 4 * ACCOUNT_MAX_LENGTH + 3 * U64_LEN + U128_LEN;
+/// Storage of the last undelegation error of a user: the user and delegate ids, the amount,
+/// the timestamp and the restored flag.
+const UNDELEGATION_ERROR_STORAGE: StorageUsage = 2 * ACCOUNT_MAX_LENGTH + U128_LEN + U64_LEN + 1;
 
 /// User data.
 /// Recording deposited voting tokens, storage used and delegations for voting.
@@ -17,7 +27,7 @@ const ACCOUNT_MAX_LENGTH: StorageUsage = 64;
 #[serde(crate = "near_sdk::serde")]
 pub struct User {
     /// Total amount of storage used by this user struct.
-    pub storage_used: StorageUsage,
+    pub storage_used: U64,
     /// Amount of $NEAR to cover storage.
     pub near_amount: U128,
     /// Amount of staked token deposited.
@@ -26,36 +36,116 @@ pub struct User {
     pub next_action_timestamp: U64,
     /// List of delegations to other accounts.
     pub delegated_amounts: Vec<(AccountId, U128)>,
+    /// Timestamp of the last deposit of staked token. Zero for users stored before it was
+    /// recorded, until their next deposit.
+    pub deposit_timestamp: U64,
 }
 
-#[derive(BorshSerialize, BorshDeserialize)]
+/// User data before `deposit_timestamp` was added.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UserV1 {
+    pub storage_used: U64,
+    pub near_amount: U128,
+    pub vote_amount: U128,
+    pub next_action_timestamp: U64,
+    pub delegated_amounts: Vec<(AccountId, U128)>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
 pub enum VersionedUser {
-    Default(User),
+    Default(UserV1),
+    V2(User),
+}
+
+impl VersionedUser {
+    /// Converts either version into the latest user data.
+    /// The timestamp of converted users is covered by the storage reserved for their account id.
+    pub fn upgrade(self) -> User {
+        match self {
+            VersionedUser::Default(user) => User {
+                storage_used: user.storage_used,
+                near_amount: user.near_amount,
+                vote_amount: user.vote_amount,
+                next_action_timestamp: user.next_action_timestamp,
+                delegated_amounts: user.delegated_amounts,
+                deposit_timestamp: U64(0),
+            },
+            VersionedUser::V2(user) => user,
+        }
+    }
+}
+
+/// Tokens delegated by a single delegator to a delegate.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Delegator {
+    pub amount: Balance,
+    /// Timestamp of the last delegation.
+    pub timestamp: u64,
+}
+
+/// Account delegating to a delegate, returned by `delegators_of`.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelegatorOutput {
+    pub account_id: AccountId,
+    pub amount: U128,
+    /// Timestamp of the last delegation.
+    pub timestamp: U64,
+}
+
+/// Undelegation rejected by the owner DAO, returned by `get_undelegation_error`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UndelegationError {
+    pub delegate_id: AccountId,
+    pub amount: U128,
+    pub timestamp: U64,
+    /// False if the delegation couldn't be restored because the tokens were withdrawn.
+    pub restored: bool,
+}
+
+/// Voting power of an account with its breakdown.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VotingPower {
+    /// Total votes delegated to the account.
+    pub total: U128,
+    /// Votes the account delegated to itself.
+    pub self_held: U128,
+    /// Votes delegated to the account by others.
+    pub delegated: U128,
+    /// Contribution of each staked token to the total.
+    pub tokens: Vec<(AccountId, U128)>,
 }
 
 impl User {
     pub fn new(near_amount: Balance) -> Self {
         Self {
-            storage_used: Self::min_storage(),
+            storage_used: U64(Self::min_storage()),
             near_amount: U128(near_amount),
             vote_amount: U128(0),
             delegated_amounts: vec![],
             next_action_timestamp: 0.into(),
+            deposit_timestamp: 0.into(),
         }
     }
 
     /// Minimum storage with empty delegations in bytes.
-    /// This includes u128 stored in DAO for delegations to this user.
+    /// This includes the registration in DAO for delegations to this user.
     /// They are deposited on internal_register and removed on internal_unregister.
+    /// Also includes the entry in the index of user ids.
     pub fn min_storage() -> StorageUsage {
-        ACCOUNT_MAX_LENGTH + 2 * U64_LEN + 4 * U128_LEN
+        3 * ACCOUNT_MAX_LENGTH + 4 * U64_LEN + 3 * U128_LEN + REGISTER_DELEGATION_STORAGE
     }
 
-    fn assert_storage(&self) {
-        assert!(
-            (self.storage_used as Balance) * env::storage_byte_cost() <= self.near_amount.0,
-            "ERR_NOT_ENOUGH_STORAGE"
-        );
+    pub(crate) fn has_storage(&self) -> bool {
+        (self.storage_used.0 as Balance) * env::storage_byte_cost() <= self.near_amount.0
+    }
+
+    pub(crate) fn assert_storage(&self) {
+        assert!(self.has_storage(), "ERR_NOT_ENOUGH_STORAGE");
     }
 
     pub(crate) fn delegated_amount(&self) -> Balance {
@@ -75,7 +165,36 @@ impl User {
             env::block_timestamp() >= self.next_action_timestamp.0,
             "ERR_NOT_ENOUGH_TIME_PASSED"
         );
-        self.storage_used += delegate_id.as_bytes().len() as StorageUsage + U128_LEN;
+        self.add_delegation(delegate_id, amount);
+    }
+
+    /// Move given amount from one delegate to another. Like undelegation, needs the cooldown
+    /// to have passed and starts a new one, so votes can't be moved between proposals.
+    /// Fails if `from_id` not found or not enough amount delegated.
+    pub fn redelegate(
+        &mut self,
+        from_id: &AccountId,
+        to_id: AccountId,
+        amount: Balance,
+        undelegation_period: Duration,
+    ) {
+        assert!(
+            env::block_timestamp() >= self.next_action_timestamp.0,
+            "ERR_NOT_ENOUGH_TIME_PASSED"
+        );
+        self.move_delegation(from_id, to_id, amount);
+        self.next_action_timestamp = (env::block_timestamp() + undelegation_period).into();
+    }
+
+    /// Move given amount from one delegate to another without checking or updating the timestamp.
+    /// Fails if `from_id` not found or not enough amount delegated.
+    pub fn move_delegation(&mut self, from_id: &AccountId, to_id: AccountId, amount: Balance) {
+        self.remove_delegation(from_id, amount);
+        self.add_delegation(to_id, amount);
+    }
+
+    fn add_delegation(&mut self, delegate_id: AccountId, amount: Balance) {
+        self.storage_used.0 += delegate_id.as_bytes().len() as StorageUsage + U128_LEN;
         self.delegated_amounts.push((delegate_id, U128(amount)));
         self.assert_storage();
     }
@@ -88,6 +207,13 @@ impl User {
         amount: Balance,
         undelegation_period: Duration,
     ) {
+        self.remove_delegation(delegate_id, amount);
+        self.next_action_timestamp = (env::block_timestamp() + undelegation_period).into();
+    }
+
+    /// Remove given amount from delegates without updating the timestamp.
+    /// Fails if delegate not found or not enough amount delegated.
+    pub fn remove_delegation(&mut self, delegate_id: &AccountId, amount: Balance) {
         let f = self
             .delegated_amounts
             .iter()
@@ -98,11 +224,67 @@ impl User {
         assert!(element.1 >= amount, "ERR_NOT_ENOUGH_AMOUNT");
         if element.1 == amount {
             self.delegated_amounts.remove(element.0);
-            self.storage_used -= delegate_id.as_bytes().len() as StorageUsage + U128_LEN;
+            self.storage_used.0 -= delegate_id.as_bytes().len() as StorageUsage + U128_LEN;
         } else {
             (self.delegated_amounts[element.0].1).0 -= amount;
         }
-        self.next_action_timestamp = (env::block_timestamp() + undelegation_period).into();
+    }
+
+    /// Burn given amount of tokens delegated by this account to itself.
+    /// Fails if not enough amount delegated to self.
+    pub fn burn(&mut self, account_id: &AccountId, amount: Balance) {
+        let index = self
+            .delegated_amounts
+            .iter()
+            .position(|(delegate_id, _)| delegate_id == account_id)
+            .expect("ERR_NO_DELEGATE");
+        let delegated = self.delegated_amounts[index].1 .0;
+        assert!(delegated >= amount, "ERR_NOT_ENOUGH_AMOUNT");
+        if delegated == amount {
+            self.delegated_amounts.remove(index);
+            self.storage_used.0 -= account_id.as_bytes().len() as StorageUsage + U128_LEN;
+        } else {
+            self.delegated_amounts[index].1 .0 -= amount;
+        }
+        self.vote_amount.0 -= amount;
+    }
+
+    /// Restore tokens burned by `burn`.
+    pub fn unburn(&mut self, account_id: &AccountId, amount: Balance) {
+        match self
+            .delegated_amounts
+            .iter()
+            .position(|(delegate_id, _)| delegate_id == account_id)
+        {
+            Some(index) => self.delegated_amounts[index].1 .0 += amount,
+            None => {
+                self.storage_used.0 += account_id.as_bytes().len() as StorageUsage + U128_LEN;
+                self.delegated_amounts
+                    .push((account_id.clone(), U128(amount)));
+            }
+        }
+        self.vote_amount.0 += amount;
+    }
+
+    /// Restore delegation removed by `undelegate`, without checking or updating the timestamp.
+    /// Returns false if not enough tokens are left to delegate.
+    pub fn restore_delegation(&mut self, delegate_id: &AccountId, amount: Balance) -> bool {
+        if self.delegated_amount() + amount > self.vote_amount.0 {
+            return false;
+        }
+        match self
+            .delegated_amounts
+            .iter()
+            .position(|(account_id, _)| account_id == delegate_id)
+        {
+            Some(index) => self.delegated_amounts[index].1 .0 += amount,
+            None => {
+                self.storage_used.0 += delegate_id.as_bytes().len() as StorageUsage + U128_LEN;
+                self.delegated_amounts
+                    .push((delegate_id.clone(), U128(amount)));
+            }
+        }
+        true
     }
 
     /// Withdraw the amount.
@@ -122,11 +304,12 @@ impl User {
     /// Deposit given amount of vote tokens.
     pub fn deposit(&mut self, amount: Balance) {
         self.vote_amount.0 += amount;
+        self.deposit_timestamp = U64(env::block_timestamp());
     }
 
     /// Returns amount in NEAR that is available for storage.
     pub fn storage_available(&self) -> Balance {
-        self.near_amount.0 - self.storage_used as Balance * env::storage_byte_cost()
+        self.near_amount.0 - self.storage_used.0 as Balance * env::storage_byte_cost()
     }
 }
 
@@ -136,15 +319,42 @@ impl Contract {
     }
 
     pub fn internal_get_user_opt(&self, account_id: &AccountId) -> Option<User> {
-        self.users
-            .get(account_id)
-            .map(|versioned_user| match versioned_user {
-                VersionedUser::Default(user) => user,
-            })
+        self.users.get(account_id).map(VersionedUser::upgrade)
     }
 
+    /// Saves given user. Delegations of users stored before delegated totals were tracked are
+    /// added to the totals on their first save, which must come before any delegation change.
+    /// Users stored before `user_ids` was added are listed from their first save.
     pub fn save_user(&mut self, account_id: &AccountId, user: User) {
-        self.users.insert(account_id, &VersionedUser::Default(user));
+        if let Some(VersionedUser::Default(old_user)) = self.users.get(account_id) {
+            assert!(self.pending_owner.is_none(), "ERR_OWNER_TRANSFER_PENDING");
+            for (delegate_id, amount) in old_user.delegated_amounts {
+                self.internal_add_delegated_total(&delegate_id, amount.0);
+            }
+        }
+        self.users.insert(account_id, &VersionedUser::V2(user));
+        self.user_ids.insert(account_id);
+    }
+
+    /// Adds delegations of given user stored before delegated totals were tracked to the totals
+    /// and lists the user in `user_ids`, see `save_user`. Called before changing the user's
+    /// delegations.
+    pub fn internal_sync_user(&mut self, account_id: &AccountId) {
+        match self.users.get(account_id) {
+            Some(VersionedUser::Default(user)) => {
+                self.save_user(account_id, VersionedUser::Default(user).upgrade());
+            }
+            Some(_) => {
+                self.user_ids.insert(account_id);
+            }
+            None => {}
+        }
+    }
+
+    /// Panics while an owner transfer is pending, as the delegated totals being forwarded to the
+    /// new owner DAO must not change.
+    fn assert_no_owner_transfer(&self) {
+        assert!(self.pending_owner.is_none(), "ERR_OWNER_TRANSFER_PENDING");
     }
 
     /// Internal register new user.
@@ -154,8 +364,8 @@ impl Contract {
         ext_sputnik::register_delegation(
             sender_id.clone(),
             self.owner_id.clone(),
-            (U128_LEN as Balance) * env::storage_byte_cost(),
-            GAS_FOR_REGISTER,
+            (REGISTER_DELEGATION_STORAGE as Balance) * env::storage_byte_cost(),
+            self.gas_config.register,
         );
     }
 
@@ -184,9 +394,41 @@ impl Contract {
         delegate_id: AccountId,
         amount: Balance,
     ) {
+        self.assert_no_owner_transfer();
+        self.internal_sync_user(&sender_id);
         let mut sender = self.internal_get_user(&sender_id);
         sender.delegate(delegate_id.clone(), amount);
+        self.internal_add_delegator(&mut sender, &sender_id, &delegate_id, amount);
+        sender.assert_storage();
         self.save_user(&sender_id, sender);
+        self.internal_add_delegated_total(&delegate_id, amount);
+    }
+
+    /// Forwards delegation recorded by `internal_delegate` to the owner DAO.
+    pub fn internal_forward_delegation(
+        &self,
+        sender_id: AccountId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> Promise {
+        ext_sputnik::delegate(
+            account_id.clone(),
+            amount,
+            self.owner_id.clone(),
+            0,
+            self.gas_config.delegate,
+        )
+        .then(ext_self::on_delegate(
+            sender_id,
+            account_id,
+            amount,
+            false,
+            env::current_account_id(),
+            0,
+            self.gas_config.register
+                + self.gas_config.delegate
+                + self.gas_config.delegate_callback * 3,
+        ))
     }
 
     /// Undelegate votes from given delegate.
@@ -196,8 +438,341 @@ impl Contract {
         delegate_id: AccountId,
         amount: Balance,
     ) {
+        self.assert_no_owner_transfer();
+        self.internal_sync_user(&sender_id);
         let mut sender = self.internal_get_user(&sender_id);
         sender.undelegate(&delegate_id, amount, self.unstake_period);
+        self.internal_remove_delegator(&mut sender, &sender_id, &delegate_id, amount);
         self.save_user(&sender_id, sender);
+        self.internal_remove_delegated_total(&delegate_id, amount);
+    }
+
+    /// Moves given amount of votes of the sender from one delegate to another.
+    /// Starts the cooldown like undelegation.
+    pub fn internal_redelegate(
+        &mut self,
+        sender_id: &AccountId,
+        from_id: &AccountId,
+        to_id: &AccountId,
+        amount: Balance,
+    ) {
+        self.assert_no_owner_transfer();
+        self.internal_sync_user(sender_id);
+        let mut sender = self.internal_get_user(sender_id);
+        sender.redelegate(from_id, to_id.clone(), amount, self.unstake_period);
+        self.internal_move_delegators(sender, sender_id, from_id, to_id, amount);
+    }
+
+    /// Moves back votes redelegated by `internal_redelegate`, e.g. when the owner DAO rejected
+    /// the undelegation. The cooldown started by the redelegation stays.
+    pub fn internal_revert_redelegate(
+        &mut self,
+        sender_id: &AccountId,
+        from_id: &AccountId,
+        to_id: &AccountId,
+        amount: Balance,
+    ) {
+        let mut sender = self.internal_get_user(sender_id);
+        sender.move_delegation(to_id, from_id.clone(), amount);
+        self.internal_move_delegators(sender, sender_id, to_id, from_id, amount);
+    }
+
+    fn internal_move_delegators(
+        &mut self,
+        mut sender: User,
+        sender_id: &AccountId,
+        from_id: &AccountId,
+        to_id: &AccountId,
+        amount: Balance,
+    ) {
+        self.internal_remove_delegator(&mut sender, sender_id, from_id, amount);
+        self.internal_add_delegator(&mut sender, sender_id, to_id, amount);
+        sender.assert_storage();
+        self.save_user(sender_id, sender);
+        self.internal_remove_delegated_total(from_id, amount);
+        self.internal_add_delegated_total(to_id, amount);
+    }
+
+    /// Undelegates all votes of the sender, or only those delegated to `delegate_id` if given,
+    /// and forwards one undelegation per delegate to the owner DAO.
+    pub fn internal_undelegate_all(
+        &mut self,
+        sender_id: AccountId,
+        delegate_id: Option<&AccountId>,
+    ) -> Promise {
+        let sender = self.internal_get_user(&sender_id);
+        let mut totals: Vec<(AccountId, Balance)> = vec![];
+        for (account_id, amount) in sender.delegated_amounts {
+            if matches!(delegate_id, Some(delegate_id) if delegate_id != &account_id) {
+                continue;
+            }
+            self.internal_undelegate(sender_id.clone(), account_id.clone(), amount.0);
+            match totals.iter_mut().find(|(id, _)| id == &account_id) {
+                Some((_, total)) => *total += amount.0,
+                None => totals.push((account_id, amount.0)),
+            }
+        }
+        totals
+            .into_iter()
+            .map(|(account_id, total)| {
+                self.internal_forward_undelegation(sender_id.clone(), account_id, U128(total))
+            })
+            .reduce(|promise, undelegation| promise.and(undelegation))
+            .expect("ERR_NO_DELEGATE")
+    }
+
+    /// Forwards undelegation recorded by `internal_undelegate` to the owner DAO.
+    pub fn internal_forward_undelegation(
+        &self,
+        sender_id: AccountId,
+        account_id: AccountId,
+        amount: U128,
+    ) -> Promise {
+        ext_sputnik::undelegate(
+            account_id.clone(),
+            amount,
+            self.owner_id.clone(),
+            0,
+            self.gas_config.undelegate,
+        )
+        .then(ext_self::on_undelegate(
+            sender_id,
+            account_id,
+            amount,
+            env::current_account_id(),
+            0,
+            self.gas_config.delegate_callback,
+        ))
+    }
+
+    /// Reverts delegation whose forwarding to the owner DAO failed.
+    /// Unlike undelegation, doesn't delay the next action.
+    pub fn internal_revert_delegate(
+        &mut self,
+        sender_id: &AccountId,
+        delegate_id: &AccountId,
+        amount: Balance,
+    ) {
+        let mut sender = self.internal_get_user(sender_id);
+        sender.remove_delegation(delegate_id, amount);
+        self.internal_remove_delegator(&mut sender, sender_id, delegate_id, amount);
+        self.save_user(sender_id, sender);
+        self.internal_remove_delegated_total(delegate_id, amount);
+    }
+
+    /// Registers given delegate in the owner DAO, paying for the storage from the sender's
+    /// storage deposit. Returns `None` if the sender doesn't have enough storage available.
+    pub fn internal_register_delegate(
+        &mut self,
+        sender_id: &AccountId,
+        delegate_id: &AccountId,
+    ) -> Option<Promise> {
+        let cost = (REGISTER_DELEGATION_STORAGE as Balance) * env::storage_byte_cost();
+        let mut sender = self.internal_get_user(sender_id);
+        if sender.storage_available() < cost {
+            return None;
+        }
+        sender.near_amount.0 -= cost;
+        self.save_user(sender_id, sender);
+        Some(ext_sputnik::register_delegation(
+            delegate_id.clone(),
+            self.owner_id.clone(),
+            cost,
+            self.gas_config.register,
+        ))
+    }
+
+    /// Credits the deposit of a delegate registration back to the sender's storage deposit,
+    /// when the owner DAO refunded it or the registration failed.
+    pub fn internal_refund_register(&mut self, sender_id: &AccountId) {
+        if let Some(mut sender) = self.internal_get_user_opt(sender_id) {
+            sender.near_amount.0 +=
+                (REGISTER_DELEGATION_STORAGE as Balance) * env::storage_byte_cost();
+            self.save_user(sender_id, sender);
+        }
+    }
+
+    /// Registers the delegates of the delegated totals from `from_index` to `to_index` in
+    /// given DAO and delegates their totals there, e.g. when the owner changes.
+    /// Registrations are paid from the attached deposit.
+    pub fn internal_forward_delegated_totals(
+        &self,
+        owner_id: &AccountId,
+        from_index: u64,
+        to_index: u64,
+    ) -> Promise {
+        let cost = (REGISTER_DELEGATION_STORAGE as Balance) * env::storage_byte_cost();
+        assert!(
+            env::attached_deposit() >= cost * (to_index - from_index) as Balance,
+            "ERR_NOT_ENOUGH_DEPOSIT"
+        );
+        let keys = self.delegated_totals.keys_as_vector();
+        let values = self.delegated_totals.values_as_vector();
+        (from_index..to_index)
+            .map(|index| {
+                let delegate_id = keys.get(index).unwrap();
+                ext_sputnik::register_delegation(
+                    delegate_id.clone(),
+                    owner_id.clone(),
+                    cost,
+                    self.gas_config.register,
+                )
+                .then(ext_sputnik::delegate(
+                    delegate_id,
+                    U128(values.get(index).unwrap()),
+                    owner_id.clone(),
+                    0,
+                    self.gas_config.delegate,
+                ))
+            })
+            .reduce(|promise, delegation| promise.and(delegation))
+            .unwrap()
+    }
+
+    /// Transfers ownership once all delegated totals were forwarded to the pending owner.
+    pub fn internal_complete_owner_transfer(&mut self) {
+        if let Some(pending) = &self.pending_owner {
+            if !pending.forwarding && pending.forwarded.0 >= self.delegated_totals.len() {
+                self.owner_id = pending.owner_id.clone();
+                self.pending_owner = None;
+            }
+        }
+    }
+
+    /// Restores delegation whose undelegation the owner DAO rejected.
+    /// Returns false if the tokens were withdrawn in the meantime, or if the user's storage
+    /// deposit no longer covers the delegation.
+    pub fn internal_restore_delegation(
+        &mut self,
+        sender_id: &AccountId,
+        delegate_id: &AccountId,
+        amount: Balance,
+    ) -> bool {
+        let mut sender = self.internal_get_user(sender_id);
+        if !sender.restore_delegation(delegate_id, amount) {
+            return false;
+        }
+        self.internal_add_delegator(&mut sender, sender_id, delegate_id, amount);
+        if !sender.has_storage() {
+            // Storage released by the undelegation was used for something else in the meantime.
+            self.internal_remove_delegator(&mut sender, sender_id, delegate_id, amount);
+            return false;
+        }
+        self.save_user(sender_id, sender);
+        self.internal_add_delegated_total(delegate_id, amount);
+        true
+    }
+
+    /// Records undelegation rejected by the owner DAO as the last one of the user.
+    /// The record is charged to the user's storage, and skipped if the deposit doesn't cover it.
+    pub fn internal_save_undelegation_error(
+        &mut self,
+        account_id: &AccountId,
+        error: &UndelegationError,
+    ) {
+        if !self.undelegation_errors.contains_key(account_id) {
+            let mut user = self.internal_get_user(account_id);
+            user.storage_used.0 += UNDELEGATION_ERROR_STORAGE;
+            if !user.has_storage() {
+                return;
+            }
+            self.save_user(account_id, user);
+        }
+        self.undelegation_errors.insert(account_id, error);
+    }
+
+    /// Burn voting tokens delegated to self for ragequit.
+    pub fn internal_burn(&mut self, sender_id: &AccountId, amount: Balance) {
+        self.assert_no_owner_transfer();
+        self.internal_sync_user(sender_id);
+        let mut sender = self.internal_get_user(sender_id);
+        sender.burn(sender_id, amount);
+        self.internal_remove_delegator(&mut sender, sender_id, sender_id, amount);
+        self.save_user(sender_id, sender);
+        self.internal_remove_delegated_total(sender_id, amount);
+        self.total_amount -= amount;
+    }
+
+    /// Restore voting tokens burned for failed ragequit.
+    pub fn internal_unburn(&mut self, sender_id: &AccountId, amount: Balance) {
+        let mut sender = self.internal_get_user(sender_id);
+        sender.unburn(sender_id, amount);
+        self.internal_add_delegator(&mut sender, sender_id, sender_id, amount);
+        self.save_user(sender_id, sender);
+        self.internal_add_delegated_total(sender_id, amount);
+        self.total_amount += amount;
+    }
+
+    /// Records delegation in the delegators index, charging the sender for a new entry.
+    fn internal_add_delegator(
+        &mut self,
+        sender: &mut User,
+        sender_id: &AccountId,
+        delegate_id: &AccountId,
+        amount: Balance,
+    ) {
+        let mut delegators = self.delegators.get(delegate_id).unwrap_or_else(|| {
+            UnorderedMap::new(StorageKeys::DelegatorsOf {
+                delegate_id: delegate_id.clone(),
+            })
+        });
+        let delegator = match delegators.get(sender_id) {
+            Some(delegator) => Delegator {
+                amount: delegator.amount + amount,
+                timestamp: env::block_timestamp(),
+            },
+            None => {
+                sender.storage_used.0 += DELEGATOR_STORAGE;
+                Delegator {
+                    amount,
+                    timestamp: env::block_timestamp(),
+                }
+            }
+        };
+        delegators.insert(sender_id, &delegator);
+        self.delegators.insert(delegate_id, &delegators);
+    }
+
+    /// Removes delegation from the delegators index, releasing the storage of a removed entry.
+    /// Delegations made before the index existed are not recorded.
+    fn internal_remove_delegator(
+        &mut self,
+        sender: &mut User,
+        sender_id: &AccountId,
+        delegate_id: &AccountId,
+        amount: Balance,
+    ) {
+        let mut delegators = match self.delegators.get(delegate_id) {
+            Some(delegators) => delegators,
+            None => return,
+        };
+        if let Some(mut delegator) = delegators.get(sender_id) {
+            if delegator.amount <= amount {
+                delegators.remove(sender_id);
+                sender.storage_used.0 -= DELEGATOR_STORAGE;
+            } else {
+                delegator.amount -= amount;
+                delegators.insert(sender_id, &delegator);
+            }
+            self.delegators.insert(delegate_id, &delegators);
+        }
+    }
+
+    fn internal_add_delegated_total(&mut self, delegate_id: &AccountId, amount: Balance) {
+        let total = self.delegated_totals.get(delegate_id).unwrap_or_default();
+        self.delegated_totals.insert(delegate_id, &(total + amount));
+        self.total_delegated += amount;
+    }
+
+    /// Saturates in case a delegation made before delegated totals were tracked wasn't synced.
+    fn internal_remove_delegated_total(&mut self, delegate_id: &AccountId, amount: Balance) {
+        let previous = self.delegated_totals.get(delegate_id).unwrap_or_default();
+        let total = previous.saturating_sub(amount);
+        self.total_delegated -= previous - total;
+        if total == 0 {
+            self.delegated_totals.remove(delegate_id);
+        } else {
+            self.delegated_totals.insert(delegate_id, &total);
+        }
     }
 }
@@ -1,5 +1,12 @@
 #!/bin/bash
 set -e
 
-RUSTFLAGS='-C link-arg=-s' cargo +stable build --target wasm32-unknown-unknown --release
+# Commit reported by the contracts' `version` views.
+export GIT_HASH=$(git rev-parse --short HEAD)
+
+# The factory embeds the DAO and staking contracts, build them first.
+(cd ../sputnik-staking && ./build.sh)
+(cd ../sputnikdao2 && ./build.sh)
+
+RUSTFLAGS='-C link-arg=-s' cargo +1.69.0 build --target wasm32-unknown-unknown --release
 cp ../target/wasm32-unknown-unknown/release/sputnikdao_factory2.wasm ./res/
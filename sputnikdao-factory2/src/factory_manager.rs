@@ -10,7 +10,7 @@ use near_sdk::{env, AccountId, Balance, CryptoHash, Gas};
 const CREATE_CALL_GAS: Gas = Gas(75_000_000_000_000);
 
 /// Gas allocated on the callback.
-const ON_CREATE_CALL_GAS: Gas = Gas(10_000_000_000_000);
+pub const ON_CREATE_CALL_GAS: Gas = Gas(10_000_000_000_000);
 
 /// Leftover gas after creating promise and calling update.
 const GAS_UPDATE_LEFTOVER: Gas = Gas(20_000_000_000_000);
@@ -82,17 +82,19 @@ impl FactoryManager {
     }
 
     /// Create given contract with args and callback factory.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_contract(
         &self,
         code_hash: Base58CryptoHash,
         account_id: AccountId,
+        attached_deposit: Balance,
         new_method: &str,
         args: &[u8],
         callback_method: &str,
         callback_args: &[u8],
+        callback_gas: Gas,
     ) {
         let code_hash: CryptoHash = code_hash.into();
-        let attached_deposit = env::attached_deposit();
         let factory_account_id = env::current_account_id();
         // Check that such contract exists.
         assert!(env::storage_has_key(&code_hash), "Contract doesn't exist");
@@ -121,7 +123,7 @@ impl FactoryManager {
             callback_method,
             callback_args,
             NO_DEPOSIT,
-            ON_CREATE_CALL_GAS,
+            callback_gas,
         );
         env::promise_return(promise_id);
     }
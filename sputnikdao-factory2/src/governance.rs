@@ -0,0 +1,94 @@
+//! Handover of the factory ownership to a meta-DAO, a Sputnik DAO created by this factory
+//! that governs it with `FunctionCall` proposals.
+
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::*;
+
+/// Storage key of the DAO that was offered the factory ownership.
+const PENDING_META_DAO_KEY: &[u8; 12] = b"PENDING_META";
+
+#[near_bindgen]
+impl SputnikDAOFactory {
+    /// Offers the factory ownership to given DAO created by this factory.
+    /// The owner stays in control until the DAO accepts with `accept_meta_dao`.
+    pub fn set_meta_dao(&self, dao_id: AccountId) {
+        self.assert_owner();
+        assert!(
+            self.daos.contains(&dao_id),
+            "Must be contract created by factory"
+        );
+        env::storage_write(PENDING_META_DAO_KEY, dao_id.as_bytes());
+    }
+
+    /// Makes the calling DAO the factory owner, if it was offered the ownership.
+    /// Called by a `FunctionCall` proposal of the DAO.
+    pub fn accept_meta_dao(&self) {
+        assert_eq!(
+            self.get_pending_meta_dao(),
+            Some(env::predecessor_account_id()),
+            "Must be pending meta DAO"
+        );
+        env::storage_remove(PENDING_META_DAO_KEY);
+        env::storage_write(FACTORY_OWNER_KEY, env::predecessor_account_id().as_bytes());
+    }
+
+    /// DAO that was offered the factory ownership and didn't accept it yet, if any.
+    pub fn get_pending_meta_dao(&self) -> Option<AccountId> {
+        env::storage_read(PENDING_META_DAO_KEY).map(|dao_id| {
+            AccountId::new_unchecked(String::from_utf8(dao_id).expect("INTERNAL_FAIL"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+
+    use super::*;
+
+    #[test]
+    fn test_meta_dao() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        let dao_id: AccountId = format!("meta.{}", accounts(0)).parse().unwrap();
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        factory.on_create(dao_id.clone(), U128(10), accounts(0), None, None, None);
+
+        factory.set_meta_dao(dao_id.clone());
+        assert_eq!(factory.get_pending_meta_dao(), Some(dao_id.clone()));
+        assert_eq!(factory.get_owner(), accounts(0));
+
+        testing_env!(context.predecessor_account_id(dao_id.clone()).build());
+        factory.accept_meta_dao();
+        assert_eq!(factory.get_owner(), dao_id);
+        assert_eq!(factory.get_pending_meta_dao(), None);
+        // Owner methods are now called by proposals of the meta-DAO.
+        factory.set_default_code_hash(factory.get_default_code_hash());
+    }
+
+    #[test]
+    #[should_panic(expected = "Must be pending meta DAO")]
+    fn test_accept_meta_dao_not_offered() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let factory = SputnikDAOFactory::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        factory.accept_meta_dao();
+    }
+}
@@ -0,0 +1,231 @@
+//! Health information about DAOs created by this factory.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::{Base58CryptoHash, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId};
+
+use crate::*;
+
+/// Storage prefix of the per DAO health records.
+/// Kept outside of the factory state to not require a migration.
+const DAO_HEALTH_PREFIX: &[u8; 1] = b"h";
+
+/// Health record of a single DAO.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct DaoHealth {
+    /// Code hash last deployed to the DAO through this factory.
+    pub code_hash: Option<Base58CryptoHash>,
+    /// Timestamp of the last `ping` from the DAO.
+    pub last_activity: u64,
+    /// Paused status as last reported by the DAO.
+    pub paused: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct DaoHealthView {
+    pub account_id: AccountId,
+    pub code_hash: Option<Base58CryptoHash>,
+    /// Version of the DAO code, if its metadata is known.
+    pub version: Option<Version>,
+    /// Number of known versions newer than the DAO code, if its metadata is known.
+    pub versions_behind: Option<u32>,
+    pub last_activity: U64,
+    pub paused: bool,
+}
+
+fn dao_health() -> LookupMap<AccountId, DaoHealth> {
+    LookupMap::new(DAO_HEALTH_PREFIX.to_vec())
+}
+
+impl SputnikDAOFactory {
+    pub(crate) fn internal_set_dao_code_hash(
+        &self,
+        account_id: &AccountId,
+        code_hash: Base58CryptoHash,
+    ) {
+        let mut health = dao_health();
+        let mut record = health.get(account_id).unwrap_or_default();
+        record.code_hash = Some(code_hash);
+        health.insert(account_id, &record);
+    }
+}
+
+#[near_bindgen]
+impl SputnikDAOFactory {
+    /// Records activity of the calling DAO, together with its paused status.
+    pub fn ping(&mut self, paused: bool) {
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.daos.contains(&account_id),
+            "Must be contract created by factory"
+        );
+        let mut health = dao_health();
+        let mut record = health.get(&account_id).unwrap_or_default();
+        record.last_activity = env::block_timestamp();
+        record.paused = paused;
+        health.insert(&account_id, &record);
+    }
+
+    /// Sets the code hash of given DAOs created before this factory recorded code hashes, e.g. as
+    /// returned by `view_code`. DAOs with a recorded code hash are skipped. Only the owner can
+    /// call this.
+    pub fn backfill_daos_code_hash(
+        &self,
+        account_ids: Vec<AccountId>,
+        code_hash: Base58CryptoHash,
+    ) {
+        self.assert_owner();
+        let health = dao_health();
+        for account_id in account_ids {
+            assert!(
+                self.daos.contains(&account_id),
+                "Must be contract created by factory"
+            );
+            if health
+                .get(&account_id)
+                .and_then(|record| record.code_hash)
+                .is_none()
+            {
+                self.internal_set_dao_code_hash(&account_id, code_hash);
+            }
+        }
+    }
+
+    /// Get health of daos in paginated view.
+    pub fn get_daos_health(&self, from_index: u64, limit: u64) -> Vec<DaoHealthView> {
+        let metadata = self.get_contracts_metadata();
+        let health = dao_health();
+        self.get_daos(from_index, limit)
+            .into_iter()
+            .map(|account_id| {
+                let record = health.get(&account_id).unwrap_or_default();
+                let version = record.code_hash.and_then(|code_hash| {
+                    metadata
+                        .iter()
+                        .find(|(hash, _)| *hash == code_hash)
+                        .map(|(_, metadata)| metadata.version)
+                });
+                let versions_behind = version.map(|version| {
+                    metadata
+                        .iter()
+                        .filter(|(_, metadata)| metadata.version > version)
+                        .count() as u32
+                });
+                DaoHealthView {
+                    account_id,
+                    code_hash: record.code_hash,
+                    version,
+                    versions_behind,
+                    last_activity: U64(record.last_activity),
+                    paused: record.paused,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+
+    use super::*;
+
+    #[test]
+    fn test_daos_health() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        let code_hash = factory.get_default_code_hash();
+        let dao_id: AccountId = format!("test.{}", accounts(0)).parse().unwrap();
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        factory.on_create(
+            dao_id.clone(),
+            U128(10),
+            accounts(0),
+            None,
+            None,
+            Some(code_hash),
+        );
+
+        testing_env!(context
+            .predecessor_account_id(dao_id.clone())
+            .block_timestamp(100)
+            .build());
+        factory.ping(true);
+
+        let health = factory.get_daos_health(0, 10);
+        assert_eq!(
+            health,
+            vec![DaoHealthView {
+                account_id: dao_id,
+                code_hash: Some(code_hash),
+                version: Some(DAO_CONTRACT_INITIAL_VERSION),
+                versions_behind: Some(0),
+                last_activity: U64(100),
+                paused: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_backfill_daos_code_hash() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        let code_hash = factory.get_default_code_hash();
+        let old_code_hash: Base58CryptoHash = [1; 32].into();
+        let dao_id: AccountId = format!("test.{}", accounts(0)).parse().unwrap();
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        factory.on_create(dao_id.clone(), U128(10), accounts(0), None, None, None);
+        assert_eq!(factory.get_daos_health(0, 10)[0].code_hash, None);
+
+        testing_env!(context.build());
+        factory.backfill_daos_code_hash(vec![dao_id.clone()], old_code_hash);
+        assert_eq!(
+            factory.get_daos_health(0, 10)[0].code_hash,
+            Some(old_code_hash)
+        );
+        // Recorded code hashes are kept.
+        factory.backfill_daos_code_hash(vec![dao_id], code_hash);
+        assert_eq!(
+            factory.get_daos_health(0, 10)[0].code_hash,
+            Some(old_code_hash)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Must be contract created by factory")]
+    fn test_ping_unknown_dao() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        factory.ping(false);
+    }
+}
@@ -1,13 +1,16 @@
 mod factory_manager;
+mod governance;
+mod health;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{UnorderedMap, UnorderedSet};
-use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::serde_json::{self, json};
 use near_sdk::{env, near_bindgen, AccountId, Balance, CryptoHash, Gas, PanicOnDefault, Promise};
 
-use factory_manager::FactoryManager;
+use factory_manager::{FactoryManager, ON_CREATE_CALL_GAS};
+pub use health::DaoHealthView;
 
 type Version = [u8; 2];
 
@@ -20,12 +23,24 @@ const CODE_METADATA_KEY: &[u8; 8] = b"METADATA";
 const DAO_CONTRACT_INITIAL_CODE: &[u8] = include_bytes!("../../sputnikdao2/res/sputnikdao2.wasm");
 const DAO_CONTRACT_INITIAL_VERSION: Version = [3, 0];
 const DAO_CONTRACT_NO_DATA: &str = "no data";
+const STAKING_CONTRACT_CODE: &[u8] =
+    include_bytes!("../../sputnik-staking/res/sputnik_staking.wasm");
 
 // Gas & Costs for blob storage
 const GAS_STORE_CONTRACT_LEFTOVER: Gas = Gas(20_000_000_000_000);
 const ON_REMOVE_CONTRACT_GAS: Gas = Gas(10_000_000_000_000);
 const NO_DEPOSIT: Balance = 0;
 
+// Gas for deploying the paired staking contract
+const ON_CREATE_WITH_STAKING_GAS: Gas = Gas(80_000_000_000_000);
+const STAKING_NEW_GAS: Gas = Gas(10_000_000_000_000);
+const ON_CREATE_STAKING_GAS: Gas = Gas(30_000_000_000_000);
+const ADD_PROPOSAL_GAS: Gas = Gas(15_000_000_000_000);
+const ON_ADD_STAKING_PROPOSAL_GAS: Gas = Gas(5_000_000_000_000);
+
+/// Suffix of the staking contracts deployed with DAOs, not allowed in DAO names.
+const STAKING_SUFFIX: &str = "-staking";
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
 #[serde(crate = "near_sdk::serde")]
@@ -39,6 +54,21 @@ pub struct DaoContractMetadata {
     pub changelog_url: Option<String>,
 }
 
+/// Parameters for deploying a staking contract together with the DAO.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StakingArgs {
+    /// Fungible token that is staked for voting.
+    pub token_id: AccountId,
+    /// Duration of unstaking, in nanoseconds.
+    pub unstake_period: U64,
+    /// Part of the attached deposit sent to the staking account for storage.
+    pub deposit: U128,
+    /// Part of the attached deposit used as bond for the `SetStakingContract` proposal.
+    pub proposal_bond: U128,
+}
+
 #[near_bindgen]
 #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
 pub struct SputnikDAOFactory {
@@ -96,36 +126,83 @@ impl SputnikDAOFactory {
         self.delete_contract_metadata(code_hash);
     }
 
+    /// Creates a new DAO. If `staking` is given, also deploys a staking contract at
+    /// `<name>-staking.<factory>` owned by the DAO and proposes it via `SetStakingContract`,
+    /// with the caller as proposer. DAO names can't end with `-staking`.
     #[payable]
-    pub fn create(&mut self, name: AccountId, args: Base64VecU8) {
+    pub fn create(&mut self, name: AccountId, args: Base64VecU8, staking: Option<StakingArgs>) {
+        assert!(
+            !name.as_str().ends_with(STAKING_SUFFIX),
+            "ERR_RESERVED_NAME"
+        );
         let account_id: AccountId = format!("{}.{}", name, env::current_account_id())
             .parse()
             .unwrap();
+        let staking_cost = staking
+            .as_ref()
+            .map(|staking| staking.deposit.0 + staking.proposal_bond.0)
+            .unwrap_or_default();
+        assert!(
+            env::attached_deposit() > staking_cost,
+            "ERR_NOT_ENOUGH_DEPOSIT"
+        );
+        let staking_id: Option<AccountId> = staking.as_ref().map(|_| {
+            format!("{}{}.{}", name, STAKING_SUFFIX, env::current_account_id())
+                .parse()
+                .expect("ERR_INVALID_STAKING_ID")
+        });
+        let code_hash = self.get_default_code_hash();
         let callback_args = serde_json::to_vec(&json!({
             "account_id": account_id,
             "attached_deposit": U128(env::attached_deposit()),
-            "predecessor_account_id": env::predecessor_account_id()
+            "predecessor_account_id": env::predecessor_account_id(),
+            "staking_id": staking_id,
+            "staking": staking,
+            "code_hash": code_hash,
         }))
         .expect("Failed to serialize");
         self.factory_manager.create_contract(
-            self.get_default_code_hash(),
+            code_hash,
             account_id,
+            env::attached_deposit() - staking_cost,
             "new",
             &args.0,
             "on_create",
             &callback_args,
+            if staking_id.is_some() {
+                ON_CREATE_WITH_STAKING_GAS
+            } else {
+                ON_CREATE_CALL_GAS
+            },
         );
     }
 
+    /// Refunds the whole attached deposit if the DAO couldn't be created. The staking deposit
+    /// and proposal bond are still held by the factory then, as the staking contract is only
+    /// deployed after the DAO.
     #[private]
     pub fn on_create(
         &mut self,
         account_id: AccountId,
         attached_deposit: U128,
         predecessor_account_id: AccountId,
+        staking_id: Option<AccountId>,
+        staking: Option<StakingArgs>,
+        code_hash: Option<Base58CryptoHash>,
     ) -> bool {
         if near_sdk::is_promise_success() {
             self.daos.insert(&account_id);
+            if let Some(code_hash) = code_hash {
+                self.internal_set_dao_code_hash(&account_id, code_hash);
+            }
+            if let (Some(staking_id), Some(staking)) = (staking_id, staking) {
+                self.internal_create_staking(
+                    account_id,
+                    predecessor_account_id,
+                    staking_id,
+                    staking,
+                );
+            }
             true
         } else {
             Promise::new(predecessor_account_id).transfer(attached_deposit.0);
@@ -133,6 +210,102 @@ impl SputnikDAOFactory {
         }
     }
 
+    /// Deploys the staking contract owned by the new DAO and then proposes it.
+    fn internal_create_staking(
+        &self,
+        dao_id: AccountId,
+        predecessor_account_id: AccountId,
+        staking_id: AccountId,
+        staking: StakingArgs,
+    ) {
+        let new_args = serde_json::to_vec(&json!({
+            "owner_id": dao_id,
+            "token_id": staking.token_id,
+            "unstake_period": staking.unstake_period,
+        }))
+        .expect("Failed to serialize");
+        let callback_args = serde_json::to_vec(&json!({
+            "dao_id": dao_id,
+            "staking_id": staking_id,
+            "predecessor_account_id": predecessor_account_id,
+            "deposit": staking.deposit,
+            "proposal_bond": staking.proposal_bond,
+        }))
+        .expect("Failed to serialize");
+        Promise::new(staking_id)
+            .create_account()
+            .transfer(staking.deposit.0)
+            .deploy_contract(STAKING_CONTRACT_CODE.to_vec())
+            .function_call("new".to_string(), new_args, NO_DEPOSIT, STAKING_NEW_GAS)
+            .then(Promise::new(env::current_account_id()).function_call(
+                "on_create_staking".to_string(),
+                callback_args,
+                NO_DEPOSIT,
+                ON_CREATE_STAKING_GAS,
+            ));
+    }
+
+    /// Adds the `SetStakingContract` proposal to the DAO on behalf of the creator if the staking
+    /// contract was deployed, otherwise refunds the staking deposit and proposal bond.
+    #[private]
+    pub fn on_create_staking(
+        &mut self,
+        dao_id: AccountId,
+        staking_id: AccountId,
+        predecessor_account_id: AccountId,
+        deposit: U128,
+        proposal_bond: U128,
+    ) -> bool {
+        if near_sdk::is_promise_success() {
+            let args = serde_json::to_vec(&json!({
+                "proposal": {
+                    "description": "Set staking contract deployed by the factory",
+                    "kind": { "SetStakingContract": { "staking_id": staking_id } }
+                },
+                "proposer": predecessor_account_id,
+            }))
+            .expect("Failed to serialize");
+            let callback_args = serde_json::to_vec(&json!({
+                "predecessor_account_id": predecessor_account_id,
+                "proposal_bond": proposal_bond,
+            }))
+            .expect("Failed to serialize");
+            Promise::new(dao_id)
+                .function_call(
+                    "add_proposal_from_factory".to_string(),
+                    args,
+                    proposal_bond.0,
+                    ADD_PROPOSAL_GAS,
+                )
+                .then(Promise::new(env::current_account_id()).function_call(
+                    "on_add_staking_proposal".to_string(),
+                    callback_args,
+                    NO_DEPOSIT,
+                    ON_ADD_STAKING_PROPOSAL_GAS,
+                ));
+            true
+        } else {
+            Promise::new(predecessor_account_id).transfer(deposit.0 + proposal_bond.0);
+            false
+        }
+    }
+
+    /// Refunds the proposal bond if the DAO rejected the `SetStakingContract` proposal,
+    /// e.g. when the creator is not allowed to add it.
+    #[private]
+    pub fn on_add_staking_proposal(
+        &mut self,
+        predecessor_account_id: AccountId,
+        proposal_bond: U128,
+    ) -> bool {
+        if near_sdk::is_promise_success() {
+            true
+        } else {
+            Promise::new(predecessor_account_id).transfer(proposal_bond.0);
+            false
+        }
+    }
+
     /// Tries to update given account created by this factory to the specified code.
     pub fn update(&self, account_id: AccountId, code_hash: Base58CryptoHash) {
         let caller_id = env::predecessor_account_id();
@@ -144,6 +317,7 @@ impl SputnikDAOFactory {
             self.daos.contains(&account_id),
             "Must be contract created by factory"
         );
+        self.internal_set_dao_code_hash(&account_id, code_hash);
         self.factory_manager
             .update_contract(account_id, code_hash, "update");
     }
@@ -260,6 +434,15 @@ impl SputnikDAOFactory {
         }
     }
 
+    /// Returns semver of this contract. If `GIT_HASH` was set at build time, the commit is
+    /// added as build metadata, e.g. `1.0.0+1a2b3c4`.
+    pub fn version(&self) -> String {
+        match option_env!("GIT_HASH") {
+            Some(hash) => format!("{}+{}", env!("CARGO_PKG_VERSION"), hash),
+            None => env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
     pub fn get_dao_list(&self) -> Vec<AccountId> {
         self.daos.to_vec()
     }
@@ -360,7 +543,7 @@ impl SputnikDAOFactory {
         return deserialized_metadata.to_vec();
     }
 
-    fn assert_owner(&self) {
+    pub(crate) fn assert_owner(&self) {
         assert_eq!(
             self.get_owner(),
             env::predecessor_account_id(),
@@ -394,7 +577,8 @@ pub extern "C" fn store() {
 
 #[cfg(test)]
 mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
     use near_sdk::{testing_env, PromiseResult};
 
     use super::*;
@@ -409,7 +593,11 @@ mod tests {
         let mut factory = SputnikDAOFactory::new();
 
         testing_env!(context.attached_deposit(10).build());
-        factory.create("test".parse().unwrap(), "{}".as_bytes().to_vec().into());
+        factory.create(
+            "test".parse().unwrap(),
+            "{}".as_bytes().to_vec().into(),
+            None,
+        );
 
         testing_env!(
             context.predecessor_account_id(accounts(0)).build(),
@@ -422,6 +610,9 @@ mod tests {
             format!("test.{}", accounts(0)).parse().unwrap(),
             U128(10),
             accounts(0),
+            None,
+            None,
+            None,
         );
         assert_eq!(
             factory.get_dao_list(),
@@ -432,4 +623,143 @@ mod tests {
             vec![format!("test.{}", accounts(0)).parse().unwrap()]
         );
     }
+
+    #[test]
+    fn test_create_with_staking() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        let staking = StakingArgs {
+            token_id: accounts(2),
+            unstake_period: U64(1_000),
+            deposit: U128(5),
+            proposal_bond: U128(1),
+        };
+
+        testing_env!(context.attached_deposit(10).build());
+        factory.create(
+            "test".parse().unwrap(),
+            "{}".as_bytes().to_vec().into(),
+            Some(staking.clone()),
+        );
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        assert!(factory.on_create(
+            format!("test.{}", accounts(0)).parse().unwrap(),
+            U128(10),
+            accounts(1),
+            Some(format!("test-staking.{}", accounts(0)).parse().unwrap()),
+            Some(staking),
+            None,
+        ));
+        assert_eq!(factory.get_number_daos(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_DEPOSIT")]
+    fn test_create_with_staking_no_deposit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        testing_env!(context.attached_deposit(5).build());
+        factory.create(
+            "test".parse().unwrap(),
+            "{}".as_bytes().to_vec().into(),
+            Some(StakingArgs {
+                token_id: accounts(2),
+                unstake_period: U64(1_000),
+                deposit: U128(5),
+                proposal_bond: U128(1),
+            }),
+        );
+    }
+
+    #[test]
+    fn test_create_failure_refund() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!factory.on_create(
+            format!("test.{}", accounts(0)).parse().unwrap(),
+            U128(10),
+            accounts(1),
+            Some(format!("test-staking.{}", accounts(0)).parse().unwrap()),
+            Some(StakingArgs {
+                token_id: accounts(2),
+                unstake_period: U64(1_000),
+                deposit: U128(5),
+                proposal_bond: U128(1),
+            }),
+            None,
+        ));
+        assert_eq!(factory.get_number_daos(), 0);
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        assert_eq!(
+            receipts[0].actions,
+            vec![VmAction::Transfer { deposit: 10 }]
+        );
+    }
+
+    #[test]
+    fn test_add_staking_proposal_failure_refund() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!factory.on_add_staking_proposal(accounts(1), U128(3)));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        assert_eq!(receipts[0].actions, vec![VmAction::Transfer { deposit: 3 }]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RESERVED_NAME")]
+    fn test_create_staking_suffix() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut factory = SputnikDAOFactory::new();
+        testing_env!(context.attached_deposit(10).build());
+        factory.create(
+            "test-staking".parse().unwrap(),
+            "{}".as_bytes().to_vec().into(),
+            None,
+        );
+    }
 }
@@ -0,0 +1,140 @@
+use crate::events::Event;
+use crate::*;
+
+impl Contract {
+    /// Checks if given account is a member of any group role.
+    fn internal_is_group_member(&self, account_id: &AccountId) -> bool {
+        self.policy.get().unwrap().to_policy().roles.iter().any(
+            |role| matches!(&role.kind, RoleKind::Group(members) if members.contains(account_id)),
+        )
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Sets alternate that can vote on behalf of the caller's seat while the caller hasn't voted.
+    /// Only members of group roles can have an alternate. `None` revokes the alternate.
+    pub fn set_alternate(&mut self, alternate_id: Option<AccountId>) {
+        let seat_id = env::predecessor_account_id();
+        match &alternate_id {
+            Some(alternate_id) => {
+                assert!(
+                    self.internal_is_group_member(&seat_id),
+                    "ERR_NOT_GROUP_MEMBER"
+                );
+                assert_ne!(&seat_id, alternate_id, "ERR_ALTERNATE_IS_SELF");
+                self.alternates.insert(&seat_id, alternate_id);
+            }
+            None => {
+                self.alternates.remove(&seat_id).expect("ERR_NO_ALTERNATE");
+            }
+        }
+        self.internal_emit_event(Event::SetAlternate {
+            seat_id,
+            alternate_id,
+        });
+    }
+
+    /// Vote on given proposal as alternate of `seat_id`. The vote is attributed to the seat.
+    /// Fails if the seat already voted on this proposal.
+    pub fn act_proposal_as_alternate(
+        &mut self,
+        id: u64,
+        seat_id: AccountId,
+        action: Action,
+        memo: Option<String>,
+    ) -> ActProposalResult {
+        let alternate_id = env::predecessor_account_id();
+        assert_eq!(
+            self.alternates.get(&seat_id),
+            Some(alternate_id.clone()),
+            "ERR_NOT_ALTERNATE"
+        );
+        assert!(
+            matches!(
+                action,
+                Action::VoteApprove | Action::VoteReject | Action::VoteRemove | Action::FlagSpam
+            ),
+            "ERR_ALTERNATE_CAN_ONLY_VOTE"
+        );
+        self.assert_no_vote_options(id, &action);
+        // The seat may have left its role since the alternate was set.
+        assert!(
+            self.internal_is_group_member(&seat_id),
+            "ERR_NOT_GROUP_MEMBER"
+        );
+        self.internal_emit_event(Event::AlternateVote {
+            proposal_id: id,
+            seat_id: seat_id.clone(),
+            alternate_id,
+        });
+        self.internal_act_proposal(id, seat_id, action, memo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U128;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Config, OLD_BASE_TOKEN};
+
+    use super::*;
+
+    fn setup_alternate(context: &mut VMContextBuilder) -> (Contract, u64) {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(4),
+                amount: U128(to_yocto("1")),
+                msg: None,
+            },
+        });
+        contract.set_alternate(Some(accounts(3)));
+        assert_eq!(contract.get_alternate(accounts(1)), Some(accounts(3)));
+        (contract, id)
+    }
+
+    #[test]
+    fn test_alternate_vote() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup_alternate(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal_as_alternate(id, accounts(1), Action::VoteReject, None);
+        let proposal = contract.get_proposal(id).proposal;
+        assert!(proposal.votes.contains_key(&accounts(1)));
+        assert!(!proposal.votes.contains_key(&accounts(3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALTERNATE")]
+    fn test_alternate_revoked() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup_alternate(&mut context);
+        contract.set_alternate(None);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal_as_alternate(id, accounts(1), Action::VoteReject, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_GROUP_MEMBER")]
+    fn test_alternate_of_former_member() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup_alternate(&mut context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.remove_member_from_role(&"council".to_string(), &accounts(1));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal_as_alternate(id, accounts(1), Action::VoteReject, None);
+    }
+}
@@ -0,0 +1,153 @@
+//! Proposals added by the sponsor role on behalf of anonymous authors, e.g. for whistleblowers.
+//! The author can reveal themselves to claim credit once voting is over.
+
+use near_sdk::json_types::Base58CryptoHash;
+
+use crate::*;
+
+/// Commitment to the author of a proposal added with `add_anonymous_proposal`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AnonymousAuthor {
+    /// `sha256("<author account id>:<salt>")`, computed off-chain by the author.
+    pub commitment: Base58CryptoHash,
+    /// Author who revealed the commitment with `reveal_proposal_author`.
+    pub author: Option<AccountId>,
+}
+
+/// Returns the commitment of given author with given salt.
+pub fn author_commitment(author: &AccountId, salt: &str) -> Base58CryptoHash {
+    let mut hash = CryptoHash::default();
+    hash.copy_from_slice(&env::sha256(format!("{}:{}", author, salt).as_bytes()));
+    hash.into()
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Adds proposal on behalf of an anonymous author, committed to with `commitment`.
+    /// Only members of the policy's `sponsor_role` can call this. The sponsor is the proposer
+    /// and pays the bond.
+    #[payable]
+    pub fn add_anonymous_proposal(
+        &mut self,
+        proposal: ProposalInput,
+        commitment: Base58CryptoHash,
+    ) -> u64 {
+        self.assert_not_blocked(&env::predecessor_account_id());
+        let policy = self.policy.get().unwrap().to_policy();
+        let sponsor_role = policy.sponsor_role.as_ref().expect("ERR_NO_SPONSOR_ROLE");
+        let role = policy
+            .internal_get_role(sponsor_role)
+            .expect("ERR_MISSING_ROLE");
+        assert!(
+            role.kind.match_user(&self.internal_user_info()),
+            "ERR_PERMISSION_DENIED"
+        );
+        let id = self.add_proposal(proposal);
+        self.anonymous_authors.insert(
+            &id,
+            &AnonymousAuthor {
+                commitment,
+                author: None,
+            },
+        );
+        id
+    }
+
+    /// Reveals the caller as the author of given anonymous proposal with the salt of the
+    /// commitment. Only possible once the proposal can't be voted on anymore.
+    pub fn reveal_proposal_author(&mut self, id: u64, salt: String) {
+        let mut anonymous = self
+            .anonymous_authors
+            .get(&id)
+            .expect("ERR_NOT_ANONYMOUS_PROPOSAL");
+        assert!(anonymous.author.is_none(), "ERR_AUTHOR_ALREADY_REVEALED");
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            !self.internal_is_proposal_pending(&policy, id),
+            "ERR_PROPOSAL_PENDING"
+        );
+        let author = env::predecessor_account_id();
+        assert!(
+            author_commitment(&author, &salt) == anonymous.commitment,
+            "ERR_INVALID_COMMITMENT"
+        );
+        anonymous.author = Some(author);
+        self.anonymous_authors.insert(&id, &anonymous);
+    }
+
+    /// Commitment and, once revealed, author of given anonymous proposal.
+    pub fn get_anonymous_author(&self, id: u64) -> Option<AnonymousAuthor> {
+        self.anonymous_authors.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn setup(context: &mut VMContextBuilder) -> (Contract, u64) {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.sponsor_role = Some("council".to_string());
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_anonymous_proposal(
+            ProposalInput {
+                description: "audit the treasury".to_string(),
+                kind: ProposalKind::Vote,
+            },
+            author_commitment(&accounts(2), "salt"),
+        );
+        (contract, id)
+    }
+
+    #[test]
+    fn test_reveal_proposal_author() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup(&mut context);
+        assert_eq!(contract.get_proposal(id).proposal.proposer, accounts(1));
+        contract.act_proposal(id, Action::VoteApprove, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.reveal_proposal_author(id, "salt".to_string());
+        assert_eq!(
+            contract.get_anonymous_author(id).unwrap().author,
+            Some(accounts(2))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_PENDING")]
+    fn test_reveal_proposal_author_during_voting() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.reveal_proposal_author(id, "salt".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_COMMITMENT")]
+    fn test_reveal_proposal_author_wrong_account() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup(&mut context);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.reveal_proposal_author(id, "salt".to_string());
+    }
+}
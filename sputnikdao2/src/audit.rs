@@ -0,0 +1,171 @@
+//! Self-audit of state invariants, to detect drift of the state early.
+
+use std::cmp::min;
+
+use near_sdk::json_types::U128;
+
+use crate::*;
+
+/// Section of the state checked by `run_self_audit`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AuditSection {
+    /// Sum of delegations of registered accounts against the total delegation amount.
+    Delegations,
+    /// Number of claims of each bounty against the number of times it can still be done.
+    Bounties,
+    /// Roles referenced by proposals in progress, and whether they are counted as active.
+    Proposals,
+}
+
+/// Result of auditing a chunk of a section.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuditReport {
+    pub section: AuditSection,
+    /// Index this chunk starts from.
+    pub from_index: u64,
+    /// Total number of entries in the section.
+    pub total: u64,
+    /// Violated invariants found in this chunk.
+    pub issues: Vec<String>,
+    /// Delegated amount or number of proposals counted as active in this chunk. Sums of all chunks
+    /// should equal `expected_sum`. Always 0 for bounties.
+    pub chunk_sum: U128,
+    /// Total delegation amount or number of active proposals recorded by the DAO.
+    pub expected_sum: U128,
+}
+
+impl ProposalKind {
+    /// Role referenced by the proposal, if any.
+    fn referenced_role(&self) -> Option<&String> {
+        match self {
+            ProposalKind::AddMemberToRole { role, .. }
+            | ProposalKind::RemoveMemberFromRole { role, .. }
+            | ProposalKind::ChangePolicyRemoveRole { role }
+            | ProposalKind::ChangeRoleVotePolicy { role, .. }
+            | ProposalKind::AddMembersToRole { role, .. }
+            | ProposalKind::ReplaceRoleMembers { role, .. } => Some(role),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Checks invariants of given section of the state, in chunks.
+    /// Delegations of accounts registered before the migration to the delegation index are not
+    /// included, same as proposals stored before `migrate` until `recount_active` counted them.
+    pub fn run_self_audit(
+        &self,
+        section: AuditSection,
+        from_index: u64,
+        limit: u64,
+    ) -> AuditReport {
+        let mut issues = vec![];
+        let mut chunk_sum = 0;
+        let (total, expected_sum) = match section {
+            AuditSection::Delegations => {
+                let accounts = self.delegation_accounts.as_vector();
+                for index in from_index..min(from_index + limit, accounts.len()) {
+                    let account_id = accounts.get(index).unwrap();
+                    chunk_sum += self.delegations.get(&account_id).unwrap_or_default();
+                }
+                (accounts.len(), self.total_delegation_amount)
+            }
+            AuditSection::Bounties => {
+                for id in from_index..min(from_index + limit, self.last_bounty_id) {
+                    let bounty: Bounty = match self.bounties.get(&id) {
+                        Some(bounty) => bounty.into(),
+                        None => continue,
+                    };
+                    let claims = self.bounty_claims_count.get(&id).unwrap_or_default();
+                    if claims > bounty.times {
+                        issues.push(format!(
+                            "bounty {}: {} claims exceed {} times left",
+                            id, claims, bounty.times
+                        ));
+                    }
+                }
+                (self.last_bounty_id, 0)
+            }
+            AuditSection::Proposals => {
+                let policy = self.policy.get().unwrap().to_policy();
+                for id in from_index..min(from_index + limit, self.last_proposal_id) {
+                    let proposal: Proposal = match self.proposals.get(&id) {
+                        Some(proposal) => proposal.into(),
+                        None => continue,
+                    };
+                    if proposal.status != ProposalStatus::InProgress {
+                        continue;
+                    }
+                    if self.active_proposals.contains(&id) {
+                        chunk_sum += 1;
+                    } else if self.internal_is_proposal_pending(&policy, id) {
+                        issues.push(format!("proposal {}: not counted as active", id));
+                    }
+                    if let Some(role) = proposal.kind.referenced_role() {
+                        if policy.internal_get_role(role).is_none() {
+                            issues.push(format!("proposal {}: missing role {}", id, role));
+                        }
+                    }
+                }
+                (
+                    self.last_proposal_id,
+                    self.active_proposals.len() as Balance,
+                )
+            }
+        };
+        AuditReport {
+            section,
+            from_index,
+            total,
+            issues,
+            chunk_sum: U128(chunk_sum),
+            expected_sum: U128(expected_sum),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    #[test]
+    fn test_self_audit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMemberToRole {
+                member_id: accounts(2),
+                role: "council".to_string(),
+            },
+        });
+        let report = contract.run_self_audit(AuditSection::Proposals, 0, 10);
+        assert!(report.issues.is_empty());
+        assert_eq!(report.chunk_sum, report.expected_sum);
+
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.remove_role(&"council".to_string());
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        let report = contract.run_self_audit(AuditSection::Proposals, 0, 10);
+        assert_eq!(
+            report.issues,
+            vec![format!("proposal {}: missing role council", id)]
+        );
+    }
+}
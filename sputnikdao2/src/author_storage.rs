@@ -0,0 +1,185 @@
+//! Storage accounting for proposal and bounty authors, enabled with the policy's
+//! `charge_author_storage`.
+
+use near_sdk::json_types::U128;
+use near_sdk::StorageUsage;
+
+use crate::*;
+
+/// Proposal, bounty or vote option selection whose storage was charged to its author.
+/// Grant applications record the storage deposit of the applicant, see `grant_apply`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum StorageChargeKey {
+    Proposal(u64),
+    Bounty(u64),
+    VoteOptionSelection(u64, AccountId),
+    Grant(u64),
+}
+
+impl Contract {
+    /// Charges storage used since `initial_storage` to the author's storage balance if the
+    /// policy has `charge_author_storage`. See `internal_charge_storage`.
+    pub(crate) fn internal_charge_author_storage(
+        &mut self,
+        policy: &Policy,
+        key: StorageChargeKey,
+        author: &AccountId,
+        initial_storage: StorageUsage,
+        partial: bool,
+    ) {
+        if policy.charge_author_storage {
+            self.internal_charge_storage(key, author, initial_storage, partial);
+        }
+    }
+
+    /// Charges storage used since `initial_storage` to the author's storage balance, on top of
+    /// anything charged for the same key before. Used directly for optional data like
+    /// translations and bounty proofs, which is charged regardless of the policy.
+    /// Panics if the balance is not enough, unless `partial`, then only the balance is charged.
+    /// The DAO itself, e.g. for recurring votes, is not charged.
+    pub(crate) fn internal_charge_storage(
+        &mut self,
+        key: StorageChargeKey,
+        author: &AccountId,
+        initial_storage: StorageUsage,
+        partial: bool,
+    ) {
+        if author == &env::current_account_id() {
+            return;
+        }
+        let previous = self
+            .storage_charges
+            .get(&key)
+            .map(|(_, charged)| charged)
+            .unwrap_or(0);
+        // Record is inserted first so its own storage is charged too.
+        self.storage_charges
+            .insert(&key, &(author.clone(), previous));
+        let cost = env::storage_usage().saturating_sub(initial_storage) as Balance
+            * env::storage_byte_cost();
+        let balance = self.author_storage.get(author).unwrap_or(0);
+        let charged = if partial {
+            std::cmp::min(balance, cost)
+        } else {
+            assert!(balance >= cost, "ERR_NOT_ENOUGH_AUTHOR_STORAGE");
+            cost
+        };
+        self.author_storage.insert(author, &(balance - charged));
+        self.storage_charges
+            .insert(&key, &(author.clone(), previous + charged));
+        // Charged amount is now covered by the storage lock.
+        self.locked_amount -= charged;
+    }
+
+    /// Returns storage charged for given proposal or bounty to its author's storage balance.
+    pub(crate) fn internal_refund_author_storage(&mut self, key: StorageChargeKey) {
+        if let Some((author, charged)) = self.storage_charges.remove(&key) {
+            let balance = self.author_storage.get(&author).unwrap_or(0);
+            self.author_storage.insert(&author, &(balance + charged));
+            self.locked_amount += charged;
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Adds attached deposit to the storage balance of given account or the caller.
+    /// Returns the new balance.
+    #[payable]
+    pub fn author_storage_deposit(&mut self, account_id: Option<AccountId>) -> U128 {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let balance = self.author_storage.get(&account_id).unwrap_or(0) + env::attached_deposit();
+        self.author_storage.insert(&account_id, &balance);
+        self.locked_amount += env::attached_deposit();
+        U128(balance)
+    }
+
+    /// Withdraws given amount or the whole storage balance of the caller not charged for
+    /// storage. Returns the new balance.
+    pub fn author_storage_withdraw(&mut self, amount: Option<U128>) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let balance = self.author_storage.get(&account_id).unwrap_or(0);
+        let amount = amount.map(|amount| amount.0).unwrap_or(balance);
+        assert!(amount <= balance, "ERR_NOT_ENOUGH_AUTHOR_STORAGE");
+        if amount == balance {
+            self.author_storage.remove(&account_id);
+        } else {
+            self.author_storage.insert(&account_id, &(balance - amount));
+        }
+        self.locked_amount -= amount;
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+        U128(balance - amount)
+    }
+
+    /// Storage balance of given author not charged for storage.
+    pub fn storage_balance_of_author(&self, account_id: AccountId) -> U128 {
+        U128(self.author_storage.get(&account_id).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn setup(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.charge_author_storage = true;
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract
+    }
+
+    #[test]
+    fn test_author_storage() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup(&mut context);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.author_storage_deposit(None);
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+        let balance = contract.storage_balance_of_author(accounts(1)).0;
+        assert!(balance > 0 && balance < to_yocto("1"));
+        assert_eq!(contract.locked_amount, to_yocto("1") + balance);
+
+        testing_env!(context.attached_deposit(0).build());
+        contract.act_proposal(id, Action::VoteRemove, None);
+        assert_eq!(
+            contract.storage_balance_of_author(accounts(1)).0,
+            to_yocto("1")
+        );
+        let locked_amount = contract.locked_amount;
+        assert_eq!(contract.author_storage_withdraw(None).0, 0);
+        assert_eq!(contract.locked_amount, locked_amount - to_yocto("1"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_AUTHOR_STORAGE")]
+    fn test_author_storage_not_enough() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup(&mut context);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+    }
+}
@@ -0,0 +1,129 @@
+use crate::events::Event;
+use crate::*;
+
+impl Contract {
+    /// Panics if given account is blocked from interacting with the DAO.
+    pub(crate) fn assert_not_blocked(&self, account_id: &AccountId) {
+        assert!(!self.blocklist.contains(account_id), "ERR_ACCOUNT_BLOCKED");
+    }
+
+    /// Adds and removes accounts from the blocklist.
+    pub(crate) fn internal_update_blocklist(&mut self, add: &[AccountId], remove: &[AccountId]) {
+        for account_id in add {
+            if self.blocklist.insert(account_id) {
+                self.internal_emit_event(Event::BlockAccount {
+                    account_id: account_id.clone(),
+                });
+            }
+        }
+        for account_id in remove {
+            if self.blocklist.remove(account_id) {
+                self.internal_emit_event(Event::UnblockAccount {
+                    account_id: account_id.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::{U128, U64};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::anonymous::author_commitment;
+    use crate::loans::{Loan, LoanStatus};
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn setup_blocklist(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::UpdateBlocklist {
+                add: vec![accounts(2)],
+                remove: vec![],
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract
+    }
+
+    #[test]
+    fn test_blocklist() {
+        let mut context = VMContextBuilder::new();
+        let contract = setup_blocklist(&mut context);
+        assert!(contract.is_blocked(accounts(2)));
+        assert_eq!(contract.get_blocklist(0, 10), vec![accounts(2)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ACCOUNT_BLOCKED")]
+    fn test_blocked_add_proposal() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_blocklist(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ACCOUNT_BLOCKED")]
+    fn test_blocked_bounty_claim() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_blocklist(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.bounty_claim(0, U64::from(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ACCOUNT_BLOCKED")]
+    fn test_blocked_anonymous_proposal() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_blocklist(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.add_anonymous_proposal(
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Vote,
+            },
+            author_commitment(&accounts(3), "salt"),
+        );
+    }
+
+    #[test]
+    fn test_blocked_loan_repay() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_blocklist(&mut context);
+        contract.loans.insert(
+            &0,
+            &Loan {
+                borrower: accounts(2),
+                token_id: accounts(3).to_string(),
+                amount: U128(100),
+                outstanding: U128(100),
+                due: U64(0),
+                collateral: None,
+                status: LoanStatus::Active,
+                default_proposal_id: None,
+            },
+        );
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_on_transfer(accounts(2), U128(100), "{\"loan_id\": 0}".to_string());
+        assert_eq!(contract.get_loan(0).unwrap().status, LoanStatus::Repaid);
+    }
+}
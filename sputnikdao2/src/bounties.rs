@@ -3,9 +3,16 @@ use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, near_bindgen, AccountId, Promise, PromiseOrValue};
 
+use crate::author_storage::StorageChargeKey;
+use crate::events::{BountyUpdate, Event};
+use crate::loans::LoanRepayMsg;
+use crate::proposals::ProposalBondMsg;
 use crate::types::{convert_old_to_new_token, OldAccountId};
 use crate::*;
 
+/// Maximum total length of the links and artifact hashes of a bounty proof.
+pub const MAX_BOUNTY_PROOF_LEN: usize = 2048;
+
 /// Information recorded about claim of the bounty by given user.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -38,6 +45,34 @@ pub struct Bounty {
     pub max_deadline: U64,
 }
 
+/// Bond for a claim paid in the bounty's fungible token instead of $NEAR.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct BountyTokenBond {
+    pub token_id: AccountId,
+    pub amount: Balance,
+}
+
+/// Evidence of bounty completion submitted with `bounty_done`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyProof {
+    /// Links to the delivered work, e.g. pull requests or documents.
+    #[serde(default)]
+    pub links: Vec<String>,
+    /// Hashes of delivered artifacts, e.g. IPFS CIDs or sha256 of files.
+    #[serde(default)]
+    pub artifact_hashes: Vec<String>,
+}
+
+/// Message passed to `ft_on_transfer` to claim a bounty with a token bond.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyClaimMsg {
+    pub bounty_id: u64,
+    pub deadline: U64,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
 #[serde(crate = "near_sdk::serde")]
@@ -61,55 +96,90 @@ impl Contract {
         self.bounties
             .insert(&id, &VersionedBounty::Default(bounty.clone()));
         self.last_bounty_id += 1;
+        self.open_bounties.insert(&id);
+        self.internal_emit_bounty_digest(id, BountyUpdate::Added, None, bounty);
         id
     }
 
-    /// This must be called when proposal to payout bounty has been voted either successfully or not.
+    /// Emits `bounty_digest` with the full bounty after given update, for job board aggregators.
+    pub(crate) fn internal_emit_bounty_digest(
+        &mut self,
+        bounty_id: u64,
+        update: BountyUpdate,
+        account_id: Option<&AccountId>,
+        bounty: &Bounty,
+    ) {
+        self.internal_emit_event(Event::BountyDigest {
+            bounty_id,
+            update,
+            account_id: account_id.cloned(),
+            bounty: bounty.clone(),
+        });
+    }
+
+    /// Pays out the bounty to the claimer of an approved `BountyDone` proposal.
+    /// The claim is kept until the payout succeeds, so a failed payout can be retried.
     pub(crate) fn internal_execute_bounty_payout(
         &mut self,
+        policy: &Policy,
+        proposal_id: u64,
         id: u64,
         receiver_id: &AccountId,
-        success: bool,
     ) -> PromiseOrValue<()> {
         let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
-        self.internal_remove_claim(id, receiver_id);
-        if success {
-            self.internal_payout(
-                &convert_old_to_new_token(&bounty.token),
-                receiver_id,
-                bounty.amount.0,
-                format!("Bounty {} payout", id),
-                None,
-            )
-        } else {
-            PromiseOrValue::Value(())
+        let token_id = convert_old_to_new_token(&bounty.token);
+        self.internal_record_outflow(policy, proposal_id, &token_id, bounty.amount.0);
+        self.internal_payout(
+            &token_id,
+            receiver_id,
+            bounty.amount.0,
+            format!("Bounty {} payout", id),
+            None,
+        )
+    }
+
+    /// Closes the claim of a `BountyDone` proposal that won't be paid out, freeing the claim slot.
+    /// Bond is forfeited if `forfeit_bond`, otherwise it must be returned by the caller.
+    pub(crate) fn internal_close_bounty_claim(
+        &mut self,
+        policy: &Policy,
+        id: u64,
+        claimer_id: &AccountId,
+        forfeit_bond: bool,
+    ) {
+        if forfeit_bond {
+            self.internal_forfeit_bounty_bond(policy, id, claimer_id);
         }
+        self.internal_remove_claim(id, claimer_id);
     }
 
-    fn internal_find_claim(&self, bounty_id: u64, claims: &[BountyClaim]) -> Option<usize> {
-        for i in 0..claims.len() {
-            if claims[i].bounty_id == bounty_id {
-                return Some(i);
-            }
+    /// Sets the pool budget of bounty `id`. Zero budget removes the pool.
+    pub(crate) fn internal_set_bounty_pool(&mut self, id: u64, budget: Balance) {
+        if budget == 0 {
+            self.bounty_pools.remove(&id);
+        } else {
+            self.bounty_pools.insert(&id, &budget);
         }
-        None
     }
-}
 
-#[near_bindgen]
-impl Contract {
-    /// Claim given bounty by caller with given expected duration to execute.
-    /// Bond must be attached to the claim.
-    /// Fails if already claimed `times` times.
-    #[payable]
-    pub fn bounty_claim(&mut self, id: u64, deadline: U64) {
+    /// Reopens bounty `id` for one more completion if its pool covers another payout.
+    pub(crate) fn internal_renew_bounty(&mut self, id: u64, bounty: &mut Bounty) {
+        let budget = match self.bounty_pools.get(&id) {
+            Some(budget) if budget >= bounty.amount.0 => budget,
+            _ => return,
+        };
+        let remaining_budget = budget - bounty.amount.0;
+        self.internal_set_bounty_pool(id, remaining_budget);
+        bounty.times = 1;
+        self.internal_emit_event(Event::BountyRenew {
+            bounty_id: id,
+            remaining_budget: U128(remaining_budget),
+        });
+    }
+
+    /// Records the claim of bounty `id` by `claimer_id`. Bond must be already collected.
+    fn internal_bounty_claim(&mut self, id: u64, claimer_id: &AccountId, deadline: U64) {
         let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
-        let policy = self.policy.get().unwrap().to_policy();
-        assert_eq!(
-            env::attached_deposit(),
-            policy.bounty_bond.0,
-            "ERR_BOUNTY_WRONG_BOND"
-        );
         let claims_count = self.bounty_claims_count.get(&id).unwrap_or_default();
         assert!(claims_count < bounty.times, "ERR_BOUNTY_ALL_CLAIMED");
         assert!(
@@ -117,23 +187,102 @@ impl Contract {
             "ERR_BOUNTY_WRONG_DEADLINE"
         );
         self.bounty_claims_count.insert(&id, &(claims_count + 1));
-        let mut claims = self
-            .bounty_claimers
-            .get(&env::predecessor_account_id())
-            .unwrap_or_default();
+        let mut claims = self.bounty_claimers.get(claimer_id).unwrap_or_default();
         claims.push(BountyClaim {
             bounty_id: id,
             start_time: U64::from(env::block_timestamp()),
             deadline,
             completed: false,
         });
-        self.bounty_claimers
-            .insert(&env::predecessor_account_id(), &claims);
-        self.locked_amount += env::attached_deposit();
+        self.bounty_claimers.insert(claimer_id, &claims);
+        self.internal_emit_event(Event::BountyClaim {
+            bounty_id: id,
+            account_id: claimer_id.clone(),
+            deadline,
+        });
+        self.internal_emit_bounty_digest(id, BountyUpdate::Claimed, Some(claimer_id), &bounty);
+    }
+
+    /// Returns bond of the claim to the claimer, either in the bounty's token or in $NEAR.
+    pub(crate) fn internal_return_bounty_bond(
+        &mut self,
+        policy: &Policy,
+        bounty_id: u64,
+        claimer_id: &AccountId,
+    ) -> PromiseOrValue<()> {
+        match self
+            .bounty_token_bonds
+            .remove(&(claimer_id.clone(), bounty_id))
+        {
+            Some(bond) => {
+                self.internal_unlock_tokens(&bond.token_id, bond.amount);
+                self.internal_payout(
+                    &Some(bond.token_id),
+                    claimer_id,
+                    bond.amount,
+                    format!("Bounty {} bond", bounty_id),
+                    None,
+                )
+            }
+            None => {
+                let bond = self.internal_remove_bounty_bond(policy, bounty_id, claimer_id);
+                self.locked_amount -= bond;
+                Promise::new(claimer_id.clone()).transfer(bond).into()
+            }
+        }
+    }
+
+    /// Forfeits bond of the claim. Bond stays in the DAO's treasury and becomes available to spend.
+    pub(crate) fn internal_forfeit_bounty_bond(
+        &mut self,
+        policy: &Policy,
+        bounty_id: u64,
+        claimer_id: &AccountId,
+    ) {
+        match self
+            .bounty_token_bonds
+            .remove(&(claimer_id.clone(), bounty_id))
+        {
+            Some(bond) => self.internal_unlock_tokens(&bond.token_id, bond.amount),
+            None => {
+                self.locked_amount -=
+                    self.internal_remove_bounty_bond(policy, bounty_id, claimer_id)
+            }
+        }
+    }
+
+    /// Removes the $NEAR bond of the first claim of `claimer_id` on this bounty and returns it.
+    fn internal_remove_bounty_bond(
+        &mut self,
+        policy: &Policy,
+        bounty_id: u64,
+        claimer_id: &AccountId,
+    ) -> Balance {
+        let key = (claimer_id.clone(), bounty_id);
+        let mut bonds = self.bounty_bonds.get(&key).unwrap_or_default();
+        if bonds.is_empty() {
+            return policy.bounty_bond.0;
+        }
+        let bond = bonds.remove(0);
+        if bonds.is_empty() {
+            self.bounty_bonds.remove(&key);
+        } else {
+            self.bounty_bonds.insert(&key, &bonds);
+        }
+        bond
+    }
+
+    fn internal_find_claim(&self, bounty_id: u64, claims: &[BountyClaim]) -> Option<usize> {
+        for i in 0..claims.len() {
+            if claims[i].bounty_id == bounty_id {
+                return Some(i);
+            }
+        }
+        None
     }
 
     /// Remove the claim of `claimer_id` from this bounty.
-    fn internal_remove_claim(&mut self, bounty_id: u64, claimer_id: &AccountId) {
+    pub(crate) fn internal_remove_claim(&mut self, bounty_id: u64, claimer_id: &AccountId) {
         let (mut claims, claim_idx) = self.internal_get_claims(bounty_id, claimer_id);
         claims.remove(claim_idx);
         if claims.len() == 0 {
@@ -155,18 +304,127 @@ impl Contract {
             .expect("ERR_NO_BOUNTY_CLAIM");
         (claims, claim_idx)
     }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Claim given bounty by caller with given expected duration to execute.
+    /// Bond must be attached to the claim.
+    /// Fails if already claimed `times` times.
+    #[payable]
+    pub fn bounty_claim(&mut self, id: u64, deadline: U64) {
+        self.assert_not_blocked(&env::predecessor_account_id());
+        let policy = self.policy.get().unwrap().to_policy();
+        assert_eq!(
+            env::attached_deposit(),
+            policy.bounty_bond.0,
+            "ERR_BOUNTY_WRONG_BOND"
+        );
+        // Bonds are returned per claimer and bounty, so a token bond can't share the bounty
+        // with another claim of the same claimer.
+        assert!(
+            self.bounty_token_bonds
+                .get(&(env::predecessor_account_id(), id))
+                .is_none(),
+            "ERR_BOUNTY_ALREADY_CLAIMED"
+        );
+        self.internal_bounty_claim(id, &env::predecessor_account_id(), deadline);
+        let key = (env::predecessor_account_id(), id);
+        let mut bonds = self.bounty_bonds.get(&key).unwrap_or_default();
+        bonds.push(env::attached_deposit());
+        self.bounty_bonds.insert(&key, &bonds);
+        self.locked_amount += env::attached_deposit();
+    }
+
+    /// Claim bounty with the bond paid in the bounty's token via `ft_transfer_call`.
+    /// `msg` must be `{"bounty_id": <id>, "deadline": "<deadline>"}`.
+    /// Amount must be equal to the policy's `bounty_token_bonds` entry for this token.
+    /// If `msg` is `{"proposal": <ProposalInput>}`, adds the proposal with the bond paid in
+    /// the token instead, see `proposal_token_bonds` of the policy.
+    /// If `msg` is `{"loan_id": <id>}`, repays the loan and returns the amount exceeding it.
+    pub fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        // Blocked borrowers can still repay their loans.
+        if let Ok(repay_msg) = near_sdk::serde_json::from_str::<LoanRepayMsg>(&msg) {
+            let token_id = Some(env::predecessor_account_id());
+            let excess = self.internal_repay_loan(repay_msg.loan_id, &token_id, amount.0);
+            return PromiseOrValue::Value(U128(excess));
+        }
+        self.assert_not_blocked(&sender_id);
+        if let Ok(bond_msg) = near_sdk::serde_json::from_str::<ProposalBondMsg>(&msg) {
+            self.internal_add_proposal_with_token_bond(
+                sender_id,
+                env::predecessor_account_id(),
+                amount.0,
+                bond_msg.proposal,
+            );
+            return PromiseOrValue::Value(U128(0));
+        }
+        let claim: BountyClaimMsg =
+            near_sdk::serde_json::from_str(&msg).expect("ERR_WRONG_BOUNTY_CLAIM_MSG");
+        let bounty: Bounty = self
+            .bounties
+            .get(&claim.bounty_id)
+            .expect("ERR_NO_BOUNTY")
+            .into();
+        let token_id = env::predecessor_account_id();
+        assert_eq!(
+            convert_old_to_new_token(&bounty.token),
+            Some(token_id.clone()),
+            "ERR_BOUNTY_WRONG_BOND_TOKEN"
+        );
+        let policy = self.policy.get().unwrap().to_policy();
+        let bond = policy
+            .bounty_token_bonds
+            .get(&bounty.token)
+            .expect("ERR_BOUNTY_NO_TOKEN_BOND");
+        assert_eq!(amount.0, bond.0, "ERR_BOUNTY_WRONG_BOND");
+        let claims = self.bounty_claimers.get(&sender_id).unwrap_or_default();
+        assert!(
+            self.internal_find_claim(claim.bounty_id, &claims).is_none(),
+            "ERR_BOUNTY_ALREADY_CLAIMED"
+        );
+        let key = (sender_id.clone(), claim.bounty_id);
+        self.internal_bounty_claim(claim.bounty_id, &sender_id, claim.deadline);
+        self.internal_lock_tokens(&token_id, amount.0);
+        self.bounty_token_bonds.insert(
+            &key,
+            &BountyTokenBond {
+                token_id,
+                amount: amount.0,
+            },
+        );
+        PromiseOrValue::Value(U128(0))
+    }
 
     /// Report that bounty is done. Creates a proposal to vote for paying out the bounty.
     /// Only creator of the claim can call `done` on bounty that is still in progress.
     /// On expired, anyone can call it to free up the claim slot.
+    /// Optional `proof` is stored with the created proposal and returned with it. Its storage is
+    /// charged to the caller's storage balance, see `author_storage_deposit`.
     #[payable]
-    pub fn bounty_done(&mut self, id: u64, account_id: Option<AccountId>, description: String) {
+    pub fn bounty_done(
+        &mut self,
+        id: u64,
+        account_id: Option<AccountId>,
+        description: String,
+        proof: Option<BountyProof>,
+    ) {
         let sender_id = account_id.unwrap_or_else(|| env::predecessor_account_id());
         let (mut claims, claim_idx) = self.internal_get_claims(id, &sender_id);
         assert!(!claims[claim_idx].completed, "ERR_BOUNTY_CLAIM_COMPLETED");
         if env::block_timestamp() > claims[claim_idx].start_time.0 + claims[claim_idx].deadline.0 {
-            // Expired. Nothing to do.
+            // Expired. Bond is forfeited.
+            let policy = self.policy.get().unwrap().to_policy();
+            self.internal_forfeit_bounty_bond(&policy, id, &sender_id);
             self.internal_remove_claim(id, &sender_id);
+            let bounty: Bounty = self.bounties.get(&id).expect("ERR_NO_BOUNTY").into();
+            self.internal_emit_bounty_digest(id, BountyUpdate::Expired, Some(&sender_id), &bounty);
         } else {
             // Still under deadline. Only the user themself can call this.
             assert_eq!(
@@ -174,13 +432,33 @@ impl Contract {
                 env::predecessor_account_id(),
                 "ERR_BOUNTY_DONE_MUST_BE_SELF"
             );
-            self.add_proposal(ProposalInput {
+            let proposal_id = self.add_proposal(ProposalInput {
                 description,
                 kind: ProposalKind::BountyDone {
                     bounty_id: id,
                     receiver_id: sender_id.clone(),
                 },
             });
+            if let Some(proof) = proof {
+                assert!(
+                    proof
+                        .links
+                        .iter()
+                        .chain(proof.artifact_hashes.iter())
+                        .map(|s| s.len())
+                        .sum::<usize>()
+                        <= MAX_BOUNTY_PROOF_LEN,
+                    "ERR_BOUNTY_PROOF_TOO_LONG"
+                );
+                let initial_storage = env::storage_usage();
+                self.bounty_proofs.insert(&proposal_id, &proof);
+                self.internal_charge_storage(
+                    StorageChargeKey::Proposal(proposal_id),
+                    &sender_id,
+                    initial_storage,
+                    false,
+                );
+            }
             claims[claim_idx].completed = true;
             self.bounty_claimers.insert(&sender_id, &claims);
         }
@@ -194,22 +472,25 @@ impl Contract {
             > policy.bounty_forgiveness_period.0
         {
             // If user over the forgiveness period.
+            self.internal_forfeit_bounty_bond(&policy, id, &env::predecessor_account_id());
             PromiseOrValue::Value(())
         } else {
             // Within forgiveness period. Return bond.
-            self.locked_amount -= policy.bounty_bond.0;
-            Promise::new(env::predecessor_account_id())
-                .transfer(policy.bounty_bond.0)
-                .into()
+            self.internal_return_bounty_bond(&policy, id, &env::predecessor_account_id())
         };
         self.internal_remove_claim(id, &env::predecessor_account_id());
+        self.internal_emit_event(Event::BountyGiveup {
+            bounty_id: id,
+            account_id: env::predecessor_account_id(),
+        });
         result
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
     use near_sdk::testing_env;
     use near_sdk_sim::to_yocto;
 
@@ -263,7 +544,7 @@ mod tests {
         assert_eq!(contract.get_bounty_claims(accounts(1)).len(), 1);
         assert_eq!(contract.get_bounty_number_of_claims(0), 1);
 
-        contract.bounty_done(0, None, "Bounty is done".to_string());
+        contract.bounty_done(0, None, "Bounty is done".to_string(), None);
         assert!(contract.get_bounty_claims(accounts(1))[0].completed);
 
         assert_eq!(contract.get_last_proposal_id(), 2);
@@ -286,7 +567,21 @@ mod tests {
         assert_eq!(contract.get_bounty(0).bounty.times, 1);
 
         contract.bounty_claim(0, U64::from(500));
-        contract.bounty_done(0, None, "Bounty is done 2".to_string());
+        contract.author_storage_deposit(None);
+        contract.bounty_done(
+            0,
+            None,
+            "Bounty is done 2".to_string(),
+            Some(BountyProof {
+                links: vec!["https://example.com/pr/1".to_string()],
+                artifact_hashes: vec![],
+            }),
+        );
+        assert_eq!(contract.get_proposal(1).bounty_proof, None);
+        assert_eq!(
+            contract.get_proposal(2).bounty_proof.unwrap().links,
+            vec!["https://example.com/pr/1".to_string()]
+        );
         contract.act_proposal(2, Action::VoteApprove, None);
         testing_env!(
             context.build(),
@@ -300,6 +595,309 @@ mod tests {
         assert_eq!(contract.get_bounty(0).bounty.times, 0);
     }
 
+    #[test]
+    #[should_panic(expected = "ERR_BOUNTY_PROOF_TOO_LONG")]
+    fn test_bounty_proof_too_long() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        contract.author_storage_deposit(None);
+        contract.bounty_done(
+            id,
+            None,
+            "Bounty is done".to_string(),
+            Some(BountyProof {
+                links: vec!["a".repeat(MAX_BOUNTY_PROOF_LEN)],
+                artifact_hashes: vec!["b".to_string()],
+            }),
+        );
+    }
+
+    /// Completes the bounty of `accounts(1)` and runs the payout callback.
+    fn complete_bounty(context: &mut VMContextBuilder, contract: &mut Contract, id: u64) {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_done(id, None, "Bounty is done".to_string(), None);
+        let proposal_id = contract.get_last_proposal_id() - 1;
+        contract.act_proposal(proposal_id, Action::VoteApprove, None);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(proposal_id);
+    }
+
+    #[test]
+    fn test_bounty_pool_renewal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 1);
+        let proposal_id = contract.add_proposal(ProposalInput {
+            description: "pool".to_string(),
+            kind: ProposalKind::SetBountyPool {
+                bounty_id: id,
+                budget: U128(to_yocto("15")),
+            },
+        });
+        contract.act_proposal(proposal_id, Action::VoteApprove, None);
+
+        complete_bounty(&mut context, &mut contract, id);
+        assert_eq!(contract.get_bounty(id).bounty.times, 1);
+        assert_eq!(contract.get_bounty_pool(id), Some(U128(to_yocto("5"))));
+
+        complete_bounty(&mut context, &mut contract, id);
+        assert_eq!(contract.get_bounty(id).bounty.times, 0);
+        assert_eq!(contract.get_bounty_pool(id), Some(U128(to_yocto("5"))));
+    }
+
+    /// Claims bounty with the bond paid in the bounty's token and gives up.
+    #[test]
+    fn test_bounty_token_bond() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy
+            .bounty_token_bonds
+            .insert(accounts(3).to_string(), U128(100));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: accounts(3).to_string(),
+                    amount: U128(1_000),
+                    times: 1,
+                    max_deadline: U64::from(1_000),
+                },
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let locked_amount = contract.locked_amount;
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_on_transfer(
+            accounts(1),
+            U128(100),
+            "{\"bounty_id\": 0, \"deadline\": \"500\"}".to_string(),
+        );
+        assert_eq!(contract.get_bounty_number_of_claims(0), 1);
+        assert_eq!(contract.locked_amount, locked_amount);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.bounty_giveup(0);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+        assert!(contract.bounty_token_bonds.get(&(accounts(1), 0)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BOUNTY_ALREADY_CLAIMED")]
+    fn test_bounty_token_bond_second_claim() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy
+            .bounty_token_bonds
+            .insert(accounts(3).to_string(), U128(100));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddBounty {
+                bounty: Bounty {
+                    description: "test bounty".to_string(),
+                    token: accounts(3).to_string(),
+                    amount: U128(1_000),
+                    times: 2,
+                    max_deadline: U64::from(1_000),
+                },
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract.bounty_claim(0, U64::from(500));
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.ft_on_transfer(
+            accounts(1),
+            U128(100),
+            "{\"bounty_id\": 0, \"deadline\": \"500\"}".to_string(),
+        );
+    }
+
+    #[test]
+    fn test_bounty_digest() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        add_bounty(&mut context, &mut contract, 1);
+        assert!(near_sdk::test_utils::get_logs()
+            .iter()
+            .any(|log| log.contains(
+                "\"event\":\"bounty_digest\",\"data\":{\"bounty_id\":0,\"update\":\"Added\""
+            ) && log.contains("\"description\":\"test bounty\"")));
+
+        contract.bounty_claim(0, U64::from(500));
+        testing_env!(context.block_timestamp(1_000).build());
+        contract.bounty_done(0, None, "Too late".to_string(), None);
+        assert!(near_sdk::test_utils::get_logs()
+            .iter()
+            .any(|log| log.contains("\"update\":\"Expired\",\"account_id\":\"bob\"")));
+    }
+
+    #[test]
+    fn test_bounty_bond_after_policy_change() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(&mut context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        let locked_amount = contract.locked_amount;
+
+        // The bond locked at claim is returned, not the one currently required.
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.bounty_bond = U128(to_yocto("5"));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract.bounty_giveup(id);
+        assert_eq!(contract.locked_amount, locked_amount - to_yocto("1"));
+        assert!(contract.bounty_bonds.get(&(accounts(1), id)).is_none());
+        let refunded = get_created_receipts().into_iter().any(|receipt| {
+            receipt.receiver_id == accounts(1)
+                && matches!(receipt.actions[0], VmAction::Transfer { deposit } if deposit == to_yocto("1"))
+        });
+        assert!(refunded);
+    }
+
+    /// Claims bounty and reports it done, returns id of the `BountyDone` proposal.
+    fn setup_bounty_done(context: &mut VMContextBuilder) -> (Contract, u64) {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = add_bounty(context, &mut contract, 1);
+        contract.bounty_claim(id, U64::from(500));
+        contract.bounty_done(id, None, "Bounty is done".to_string(), None);
+        let proposal_id = contract.get_last_proposal_id() - 1;
+        (contract, proposal_id)
+    }
+
+    #[test]
+    fn test_bounty_done_rejected_or_removed() {
+        for (action, returned) in [
+            (Action::VoteReject, true),
+            (Action::VoteRemove, true),
+            (Action::FlagSpam, false),
+        ] {
+            let mut context = VMContextBuilder::new();
+            let (mut contract, proposal_id) = setup_bounty_done(&mut context);
+            let locked_amount = contract.locked_amount;
+            let refunds = || {
+                get_created_receipts()
+                    .into_iter()
+                    .filter(|receipt| {
+                        receipt.receiver_id == accounts(1)
+                            && matches!(receipt.actions[0], VmAction::Transfer { .. })
+                    })
+                    .count()
+            };
+            let initial_refunds = refunds();
+            contract.act_proposal(proposal_id, action, None);
+            assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+            assert!(contract.get_bounty_claims(accounts(1)).is_empty());
+            assert_eq!(contract.get_bounty(0).bounty.times, 1);
+            // Proposal bond and claim bond are returned together, or both forfeited to the treasury.
+            assert_eq!(contract.locked_amount, locked_amount - to_yocto("2"));
+            assert_eq!(refunds() == initial_refunds + 2, returned);
+        }
+    }
+
+    #[test]
+    fn test_bounty_done_expired() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, proposal_id) = setup_bounty_done(&mut context);
+        testing_env!(context
+            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+            .build());
+        contract.act_proposal(proposal_id, Action::Finalize, None);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+        assert!(contract.get_bounty_claims(accounts(1)).is_empty());
+    }
+
+    #[test]
+    fn test_bounty_done_removed_by_admin() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, proposal_id) = setup_bounty_done(&mut context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.roles[1]
+            .permissions
+            .insert("*:RemoveProposal".to_string());
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract.act_proposal(proposal_id, Action::RemoveProposal, None);
+        assert_eq!(contract.get_bounty_number_of_claims(0), 0);
+        assert!(contract.get_bounty_claims(accounts(1)).is_empty());
+    }
+
+    #[test]
+    fn test_bounty_done_failed_payout() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, proposal_id) = setup_bounty_done(&mut context);
+        contract.act_proposal(proposal_id, Action::VoteApprove, None);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_proposal_callback(proposal_id);
+        assert_eq!(
+            contract.get_proposal(proposal_id).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert!(contract.get_bounty_claims(accounts(1))[0].completed);
+
+        // Retry the payout.
+        testing_env!(context.build());
+        contract.act_proposal(proposal_id, Action::Finalize, None);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(proposal_id);
+        assert!(contract.get_bounty_claims(accounts(1)).is_empty());
+        assert_eq!(contract.get_bounty(0).bounty.times, 0);
+    }
+
     #[test]
     #[should_panic(expected = "ERR_BOUNTY_ALL_CLAIMED")]
     fn test_bounty_claim_not_allowed() {
@@ -311,7 +909,7 @@ mod tests {
         );
         let id = add_bounty(&mut context, &mut contract, 1);
         contract.bounty_claim(id, U64::from(500));
-        contract.bounty_done(id, None, "Bounty is done 2".to_string());
+        contract.bounty_done(id, None, "Bounty is done 2".to_string(), None);
         contract.bounty_claim(id, U64::from(500));
     }
 }
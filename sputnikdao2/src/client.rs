@@ -0,0 +1,113 @@
+//! Method names and argument / return types of the DAO for Rust clients, e.g. bots and
+//! integration tests in other repositories. Enabled with the `client` feature.
+//!
+//! Contract entry points are left out with the `client` feature, so crates using this module,
+//! including contracts built for `wasm32`, don't include or export contract code.
+
+pub use crate::consts::*;
+pub use crate::{
+    author_commitment, ActProposalResult, Action, ActionCall, AnonymousAuthor, AuditReport,
+    AuditSection, Bounty, BountyClaim, BountyOutput, BountyProof, Config, DelegationOutput,
+    FundingSource, GasConfig, Grant, GrantInput, GrantMilestone, GrantMilestoneInput,
+    GrantMilestoneStatus, GrantOutput, GrantStatus, Loan, LoanStatus, MembershipChange, MirrorLink,
+    OffchainTally, Policy, PolicyBuilder, PolicyError, PolicyV1, PolicyVocabulary,
+    ProposalHashOutput, ProposalInput, ProposalKind, ProposalOutput, ProposalOutputV1,
+    ProposalOverrides, ProposalStatus, ProposalTokenBond, RagequitPolicy, RecurringVote, RoleKind,
+    RolePermission, RolePermissionV1, SpendLimits, StateChunk, StateChunkData, StateSection,
+    TransferTier, Translations, TrustedTransferRule, VersionedPolicy, VoteMirror, VoteOption,
+    VoteOptions, VotePolicy, VotePolicyV1, WeightOrRatio, OLD_BASE_TOKEN,
+};
+
+// Change methods.
+pub const NEW: &str = "new";
+pub const MIGRATE: &str = "migrate";
+pub const STORE_BLOB: &str = "store_blob";
+pub const REMOVE_BLOB: &str = "remove_blob";
+pub const ADD_PROPOSAL: &str = "add_proposal";
+pub const ADD_PROPOSAL_FROM_FACTORY: &str = "add_proposal_from_factory";
+pub const PROPOSE_WITH_DEPOSIT: &str = "propose_with_deposit";
+pub const CLONE_PROPOSAL: &str = "clone_proposal";
+pub const ADD_LOCALIZED_PROPOSAL: &str = "add_localized_proposal";
+pub const ACT_PROPOSAL: &str = "act_proposal";
+pub const ACT_PROPOSAL_AS_ALTERNATE: &str = "act_proposal_as_alternate";
+pub const ACT_PROPOSAL_WITH_OPTION: &str = "act_proposal_with_option";
+pub const ADD_VOTE_WITH_OPTIONS: &str = "add_vote_with_options";
+pub const RECLAIM_EXPIRED_BONDS: &str = "reclaim_expired_bonds";
+pub const RECOUNT_ACTIVE: &str = "recount_active";
+pub const SET_ALTERNATE: &str = "set_alternate";
+pub const BOUNTY_CLAIM: &str = "bounty_claim";
+pub const BOUNTY_DONE: &str = "bounty_done";
+pub const BOUNTY_GIVEUP: &str = "bounty_giveup";
+pub const FT_ON_TRANSFER: &str = "ft_on_transfer";
+pub const REGISTER_DELEGATION: &str = "register_delegation";
+pub const DELEGATE: &str = "delegate";
+pub const UNDELEGATE: &str = "undelegate";
+pub const RAGEQUIT: &str = "ragequit";
+pub const GRANT_APPLY: &str = "grant_apply";
+pub const GRANT_REVIEW: &str = "grant_review";
+pub const GRANT_MILESTONE_DONE: &str = "grant_milestone_done";
+pub const REGISTER_FULL_ACCESS_KEY: &str = "register_full_access_key";
+pub const CONTINUE_ADD_MEMBERS: &str = "continue_add_members";
+pub const CONTINUE_MIGRATE_TREASURY: &str = "continue_migrate_treasury";
+pub const SYNC_MIRROR: &str = "sync_mirror";
+pub const POST_OFFCHAIN_TALLY: &str = "post_offchain_tally";
+pub const TRIGGER_RECURRING_VOTE: &str = "trigger_recurring_vote";
+pub const AUTHOR_STORAGE_DEPOSIT: &str = "author_storage_deposit";
+pub const AUTHOR_STORAGE_WITHDRAW: &str = "author_storage_withdraw";
+pub const LOAN_REPAY: &str = "loan_repay";
+pub const LOAN_CHECK_OVERDUE: &str = "loan_check_overdue";
+pub const ADD_ANONYMOUS_PROPOSAL: &str = "add_anonymous_proposal";
+pub const REVEAL_PROPOSAL_AUTHOR: &str = "reveal_proposal_author";
+
+// View methods.
+pub const VERSION: &str = "version";
+pub const GET_CONFIG: &str = "get_config";
+pub const GET_POLICY: &str = "get_policy";
+pub const GET_POLICY_V1: &str = "get_policy_v1";
+pub const GET_POLICY_VOCABULARY: &str = "get_policy_vocabulary";
+pub const GET_STAKING_CONTRACT: &str = "get_staking_contract";
+pub const GET_FACTORY_INFO: &str = "get_factory_info";
+pub const HAS_BLOB: &str = "has_blob";
+pub const GET_BLOB_REFERENCES: &str = "get_blob_references";
+pub const GET_LOCKED_STORAGE_AMOUNT: &str = "get_locked_storage_amount";
+pub const GET_AVAILABLE_AMOUNT: &str = "get_available_amount";
+pub const DELEGATION_TOTAL_SUPPLY: &str = "delegation_total_supply";
+pub const DELEGATION_BALANCE_OF: &str = "delegation_balance_of";
+pub const DELEGATION_BALANCE_RATIO: &str = "delegation_balance_ratio";
+pub const GET_RECEIVER_PAYOUTS: &str = "get_receiver_payouts";
+pub const GET_LAST_PROPOSAL_ID: &str = "get_last_proposal_id";
+pub const GET_ACTIVE_PROPOSAL_COUNT: &str = "get_active_proposal_count";
+pub const GET_PROPOSALS: &str = "get_proposals";
+pub const GET_PROPOSAL: &str = "get_proposal";
+pub const GET_PROPOSALS_V1: &str = "get_proposals_v1";
+pub const GET_PROPOSAL_V1: &str = "get_proposal_v1";
+pub const HASH_PROPOSAL: &str = "hash_proposal";
+pub const GET_ALTERNATE: &str = "get_alternate";
+pub const GET_LAST_EVENT_SEQ: &str = "get_last_event_seq";
+pub const GET_LAST_BOUNTY_ID: &str = "get_last_bounty_id";
+pub const GET_BOUNTY: &str = "get_bounty";
+pub const GET_BOUNTIES: &str = "get_bounties";
+pub const GET_BOUNTY_POOL: &str = "get_bounty_pool";
+pub const GET_BOUNTY_CLAIMS: &str = "get_bounty_claims";
+pub const GET_BOUNTY_NUMBER_OF_CLAIMS: &str = "get_bounty_number_of_claims";
+pub const GET_OPEN_BOUNTY_COUNT: &str = "get_open_bounty_count";
+pub const GET_LAST_GRANT_ID: &str = "get_last_grant_id";
+pub const GET_GRANT: &str = "get_grant";
+pub const GET_GRANTS: &str = "get_grants";
+pub const IS_BLOCKED: &str = "is_blocked";
+pub const GET_BLOCKLIST: &str = "get_blocklist";
+pub const GET_FULL_ACCESS_KEYS: &str = "get_full_access_keys";
+pub const GET_MEMBERSHIP_HISTORY: &str = "get_membership_history";
+pub const GET_PENDING_MEMBERS_INDEX: &str = "get_pending_members_index";
+pub const GET_PENDING_MIGRATION_INDEX: &str = "get_pending_migration_index";
+pub const GET_VOTE_MIRROR: &str = "get_vote_mirror";
+pub const GET_VOTE_OPTION_SELECTION: &str = "get_vote_option_selection";
+pub const GET_GAS_CONFIG: &str = "get_gas_config";
+pub const GET_OFFCHAIN_TALLY: &str = "get_offchain_tally";
+pub const GET_RECURRING_VOTE: &str = "get_recurring_vote";
+pub const GET_LOAN: &str = "get_loan";
+pub const GET_ANONYMOUS_AUTHOR: &str = "get_anonymous_author";
+pub const VERIFY_OFFCHAIN_TALLY: &str = "verify_offchain_tally";
+pub const EXPORT_STATE_CHUNK: &str = "export_state_chunk";
+pub const RUN_SELF_AUDIT: &str = "run_self_audit";
+pub const STORAGE_BALANCE_OF_AUTHOR: &str = "storage_balance_of_author";
@@ -0,0 +1,123 @@
+//! Resubmission of existing proposals with selective changes.
+
+use near_sdk::json_types::U128;
+
+use crate::*;
+
+/// Fields to change when cloning a proposal. Fields not set are copied from the original.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalOverrides {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub kind: Option<ProposalKind>,
+    /// Amount of `Transfer` or `AddBounty` kinds, applied after `kind`.
+    #[serde(default)]
+    pub amount: Option<U128>,
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Adds a new proposal copied from given proposal in any status, with `overrides` applied.
+    /// Requires the same deposit as `add_proposal`. Returns id of the new proposal.
+    #[payable]
+    pub fn clone_proposal(&mut self, id: u64, overrides: ProposalOverrides) -> u64 {
+        let original: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let mut proposal = ProposalInput {
+            description: overrides.description.unwrap_or(original.description),
+            kind: overrides.kind.unwrap_or(original.kind),
+        };
+        if let Some(amount) = overrides.amount {
+            let value = match &mut proposal.kind {
+                ProposalKind::Transfer { amount, .. } => Some(amount),
+                ProposalKind::AddBounty { bounty } => Some(&mut bounty.amount),
+                _ => None,
+            }
+            .expect("ERR_NO_AMOUNT_TO_OVERRIDE");
+            *value = amount;
+        }
+        let new_id = self.add_proposal(proposal);
+        self.cloned_from.insert(&new_id, &id);
+        new_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config, OLD_BASE_TOKEN};
+
+    use super::*;
+
+    #[test]
+    fn test_clone_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "pay bob".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("100")),
+                msg: None,
+            },
+        });
+        contract.act_proposal(id, Action::VoteReject, None);
+
+        let new_id = contract.clone_proposal(
+            id,
+            ProposalOverrides {
+                amount: Some(U128(to_yocto("10"))),
+                ..Default::default()
+            },
+        );
+        let output = contract.get_proposal(new_id);
+        assert_eq!(output.cloned_from, Some(id));
+        assert_eq!(output.proposal.description, "pay bob");
+        assert_eq!(output.proposal.status, ProposalStatus::InProgress);
+        match output.proposal.kind {
+            ProposalKind::Transfer {
+                receiver_id,
+                amount,
+                ..
+            } => {
+                assert_eq!(receiver_id, accounts(2));
+                assert_eq!(amount, U128(to_yocto("10")));
+            }
+            _ => panic!("wrong kind"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_AMOUNT_TO_OVERRIDE")]
+    fn test_clone_proposal_wrong_kind() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "poll".to_string(),
+            kind: ProposalKind::Vote,
+        });
+        contract.clone_proposal(
+            id,
+            ProposalOverrides {
+                amount: Some(U128(1)),
+                ..Default::default()
+            },
+        );
+    }
+}
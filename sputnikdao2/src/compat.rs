@@ -0,0 +1,298 @@
+//! Views in the JSON shapes of the v2.0 release, for deployed frontends that don't accept fields
+//! added since. Proposal kinds added since are rendered as is.
+
+use std::collections::{HashMap, HashSet};
+
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+
+use crate::policy::WeightKind;
+use crate::proposals::{ActionCall, Vote};
+use crate::*;
+
+/// `VotePolicy` without `min_voters`.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VotePolicyV1 {
+    pub weight_kind: WeightKind,
+    pub quorum: U128,
+    pub threshold: WeightOrRatio,
+}
+
+impl From<&VotePolicy> for VotePolicyV1 {
+    fn from(vote_policy: &VotePolicy) -> Self {
+        Self {
+            weight_kind: vote_policy.weight_kind.clone(),
+            quorum: vote_policy.quorum,
+            threshold: vote_policy.threshold.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RolePermissionV1 {
+    pub name: String,
+    pub kind: RoleKind,
+    pub permissions: HashSet<String>,
+    pub vote_policy: HashMap<String, VotePolicyV1>,
+}
+
+impl From<&RolePermission> for RolePermissionV1 {
+    fn from(role: &RolePermission) -> Self {
+        Self {
+            name: role.name.clone(),
+            kind: role.kind.clone(),
+            permissions: role.permissions.clone(),
+            vote_policy: role
+                .vote_policy
+                .iter()
+                .map(|(label, vote_policy)| (label.clone(), vote_policy.into()))
+                .collect(),
+        }
+    }
+}
+
+/// `Policy` with only the parameters of the v2.0 release.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PolicyV1 {
+    pub roles: Vec<RolePermissionV1>,
+    pub default_vote_policy: VotePolicyV1,
+    pub proposal_bond: U128,
+    pub proposal_period: U64,
+    pub bounty_bond: U128,
+    pub bounty_forgiveness_period: U64,
+}
+
+impl From<&Policy> for PolicyV1 {
+    fn from(policy: &Policy) -> Self {
+        Self {
+            roles: policy.roles.iter().map(Into::into).collect(),
+            default_vote_policy: (&policy.default_vote_policy).into(),
+            proposal_bond: policy.proposal_bond,
+            proposal_period: policy.proposal_period,
+            bounty_bond: policy.bounty_bond,
+            bounty_forgiveness_period: policy.bounty_forgiveness_period,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde", untagged)]
+pub enum VersionedPolicyV1 {
+    Default(Vec<AccountId>),
+    Current(PolicyV1),
+}
+
+/// `ActionCall` without `funding_source`.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActionCallV1 {
+    pub method_name: String,
+    pub args: Base64VecU8,
+    pub deposit: U128,
+    pub gas: U64,
+}
+
+impl From<&ActionCall> for ActionCallV1 {
+    fn from(action: &ActionCall) -> Self {
+        Self {
+            method_name: action.method_name.clone(),
+            args: action.args.clone(),
+            deposit: action.deposit,
+            gas: action.gas,
+        }
+    }
+}
+
+/// Proposal kinds of the v2.0 release whose shape changed since.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ChangedProposalKindV1 {
+    ChangePolicy {
+        policy: VersionedPolicyV1,
+    },
+    FunctionCall {
+        receiver_id: AccountId,
+        actions: Vec<ActionCallV1>,
+    },
+    ChangePolicyAddOrUpdateRole {
+        role: RolePermissionV1,
+    },
+    ChangePolicyUpdateDefaultVotePolicy {
+        vote_policy: VotePolicyV1,
+    },
+}
+
+/// `ProposalKind` with the kinds of the v2.0 release in their v2.0 shape.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde", untagged)]
+#[allow(clippy::large_enum_variant)]
+pub enum ProposalKindV1 {
+    Changed(ChangedProposalKindV1),
+    Unchanged(ProposalKind),
+}
+
+impl From<ProposalKind> for ProposalKindV1 {
+    fn from(kind: ProposalKind) -> Self {
+        let changed = match &kind {
+            ProposalKind::ChangePolicy { policy } => ChangedProposalKindV1::ChangePolicy {
+                policy: match policy {
+                    VersionedPolicy::Default(accounts) => {
+                        VersionedPolicyV1::Default(accounts.clone())
+                    }
+                    VersionedPolicy::Current(policy) => VersionedPolicyV1::Current(policy.into()),
+                },
+            },
+            ProposalKind::FunctionCall {
+                receiver_id,
+                actions,
+            } => ChangedProposalKindV1::FunctionCall {
+                receiver_id: receiver_id.clone(),
+                actions: actions.iter().map(Into::into).collect(),
+            },
+            ProposalKind::ChangePolicyAddOrUpdateRole { role } => {
+                ChangedProposalKindV1::ChangePolicyAddOrUpdateRole { role: role.into() }
+            }
+            ProposalKind::ChangePolicyUpdateDefaultVotePolicy { vote_policy } => {
+                ChangedProposalKindV1::ChangePolicyUpdateDefaultVotePolicy {
+                    vote_policy: vote_policy.into(),
+                }
+            }
+            _ => return ProposalKindV1::Unchanged(kind),
+        };
+        ProposalKindV1::Changed(changed)
+    }
+}
+
+/// `ProposalOutput` without the fields added since the v2.0 release.
+/// `FlagSpam` votes are left out of `votes`, they are not counted in `vote_counts` either.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalOutputV1 {
+    pub id: u64,
+    pub proposer: AccountId,
+    pub description: String,
+    pub kind: ProposalKindV1,
+    pub status: ProposalStatus,
+    pub vote_counts: HashMap<String, [Balance; 3]>,
+    pub votes: HashMap<AccountId, Vote>,
+    pub submission_time: U64,
+}
+
+impl ProposalOutputV1 {
+    fn new(id: u64, proposal: VersionedProposal) -> Self {
+        let mut proposal: Proposal = proposal.into();
+        proposal.votes.retain(|_, vote| !matches!(vote, Vote::Spam));
+        Self {
+            id,
+            proposer: proposal.proposer,
+            description: proposal.description,
+            kind: proposal.kind.into(),
+            status: proposal.status,
+            vote_counts: proposal.vote_counts,
+            votes: proposal.votes,
+            submission_time: proposal.submission_time,
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Policy in the shape of `get_policy` of the v2.0 release.
+    pub fn get_policy_v1(&self) -> PolicyV1 {
+        (&self.policy.get().unwrap().to_policy()).into()
+    }
+
+    /// Proposal in the shape of `get_proposal` of the v2.0 release.
+    pub fn get_proposal_v1(&self, id: u64) -> ProposalOutputV1 {
+        ProposalOutputV1::new(id, self.proposals.get(&id).expect("ERR_NO_PROPOSAL"))
+    }
+
+    /// Proposals in the shape of `get_proposals` of the v2.0 release.
+    pub fn get_proposals_v1(&self, from_index: u64, limit: u64) -> Vec<ProposalOutputV1> {
+        (from_index..std::cmp::min(self.last_proposal_id, from_index + limit))
+            .filter_map(|id| {
+                self.proposals
+                    .get(&id)
+                    .map(|proposal| ProposalOutputV1::new(id, proposal))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::serde_json::{json, to_value};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    #[test]
+    fn test_views_v1() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let policy = to_value(contract.get_policy_v1()).unwrap();
+        assert_eq!(
+            policy["default_vote_policy"],
+            json!({"weight_kind": "RoleWeight", "quorum": "0", "threshold": [1, 2]})
+        );
+        assert!(policy.get("vote_rate_limits").is_none());
+
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.act_proposal(id, Action::FlagSpam, None);
+        assert_eq!(contract.get_proposal(id).proposal.votes.len(), 1);
+        let proposal = to_value(contract.get_proposal_v1(id)).unwrap();
+        assert_eq!(proposal["votes"], json!({}));
+        assert!(proposal.get("spam_flags").is_none());
+        assert_eq!(proposal["kind"], json!("Vote"));
+        assert_eq!(contract.get_proposals_v1(0, 10).len(), 1);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "call".to_string(),
+            kind: ProposalKind::FunctionCall {
+                receiver_id: accounts(3),
+                actions: vec![ActionCall {
+                    method_name: "ping".to_string(),
+                    args: Base64VecU8(vec![]),
+                    deposit: U128(0),
+                    gas: U64(10),
+                    funding_source: Default::default(),
+                }],
+            },
+        });
+        let proposal = to_value(contract.get_proposal_v1(id)).unwrap();
+        assert_eq!(
+            proposal["kind"],
+            json!({"FunctionCall": {"receiver_id": accounts(3), "actions": [
+                {"method_name": "ping", "args": "", "deposit": "0", "gas": "10"}
+            ]}})
+        );
+    }
+}
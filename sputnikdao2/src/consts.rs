@@ -0,0 +1,149 @@
+//! Canonical labels and permission strings for building policies programmatically.
+
+pub use crate::policy::default_policy;
+
+/// Matches any proposal kind or any action in a permission.
+pub const WILDCARD: &str = "*";
+
+/// Role of the default policy that everyone belongs to.
+pub const ROLE_ALL: &str = "all";
+/// Role of the default policy with the council members.
+pub const ROLE_COUNCIL: &str = "council";
+
+// Proposal kind labels, as returned by `ProposalKind::to_policy_label`.
+pub const KIND_CONFIG: &str = "config";
+pub const KIND_POLICY: &str = "policy";
+pub const KIND_ADD_MEMBER_TO_ROLE: &str = "add_member_to_role";
+pub const KIND_REMOVE_MEMBER_FROM_ROLE: &str = "remove_member_from_role";
+pub const KIND_CALL: &str = "call";
+pub const KIND_UPGRADE_SELF: &str = "upgrade_self";
+pub const KIND_UPGRADE_REMOTE: &str = "upgrade_remote";
+pub const KIND_TRANSFER: &str = "transfer";
+pub const KIND_SET_VOTE_TOKEN: &str = "set_vote_token";
+pub const KIND_ADD_BOUNTY: &str = "add_bounty";
+pub const KIND_BOUNTY_DONE: &str = "bounty_done";
+pub const KIND_VOTE: &str = "vote";
+pub const KIND_FACTORY_INFO_UPDATE: &str = "factory_info_update";
+pub const KIND_POLICY_ADD_OR_UPDATE_ROLE: &str = "policy_add_or_update_role";
+pub const KIND_POLICY_REMOVE_ROLE: &str = "policy_remove_role";
+pub const KIND_POLICY_UPDATE_DEFAULT_VOTE_POLICY: &str = "policy_update_default_vote_policy";
+pub const KIND_POLICY_UPDATE_PARAMETERS: &str = "policy_update_parameters";
+pub const KIND_APPROVE_GRANT: &str = "approve_grant";
+pub const KIND_GRANT_MILESTONE_DONE: &str = "grant_milestone_done";
+pub const KIND_SET_AVATAR_NFT: &str = "set_avatar_nft";
+pub const KIND_POLICY_CHANGE_ROLE_VOTE_POLICY: &str = "policy_change_role_vote_policy";
+pub const KIND_SET_VOTE_MIRROR: &str = "set_vote_mirror";
+pub const KIND_UPDATE_BLOCKLIST: &str = "update_blocklist";
+pub const KIND_LOCKDOWN: &str = "lockdown";
+pub const KIND_ADD_MEMBERS_TO_ROLE: &str = "add_members_to_role";
+pub const KIND_SET_GAS_CONFIG: &str = "set_gas_config";
+pub const KIND_SET_BOUNTY_POOL: &str = "set_bounty_pool";
+pub const KIND_MIGRATE_TREASURY: &str = "migrate_treasury";
+pub const KIND_ADD_RECURRING_VOTE: &str = "add_recurring_vote";
+pub const KIND_REPLACE_ROLE_MEMBERS: &str = "replace_role_members";
+pub const KIND_APPROVE_LOAN: &str = "approve_loan";
+pub const KIND_LOAN_DEFAULT: &str = "loan_default";
+pub const KIND_REMOVE_RECURRING_VOTE: &str = "remove_recurring_vote";
+pub const KIND_DEPLOY_EXECUTOR: &str = "deploy_executor";
+
+/// All proposal kind labels.
+pub const PROPOSAL_KIND_LABELS: &[&str] = &[
+    KIND_CONFIG,
+    KIND_POLICY,
+    KIND_ADD_MEMBER_TO_ROLE,
+    KIND_REMOVE_MEMBER_FROM_ROLE,
+    KIND_CALL,
+    KIND_UPGRADE_SELF,
+    KIND_UPGRADE_REMOTE,
+    KIND_TRANSFER,
+    KIND_SET_VOTE_TOKEN,
+    KIND_ADD_BOUNTY,
+    KIND_BOUNTY_DONE,
+    KIND_VOTE,
+    KIND_FACTORY_INFO_UPDATE,
+    KIND_POLICY_ADD_OR_UPDATE_ROLE,
+    KIND_POLICY_REMOVE_ROLE,
+    KIND_POLICY_UPDATE_DEFAULT_VOTE_POLICY,
+    KIND_POLICY_UPDATE_PARAMETERS,
+    KIND_APPROVE_GRANT,
+    KIND_GRANT_MILESTONE_DONE,
+    KIND_SET_AVATAR_NFT,
+    KIND_POLICY_CHANGE_ROLE_VOTE_POLICY,
+    KIND_SET_VOTE_MIRROR,
+    KIND_UPDATE_BLOCKLIST,
+    KIND_LOCKDOWN,
+    KIND_ADD_MEMBERS_TO_ROLE,
+    KIND_SET_GAS_CONFIG,
+    KIND_SET_BOUNTY_POOL,
+    KIND_MIGRATE_TREASURY,
+    KIND_ADD_RECURRING_VOTE,
+    KIND_REPLACE_ROLE_MEMBERS,
+    KIND_APPROVE_LOAN,
+    KIND_LOAN_DEFAULT,
+    KIND_REMOVE_RECURRING_VOTE,
+    KIND_DEPLOY_EXECUTOR,
+];
+
+/// Proposal kinds that can be decided by off-chain votes, if listed in the policy's
+/// `offchain_tally_kinds`. Never kinds that change the policy or spend from the treasury.
+pub const OFFCHAIN_TALLY_KINDS: &[&str] = &[
+    KIND_CONFIG,
+    KIND_VOTE,
+    KIND_SET_AVATAR_NFT,
+    KIND_SET_VOTE_MIRROR,
+    KIND_LOAN_DEFAULT,
+];
+
+// Action labels, as returned by `Action::to_policy_label`.
+pub const ACTION_ADD_PROPOSAL: &str = "AddProposal";
+pub const ACTION_REMOVE_PROPOSAL: &str = "RemoveProposal";
+pub const ACTION_VOTE_APPROVE: &str = "VoteApprove";
+pub const ACTION_VOTE_REJECT: &str = "VoteReject";
+pub const ACTION_VOTE_REMOVE: &str = "VoteRemove";
+pub const ACTION_FINALIZE: &str = "Finalize";
+pub const ACTION_MOVE_TO_HUB: &str = "MoveToHub";
+pub const ACTION_FLAG_SPAM: &str = "FlagSpam";
+
+/// All action labels.
+pub const ACTION_LABELS: &[&str] = &[
+    ACTION_ADD_PROPOSAL,
+    ACTION_REMOVE_PROPOSAL,
+    ACTION_VOTE_APPROVE,
+    ACTION_VOTE_REJECT,
+    ACTION_VOTE_REMOVE,
+    ACTION_FINALIZE,
+    ACTION_MOVE_TO_HUB,
+    ACTION_FLAG_SPAM,
+];
+
+/// Permission string for given proposal kind and action labels, e.g. `transfer:VoteApprove`.
+/// Either can be `WILDCARD`.
+pub fn permission(kind_label: &str, action_label: &str) -> String {
+    format!("{}:{}", kind_label, action_label)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Action;
+
+    #[test]
+    fn test_action_labels() {
+        let actions = [
+            Action::AddProposal,
+            Action::RemoveProposal,
+            Action::VoteApprove,
+            Action::VoteReject,
+            Action::VoteRemove,
+            Action::Finalize,
+            Action::MoveToHub,
+            Action::FlagSpam,
+        ];
+        let labels: Vec<String> = actions.iter().map(|a| a.to_policy_label()).collect();
+        assert_eq!(labels, ACTION_LABELS);
+        assert_eq!(
+            permission(WILDCARD, ACTION_VOTE_APPROVE),
+            "*:VoteApprove".to_string()
+        );
+    }
+}
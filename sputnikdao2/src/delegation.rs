@@ -1,23 +1,43 @@
+use near_sdk::StorageUsage;
+
+use crate::events::Event;
 use crate::*;
 
+/// Storage paid by the staking contract to register a delegate: the delegated amount and the
+/// entry in `delegation_accounts`, which stores the account id and its index twice.
+pub const REGISTER_DELEGATION_STORAGE: StorageUsage = 16 + 2 * (64 + 8);
+
 impl Contract {
     pub fn get_user_weight(&self, account_id: &AccountId) -> Balance {
         self.delegations.get(account_id).unwrap_or_default()
     }
 }
 
+#[cfg(not(feature = "client"))]
 #[near_bindgen]
 impl Contract {
+    /// Registers given account to receive delegations. Returns false if it was already
+    /// registered: it keeps its delegated amount and the deposit is refunded, so the staking
+    /// contract can safely retry registrations.
     #[payable]
-    pub fn register_delegation(&mut self, account_id: &AccountId) {
+    pub fn register_delegation(&mut self, account_id: &AccountId) -> bool {
         let staking_id = self.staking_id.clone().expect("ERR_NO_STAKING");
         assert_eq!(
             env::predecessor_account_id(),
             staking_id,
             "ERR_INVALID_CALLER"
         );
-        assert_eq!(env::attached_deposit(), 16 * env::storage_byte_cost());
+        assert_eq!(
+            env::attached_deposit(),
+            REGISTER_DELEGATION_STORAGE as Balance * env::storage_byte_cost()
+        );
+        if self.delegations.contains_key(account_id) {
+            Promise::new(staking_id).transfer(env::attached_deposit());
+            return false;
+        }
         self.delegations.insert(account_id, &0);
+        self.delegation_accounts.insert(account_id);
+        true
     }
 
     /// Adds given amount to given account as delegated weight.
@@ -36,6 +56,14 @@ impl Contract {
         let new_amount = prev_amount + amount.0;
         self.delegations.insert(account_id, &new_amount);
         self.total_delegation_amount += amount.0;
+        if self.policy.get().unwrap().to_policy().ragequit.is_some() {
+            self.delegation_timestamps
+                .insert(account_id, &env::block_timestamp());
+        }
+        self.internal_emit_event(Event::Delegate {
+            account_id: account_id.clone(),
+            amount,
+        });
         (
             U128(prev_amount),
             U128(new_amount),
@@ -57,6 +85,10 @@ impl Contract {
         let new_amount = prev_amount - amount.0;
         self.delegations.insert(account_id, &new_amount);
         self.total_delegation_amount -= amount.0;
+        self.internal_emit_event(Event::Undelegate {
+            account_id: account_id.clone(),
+            amount,
+        });
         (
             U128(prev_amount),
             U128(new_amount),
@@ -0,0 +1,154 @@
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::serde::Serialize;
+use near_sdk::{env, AccountId};
+
+use crate::proposals::ProposalStatus;
+use crate::*;
+
+/// Standard name of the events emitted by this contract.
+pub const EVENT_STANDARD: &str = "sputnikdao";
+/// Version of the events standard.
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Events emitted by the DAO.
+#[derive(Serialize)]
+#[serde(
+    crate = "near_sdk::serde",
+    tag = "event",
+    content = "data",
+    rename_all = "snake_case"
+)]
+pub enum Event {
+    AddProposal {
+        proposal_id: u64,
+        proposer: AccountId,
+        kind: String,
+    },
+    ActProposal {
+        proposal_id: u64,
+        account_id: AccountId,
+        action: String,
+        status: ProposalStatus,
+    },
+    ProposalCallback {
+        proposal_id: u64,
+        status: ProposalStatus,
+    },
+    BountyClaim {
+        bounty_id: u64,
+        account_id: AccountId,
+        deadline: U64,
+    },
+    BountyGiveup {
+        bounty_id: u64,
+        account_id: AccountId,
+    },
+    BountyRenew {
+        bounty_id: u64,
+        remaining_budget: U128,
+    },
+    Delegate {
+        account_id: AccountId,
+        amount: U128,
+    },
+    Undelegate {
+        account_id: AccountId,
+        amount: U128,
+    },
+    Ragequit {
+        account_id: AccountId,
+        amount: U128,
+    },
+    GrantApply {
+        grant_id: u64,
+        applicant: AccountId,
+    },
+    GrantReview {
+        grant_id: u64,
+        reviewer: AccountId,
+        score: u8,
+    },
+    RemoveBlob {
+        hash: Base58CryptoHash,
+        account_id: AccountId,
+    },
+    SetAlternate {
+        seat_id: AccountId,
+        alternate_id: Option<AccountId>,
+    },
+    AlternateVote {
+        proposal_id: u64,
+        seat_id: AccountId,
+        alternate_id: AccountId,
+    },
+    BlockAccount {
+        account_id: AccountId,
+    },
+    UnblockAccount {
+        account_id: AccountId,
+    },
+    ReclaimBond {
+        proposal_id: u64,
+        proposer: AccountId,
+        token_id: Option<AccountId>,
+        amount: U128,
+    },
+    OffchainTally {
+        proposal_id: u64,
+        account_id: AccountId,
+        status: ProposalStatus,
+    },
+    ProposalFinalized {
+        proposal_id: u64,
+        proposer: AccountId,
+        status: ProposalStatus,
+    },
+    BountyDigest {
+        bounty_id: u64,
+        update: BountyUpdate,
+        account_id: Option<AccountId>,
+        bounty: Bounty,
+    },
+}
+
+/// Change of a bounty reported in the `bounty_digest` event.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BountyUpdate {
+    Added,
+    /// Claimed by `account_id`.
+    Claimed,
+    /// Paid out to `account_id`.
+    Completed,
+    /// Claim of `account_id` passed its deadline and was removed.
+    Expired,
+}
+
+/// NEP-297 event log. `seq` increases by one with every event emitted by this DAO,
+/// so indexers can detect missed events.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    seq: u64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+impl Contract {
+    /// Emits given event with the next sequence number.
+    pub(crate) fn internal_emit_event(&mut self, event: Event) {
+        self.last_event_seq += 1;
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_STANDARD_VERSION,
+            seq: self.last_event_seq,
+            event: &event,
+        };
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            near_sdk::serde_json::to_string(&log).unwrap()
+        ));
+    }
+}
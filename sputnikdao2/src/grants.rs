@@ -0,0 +1,571 @@
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, log, near_bindgen, AccountId, PromiseOrValue};
+
+use crate::author_storage::StorageChargeKey;
+use crate::events::Event;
+use crate::types::{convert_old_to_new_token, OldAccountId};
+use crate::*;
+
+/// Permission label that allows to review grant applications, e.g. `grant:Review`.
+pub const GRANT_PERMISSION_LABEL: &str = "grant";
+/// Action label that allows to review grant applications.
+pub const GRANT_REVIEW_ACTION_LABEL: &str = "Review";
+/// Maximum score reviewer can give to the application.
+pub const MAX_GRANT_SCORE: u8 = 10;
+/// Maximum length of the description of the application and of each milestone.
+pub const MAX_GRANT_DESCRIPTION_LEN: usize = 2048;
+/// Maximum number of milestones of the application.
+pub const MAX_GRANT_MILESTONES: usize = 20;
+
+/// Status of the grant application.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GrantStatus {
+    /// Application is in the queue, reviewers can score it.
+    Submitted,
+    /// Application was approved via `ApproveGrant` proposal. Milestones can be paid out.
+    Approved,
+    /// Application was rejected or removed via `ApproveGrant` proposal.
+    /// The storage deposit is refunded.
+    Rejected,
+    /// All milestones were paid out. The storage deposit is refunded.
+    Completed,
+}
+
+/// Status of the single milestone of the grant.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum GrantMilestoneStatus {
+    /// Milestone is not done yet.
+    Pending,
+    /// Applicant reported milestone as done, payout proposal is being voted on.
+    InReview,
+    /// Milestone was paid out.
+    Paid,
+}
+
+/// Milestone as submitted by the applicant.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GrantMilestoneInput {
+    /// Description of the deliverable.
+    pub description: String,
+    /// Amount to be paid out on completion.
+    pub amount: U128,
+}
+
+/// Milestone of the grant.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GrantMilestone {
+    /// Description of the deliverable.
+    pub description: String,
+    /// Amount to be paid out on completion.
+    pub amount: U128,
+    /// Current status of the milestone.
+    pub status: GrantMilestoneStatus,
+}
+
+/// Grant application submitted by the user.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GrantInput {
+    /// Description of the application.
+    pub description: String,
+    /// Token the grant will be paid out.
+    /// Can be "" for $NEAR or a valid account id.
+    pub token: OldAccountId,
+    /// Milestones with amounts to be paid out.
+    pub milestones: Vec<GrantMilestoneInput>,
+}
+
+/// Grant application and, once approved, the funded agreement.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Grant {
+    /// Account that applied and receives the payouts.
+    pub applicant: AccountId,
+    /// Description of the application.
+    pub description: String,
+    /// Token the grant will be paid out.
+    /// Can be "" for $NEAR or a valid account id.
+    pub token: OldAccountId,
+    /// Milestones with amounts to be paid out.
+    pub milestones: Vec<GrantMilestone>,
+    /// Current status of the application.
+    pub status: GrantStatus,
+    /// Scores given by reviewers.
+    pub scores: HashMap<AccountId, u8>,
+    /// Submission time of the application.
+    pub submission_time: U64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum VersionedGrant {
+    Default(Grant),
+}
+
+impl From<VersionedGrant> for Grant {
+    fn from(v: VersionedGrant) -> Self {
+        match v {
+            VersionedGrant::Default(g) => g,
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_grant(&self, id: u64) -> Grant {
+        self.grants.get(&id).expect("ERR_NO_GRANT").into()
+    }
+
+    fn internal_save_grant(&mut self, id: u64, grant: Grant) {
+        self.grants.insert(&id, &VersionedGrant::Default(grant));
+    }
+
+    /// Called when `ApproveGrant` proposal is approved.
+    pub(crate) fn internal_approve_grant(&mut self, id: u64) {
+        let mut grant = self.internal_get_grant(id);
+        if grant.status != GrantStatus::Submitted {
+            env::log_str(&format!("ERR_GRANT_NOT_SUBMITTED:{}", id));
+            return;
+        }
+        grant.status = GrantStatus::Approved;
+        self.internal_save_grant(id, grant);
+    }
+
+    /// Called when `ApproveGrant` proposal is rejected or removed.
+    pub(crate) fn internal_reject_grant(&mut self, id: u64) {
+        let mut grant = self.internal_get_grant(id);
+        if grant.status == GrantStatus::Submitted {
+            grant.status = GrantStatus::Rejected;
+            self.internal_save_grant(id, grant);
+            self.internal_refund_grant_deposit(id);
+        }
+    }
+
+    /// Returns the storage deposit of finished grant application to the applicant.
+    fn internal_refund_grant_deposit(&mut self, id: u64) {
+        if let Some((applicant, deposit)) =
+            self.storage_charges.remove(&StorageChargeKey::Grant(id))
+        {
+            Promise::new(applicant).transfer(deposit);
+        }
+    }
+
+    /// Marks milestone as being reviewed when the payout proposal is added.
+    /// Only applicant can report milestones of the approved grant, one proposal per milestone.
+    pub(crate) fn internal_start_grant_milestone(&mut self, id: u64, milestone: u32) {
+        let mut grant = self.internal_get_grant(id);
+        assert_eq!(
+            grant.applicant,
+            env::predecessor_account_id(),
+            "ERR_GRANT_DONE_MUST_BE_APPLICANT"
+        );
+        assert_eq!(
+            grant.status,
+            GrantStatus::Approved,
+            "ERR_GRANT_NOT_APPROVED"
+        );
+        let m = grant
+            .milestones
+            .get_mut(milestone as usize)
+            .expect("ERR_NO_GRANT_MILESTONE");
+        assert_eq!(
+            m.status,
+            GrantMilestoneStatus::Pending,
+            "ERR_GRANT_MILESTONE_NOT_PENDING"
+        );
+        m.status = GrantMilestoneStatus::InReview;
+        self.internal_save_grant(id, grant);
+    }
+
+    /// Pays out given milestone of the grant to the applicant.
+    pub(crate) fn internal_execute_grant_payout(
+        &mut self,
+        policy: &Policy,
+        proposal_id: u64,
+        id: u64,
+        milestone: u32,
+    ) -> PromiseOrValue<()> {
+        let grant = self.internal_get_grant(id);
+        let m = grant
+            .milestones
+            .get(milestone as usize)
+            .expect("ERR_NO_GRANT_MILESTONE");
+        let token_id = convert_old_to_new_token(&grant.token);
+        self.internal_record_outflow(policy, proposal_id, &token_id, m.amount.0);
+        self.internal_payout(
+            &token_id,
+            &grant.applicant,
+            m.amount.0,
+            format!("Grant {} milestone {} payout", id, milestone),
+            None,
+        )
+    }
+
+    /// Updates milestone after the payout proposal has been finalized.
+    /// On success milestone is marked as paid, otherwise it returns into pending state.
+    pub(crate) fn internal_finish_grant_milestone(&mut self, id: u64, milestone: u32, paid: bool) {
+        let mut grant = self.internal_get_grant(id);
+        let m = grant
+            .milestones
+            .get_mut(milestone as usize)
+            .expect("ERR_NO_GRANT_MILESTONE");
+        m.status = if paid {
+            GrantMilestoneStatus::Paid
+        } else {
+            GrantMilestoneStatus::Pending
+        };
+        if grant
+            .milestones
+            .iter()
+            .all(|m| m.status == GrantMilestoneStatus::Paid)
+        {
+            grant.status = GrantStatus::Completed;
+            self.internal_refund_grant_deposit(id);
+        }
+        self.internal_save_grant(id, grant);
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Submit grant application. Doesn't require a bond, but the attached deposit must cover the
+    /// storage of the application. The rest of the deposit is refunded, and the deposit itself
+    /// when the application is rejected or all its milestones are paid out.
+    /// Application is queued for review and must be approved via `ApproveGrant` proposal.
+    #[payable]
+    pub fn grant_apply(&mut self, grant: GrantInput) -> u64 {
+        let applicant = env::predecessor_account_id();
+        self.assert_not_blocked(&applicant);
+        assert!(!grant.milestones.is_empty(), "ERR_GRANT_NO_MILESTONES");
+        assert!(
+            grant.milestones.len() <= MAX_GRANT_MILESTONES,
+            "ERR_GRANT_TOO_MANY_MILESTONES"
+        );
+        assert!(
+            grant.milestones.iter().all(|m| m.amount.0 > 0),
+            "ERR_GRANT_ZERO_MILESTONE"
+        );
+        assert!(
+            grant.description.len() <= MAX_GRANT_DESCRIPTION_LEN
+                && grant
+                    .milestones
+                    .iter()
+                    .all(|m| m.description.len() <= MAX_GRANT_DESCRIPTION_LEN),
+            "ERR_GRANT_DESCRIPTION_TOO_LONG"
+        );
+        let initial_storage = env::storage_usage();
+        let id = self.last_grant_id;
+        self.internal_save_grant(
+            id,
+            Grant {
+                applicant: applicant.clone(),
+                description: grant.description,
+                token: grant.token,
+                milestones: grant
+                    .milestones
+                    .into_iter()
+                    .map(|m| GrantMilestone {
+                        description: m.description,
+                        amount: m.amount,
+                        status: GrantMilestoneStatus::Pending,
+                    })
+                    .collect(),
+                status: GrantStatus::Submitted,
+                scores: HashMap::default(),
+                submission_time: U64::from(env::block_timestamp()),
+            },
+        );
+        self.last_grant_id += 1;
+        // Record is inserted first so its own storage is covered too.
+        let key = StorageChargeKey::Grant(id);
+        self.storage_charges.insert(&key, &(applicant.clone(), 0));
+        let cost = (env::storage_usage() - initial_storage) as Balance * env::storage_byte_cost();
+        assert!(env::attached_deposit() >= cost, "ERR_NOT_ENOUGH_DEPOSIT");
+        self.storage_charges
+            .insert(&key, &(applicant.clone(), cost));
+        if env::attached_deposit() > cost {
+            Promise::new(applicant.clone()).transfer(env::attached_deposit() - cost);
+        }
+        self.internal_emit_event(Event::GrantApply {
+            grant_id: id,
+            applicant,
+        });
+        id
+    }
+
+    /// Score grant application. Only roles with `grant:Review` permission can review.
+    /// Repeated review from the same account overrides the previous score.
+    pub fn grant_review(&mut self, id: u64, score: u8, memo: Option<String>) {
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            policy
+                .can_execute_label(
+                    self.internal_user_info(),
+                    GRANT_PERMISSION_LABEL,
+                    GRANT_REVIEW_ACTION_LABEL
+                )
+                .1,
+            "ERR_PERMISSION_DENIED"
+        );
+        assert!(score <= MAX_GRANT_SCORE, "ERR_GRANT_INVALID_SCORE");
+        let mut grant = self.internal_get_grant(id);
+        assert_eq!(
+            grant.status,
+            GrantStatus::Submitted,
+            "ERR_GRANT_NOT_SUBMITTED"
+        );
+        grant.scores.insert(env::predecessor_account_id(), score);
+        self.internal_save_grant(id, grant);
+        self.internal_emit_event(Event::GrantReview {
+            grant_id: id,
+            reviewer: env::predecessor_account_id(),
+            score,
+        });
+        if let Some(memo) = memo {
+            log!("Memo: {}", memo);
+        }
+    }
+
+    /// Report that milestone of the approved grant is done. Only the applicant can report it.
+    /// Adds a proposal of the DAO without a bond to vote for paying out the milestone.
+    pub fn grant_milestone_done(&mut self, id: u64, milestone: u32, description: String) -> u64 {
+        self.assert_not_blocked(&env::predecessor_account_id());
+        let policy = self.policy.get().unwrap().to_policy();
+        self.internal_add_proposal(
+            &policy,
+            env::current_account_id(),
+            ProposalInput {
+                description,
+                kind: ProposalKind::GrantMilestoneDone {
+                    grant_id: id,
+                    milestone,
+                },
+            },
+            0,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn apply(context: &mut VMContextBuilder, contract: &mut Contract) -> u64 {
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.grant_apply(GrantInput {
+            description: "build a thing".to_string(),
+            token: String::from(OLD_BASE_TOKEN),
+            milestones: vec![
+                GrantMilestoneInput {
+                    description: "design".to_string(),
+                    amount: U128(to_yocto("5")),
+                },
+                GrantMilestoneInput {
+                    description: "release".to_string(),
+                    amount: U128(to_yocto("10")),
+                },
+            ],
+        })
+    }
+
+    fn setup_contract(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy.to_policy_mut().add_or_update_role(&RolePermission {
+            name: "reviewers".to_string(),
+            kind: RoleKind::Group(vec![accounts(3)].into_iter().collect()),
+            permissions: vec!["grant:Review".to_string()].into_iter().collect(),
+            vote_policy: HashMap::default(),
+        });
+        Contract::new(Config::test_config(), policy)
+    }
+
+    /// Applies for the grant, reviews, approves it and pays out all milestones.
+    #[test]
+    fn test_grant_lifecycle() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_contract(&mut context);
+
+        let id = apply(&mut context, &mut contract);
+        assert_eq!(contract.get_last_grant_id(), 1);
+        assert_eq!(contract.get_grant(id).grant.status, GrantStatus::Submitted);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.grant_review(id, 7, None);
+        assert_eq!(contract.get_grant(id).grant.scores[&accounts(3)], 7);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let proposal_id = contract.add_proposal(ProposalInput {
+            description: "approve grant".to_string(),
+            kind: ProposalKind::ApproveGrant { grant_id: id },
+        });
+        contract.act_proposal(proposal_id, Action::VoteApprove, None);
+        assert_eq!(contract.get_grant(id).grant.status, GrantStatus::Approved);
+
+        let (_, deposit) = contract
+            .storage_charges
+            .get(&StorageChargeKey::Grant(id))
+            .unwrap();
+        for milestone in 0..2 {
+            testing_env!(context
+                .predecessor_account_id(accounts(2))
+                .attached_deposit(0)
+                .build());
+            let locked_amount = contract.locked_amount;
+            let proposal_id = contract.grant_milestone_done(id, milestone, "done".to_string());
+            assert_eq!(
+                contract.get_grant(id).grant.milestones[milestone as usize].status,
+                GrantMilestoneStatus::InReview
+            );
+            // Added by the DAO, without a bond.
+            assert_eq!(
+                contract.get_proposal(proposal_id).proposal.proposer,
+                env::current_account_id()
+            );
+            assert_eq!(contract.locked_amount, locked_amount);
+            testing_env!(context.predecessor_account_id(accounts(1)).build());
+            contract.act_proposal(proposal_id, Action::VoteApprove, None);
+            testing_env!(
+                context.build(),
+                near_sdk::VMConfig::test(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                vec![PromiseResult::Successful(vec![])],
+            );
+            contract.on_proposal_callback(proposal_id);
+            assert_eq!(
+                contract.get_grant(id).grant.milestones[milestone as usize].status,
+                GrantMilestoneStatus::Paid
+            );
+        }
+        assert_eq!(contract.get_grant(id).grant.status, GrantStatus::Completed);
+        assert!(contract
+            .storage_charges
+            .get(&StorageChargeKey::Grant(id))
+            .is_none());
+        assert!(get_created_receipts().into_iter().any(|receipt| {
+            receipt.receiver_id == accounts(2)
+                && matches!(receipt.actions[0], VmAction::Transfer { deposit: amount } if amount == deposit)
+        }));
+    }
+
+    #[test]
+    fn test_grant_rejected() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_contract(&mut context);
+        let id = apply(&mut context, &mut contract);
+        let (_, deposit) = contract
+            .storage_charges
+            .get(&StorageChargeKey::Grant(id))
+            .unwrap();
+        assert!(deposit > 0 && deposit < to_yocto("1"));
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let proposal_id = contract.add_proposal(ProposalInput {
+            description: "approve grant".to_string(),
+            kind: ProposalKind::ApproveGrant { grant_id: id },
+        });
+        contract.act_proposal(proposal_id, Action::VoteReject, None);
+        assert_eq!(contract.get_grant(id).grant.status, GrantStatus::Rejected);
+        assert!(get_created_receipts().into_iter().any(|receipt| {
+            receipt.receiver_id == accounts(2)
+                && matches!(receipt.actions[0], VmAction::Transfer { deposit: amount } if amount == deposit)
+        }));
+    }
+
+    #[test]
+    fn test_grant_milestone_rejected() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_contract(&mut context);
+        let id = apply(&mut context, &mut contract);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let proposal_id = contract.add_proposal(ProposalInput {
+            description: "approve grant".to_string(),
+            kind: ProposalKind::ApproveGrant { grant_id: id },
+        });
+        contract.act_proposal(proposal_id, Action::VoteApprove, None);
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        let proposal_id = contract.grant_milestone_done(id, 0, "done".to_string());
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.act_proposal(proposal_id, Action::VoteReject, None);
+        let grant = contract.get_grant(id).grant;
+        assert_eq!(grant.milestones[0].status, GrantMilestoneStatus::Pending);
+        assert_eq!(grant.status, GrantStatus::Approved);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PERMISSION_DENIED")]
+    fn test_grant_review_not_allowed() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_contract(&mut context);
+        let id = apply(&mut context, &mut contract);
+        contract.grant_review(id, 5, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_DEPOSIT")]
+    fn test_grant_apply_no_deposit() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_contract(&mut context);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.grant_apply(GrantInput {
+            description: "build a thing".to_string(),
+            token: String::from(OLD_BASE_TOKEN),
+            milestones: vec![GrantMilestoneInput {
+                description: "release".to_string(),
+                amount: U128(to_yocto("10")),
+            }],
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_GRANT_DESCRIPTION_TOO_LONG")]
+    fn test_grant_apply_description_too_long() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_contract(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        contract.grant_apply(GrantInput {
+            description: "a".repeat(MAX_GRANT_DESCRIPTION_LEN + 1),
+            token: String::from(OLD_BASE_TOKEN),
+            milestones: vec![GrantMilestoneInput {
+                description: "release".to_string(),
+                amount: U128(to_yocto("10")),
+            }],
+        });
+    }
+}
@@ -1,27 +1,89 @@
+// Contract entry points are left out for clients, leaving some helpers and imports unused.
+#![cfg_attr(feature = "client", allow(dead_code, unused_imports))]
+
+use std::collections::HashMap;
+
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedSet};
 use near_sdk::json_types::{Base58CryptoHash, U128};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     env, ext_contract, near_bindgen, AccountId, Balance, BorshStorageKey, CryptoHash,
-    PanicOnDefault, Promise, PromiseResult,
+    PanicOnDefault, Promise, PromiseResult, PublicKey,
 };
 
-pub use crate::bounties::{Bounty, BountyClaim, VersionedBounty};
-pub use crate::policy::{Policy, RoleKind, RolePermission, VersionedPolicy, VotePolicy};
+pub use crate::anonymous::{author_commitment, AnonymousAuthor};
+pub use crate::audit::{AuditReport, AuditSection};
+use crate::author_storage::StorageChargeKey;
+pub use crate::bounties::{Bounty, BountyClaim, BountyProof, BountyTokenBond, VersionedBounty};
+pub use crate::cloning::ProposalOverrides;
+pub use crate::compat::{PolicyV1, ProposalOutputV1, RolePermissionV1, VotePolicyV1};
+use crate::events::Event;
+pub use crate::grants::{
+    Grant, GrantInput, GrantMilestone, GrantMilestoneInput, GrantMilestoneStatus, GrantStatus,
+    VersionedGrant,
+};
+pub use crate::loans::{Loan, LoanStatus};
+pub use crate::members::MembershipChange;
+pub use crate::mirroring::{MirrorLink, VoteMirror};
+pub use crate::offchain::OffchainTally;
+pub use crate::policy::{
+    Policy, ProposalTokenBond, RagequitPolicy, RecordedOutflow, RoleKind, RolePermission,
+    SpendLimits, TransferTier, TrustedTransferRule, VersionedPolicy, VotePolicy, WeightOrRatio,
+};
+pub use crate::policy_builder::{PolicyBuilder, PolicyError};
 use crate::proposals::VersionedProposal;
-pub use crate::proposals::{Proposal, ProposalInput, ProposalKind, ProposalStatus};
-pub use crate::types::{Action, Config, OldAccountId, OLD_BASE_TOKEN};
-use crate::upgrade::{internal_get_factory_info, internal_set_factory_info, FactoryInfo};
-pub use crate::views::{BountyOutput, ProposalOutput};
+pub use crate::proposals::{
+    ActProposalResult, ActionCall, FundingSource, Proposal, ProposalInput, ProposalKind,
+    ProposalStatus,
+};
+pub use crate::ragequit::PendingRagequit;
+pub use crate::recurring::RecurringVote;
+pub use crate::translations::Translations;
+use crate::types::FACTORY_PING_INTERVAL;
+pub use crate::types::{Action, Config, GasConfig, OldAccountId, OLD_BASE_TOKEN};
+use crate::upgrade::{
+    internal_get_factory_info, internal_set_factory_info, FactoryInfo, OldContract,
+};
+pub use crate::views::{
+    BountyOutput, DelegationOutput, GrantOutput, PolicyVocabulary, ProposalHashOutput,
+    ProposalOutput, StateChunk, StateChunkData, StateSection,
+};
+pub use crate::vote_options::{VoteOption, VoteOptions};
 
+mod alternates;
+mod anonymous;
+mod audit;
+mod author_storage;
+mod blocklist;
 mod bounties;
+#[cfg(feature = "client")]
+pub mod client;
+mod cloning;
+mod compat;
+pub mod consts;
 mod delegation;
+mod events;
+mod grants;
+mod loans;
+mod lockdown;
+mod members;
+mod mirroring;
+mod offchain;
 mod policy;
+mod policy_builder;
 mod proposals;
+mod ragequit;
+mod rate_limits;
+mod receipts;
+mod recurring;
+mod social;
+mod translations;
+mod treasury;
 mod types;
 mod upgrade;
 pub mod views;
+mod vote_options;
 
 #[derive(BorshStorageKey, BorshSerialize)]
 pub enum StorageKeys {
@@ -33,6 +95,48 @@ pub enum StorageKeys {
     BountyClaimers,
     BountyClaimCounts,
     Blobs,
+    Grants,
+    DelegationAccounts,
+    ReceiverPayouts,
+    BountyTokenBonds,
+    DelegationTimestamps,
+    ApproveVotes,
+    Alternates,
+    MemberLastVotes,
+    BlobReferences,
+    BountyProofs,
+    ProposalFundings,
+    MirrorLinks,
+    Blocklist,
+    FullAccessKeys,
+    VoteOptions,
+    VoteOptionSelections,
+    MemberAdditions,
+    ProposalTokenBonds,
+    BountyPools,
+    MembershipHistory,
+    TreasuryMigrations,
+    ProposalVoterCounts,
+    OffchainTallies,
+    RecurringVotes,
+    AuthorStorage,
+    StorageCharges,
+    SpamFlags,
+    ClonedFrom,
+    ProposalTranslations,
+    BountyTranslations,
+    VoteRates,
+    Loans,
+    AnonymousAuthors,
+    OffchainTallyPosts,
+    ActiveProposals,
+    OpenBounties,
+    LockedTokens,
+    TokenOutflows,
+    ProposalOutflows,
+    ProposalBonds,
+    ProposalKindCounts,
+    BountyBonds,
 }
 
 /// After payouts, allows a callback
@@ -40,9 +144,64 @@ pub enum StorageKeys {
 pub trait ExtSelf {
     /// Callback after proposal execution.
     fn on_proposal_callback(&mut self, proposal_id: u64) -> PromiseOrValue<()>;
+    /// Callback with id of the advisory proposal created in the parent DAO.
+    fn on_mirror_proposal(
+        &mut self,
+        proposal_id: u64,
+        parent_id: AccountId,
+        proposer: AccountId,
+        bond: U128,
+    );
+    /// Callback with the advisory proposal of the parent DAO.
+    fn on_mirror_status(&mut self, proposal_id: u64);
+    /// Callback with DAO's balances of the tokens to pay out ragequit shares.
+    fn on_ragequit_balances(
+        &mut self,
+        ragequit: PendingRagequit,
+        tokens: Vec<AccountId>,
+    ) -> PromiseOrValue<bool>;
+    /// Callback after ragequit payouts of the tokens.
+    fn on_ragequit_payouts(
+        &mut self,
+        ragequit: PendingRagequit,
+        shares: Vec<(AccountId, U128)>,
+    ) -> bool;
+    /// Callback with DAO's balance of the token to sweep to the target DAO.
+    fn on_migrate_treasury_balance(&mut self, token_id: AccountId, target_dao: AccountId);
 }
 
-#[near_bindgen]
+/// Non fungible token, to verify ownership of the avatar NFT and to migrate the treasury.
+#[ext_contract(ext_non_fungible_token)]
+pub trait NonFungibleToken {
+    fn nft_token(&self, token_id: String);
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: String,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    );
+}
+
+/// Parent DAO that advisory proposals are mirrored to.
+#[ext_contract(ext_parent_dao)]
+pub trait ParentDao {
+    fn add_proposal(&mut self, proposal: ProposalInput) -> u64;
+    fn get_proposal(&self, id: u64) -> ProposalOutput;
+}
+
+#[ext_contract(ext_factory)]
+pub trait Factory {
+    fn ping(&mut self, paused: bool);
+}
+
+/// Contract that added a proposal, notified when it is finalized.
+#[ext_contract(ext_proposer)]
+pub trait Proposer {
+    fn on_proposal_finalized(&mut self, proposal_id: u64, status: ProposalStatus);
+}
+
+#[cfg_attr(not(feature = "client"), near_bindgen)]
 #[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
 pub struct Contract {
     /// DAO configuration.
@@ -76,13 +235,163 @@ pub struct Contract {
 
     /// Large blob storage.
     pub blobs: LookupMap<CryptoHash, AccountId>,
+
+    /// Last available id for the grant applications.
+    pub last_grant_id: u64,
+    /// Grant applications map from ID to grant information.
+    pub grants: LookupMap<u64, VersionedGrant>,
+
+    /// Accounts registered for delegation. Allows to enumerate `delegations`.
+    /// Only includes accounts registered after this index was introduced.
+    pub delegation_accounts: UnorderedSet<AccountId>,
+
+    /// Number of successful `Transfer` payouts per receiver and token. Used by trusted transfer rules.
+    pub receiver_payouts: LookupMap<(AccountId, OldAccountId), u32>,
+
+    /// Bonds paid in the bounty's token per claimer and bounty id.
+    pub bounty_token_bonds: LookupMap<(AccountId, u64), BountyTokenBond>,
+    /// $NEAR bonds per claimer and bounty id, in the order of the claims.
+    /// Claims made before this was introduced use the current policy's bond.
+    pub bounty_bonds: LookupMap<(AccountId, u64), Vec<Balance>>,
+
+    /// Time of the last delegation to the account. Only recorded while ragequit is enabled.
+    pub delegation_timestamps: LookupMap<AccountId, u64>,
+    /// Proposals the account voted to approve, while they can still be active.
+    /// Only recorded while ragequit is enabled.
+    pub approve_votes: LookupMap<AccountId, Vec<u64>>,
+
+    /// Start of the current spend limit period.
+    pub outflow_period_start: u64,
+    /// Amount of $NEAR paid out of the treasury in the current spend limit period.
+    pub outflow_period_amount: Balance,
+    /// Start of the current spend limit period and amount paid out in it, by token.
+    pub token_outflows: LookupMap<AccountId, (u64, Balance)>,
+    /// Outflows counted against the spend limits, by id of the proposal waiting for its payout.
+    pub proposal_outflows: LookupMap<u64, RecordedOutflow>,
+
+    /// Sequence number of the last emitted event.
+    pub last_event_seq: u64,
+
+    /// Alternate per group member seat, that can vote while the member hasn't voted.
+    pub alternates: LookupMap<AccountId, AccountId>,
+
+    /// Last proposal each account voted on. Only recorded while vote decay is enabled.
+    pub member_last_votes: LookupMap<AccountId, u64>,
+    /// First proposal id counted as missed for members without recorded votes.
+    pub vote_decay_start: u64,
+    /// Number of proposals added before each proposal id, by kind label, to count absences for
+    /// vote decay. Only recorded while vote decay is enabled, also for the next proposal id.
+    pub proposal_kind_counts: LookupMap<u64, HashMap<String, u64>>,
+
+    /// Ids of upgrade proposals referencing each blob.
+    pub blob_references: LookupMap<CryptoHash, Vec<u64>>,
+
+    /// Proofs of completion submitted with `bounty_done`, by proposal id.
+    pub bounty_proofs: LookupMap<u64, BountyProof>,
+
+    /// $NEAR attached by proposers via `propose_with_deposit` to fund their pending proposals.
+    pub proposal_fundings: LookupMap<u64, Balance>,
+
+    /// Ids of proposals in progress. Expired ones are released once `max_active_proposals` is reached.
+    pub active_proposals: UnorderedSet<u64>,
+    /// Ids of bounties that can still be claimed or completed.
+    pub open_bounties: UnorderedSet<u64>,
+    /// Token bonds held in escrow, by token. Like `locked_amount`, not part of the treasury.
+    pub locked_tokens: LookupMap<AccountId, Balance>,
+
+    /// Which proposals are mirrored as advisory votes in a parent DAO.
+    pub vote_mirror: Option<VoteMirror>,
+    /// Advisory proposals in the parent DAO, by id of the mirrored proposal.
+    pub mirror_links: LookupMap<u64, MirrorLink>,
+
+    /// Accounts that can't add proposals or claim bounties.
+    pub blocklist: UnorderedSet<AccountId>,
+
+    /// Full access keys of this account, deleted by `Lockdown`.
+    pub full_access_keys: UnorderedSet<PublicKey>,
+
+    /// Options of `Vote` proposals added with `add_vote_with_options`, by proposal id.
+    pub vote_options: LookupMap<u64, VoteOptions>,
+    /// Option selected by each voter on `Vote` proposals with options.
+    pub vote_option_selections: LookupMap<(u64, AccountId), u32>,
+
+    /// Index of the next member to add for approved `AddMembersToRole` proposals with members left.
+    pub member_additions: LookupMap<u64, u64>,
+
+    /// Gas attached to cross-contract calls.
+    pub gas_config: GasConfig,
+
+    /// Proposal bonds paid in a token via `ft_transfer_call`, by proposal id.
+    pub proposal_token_bonds: LookupMap<u64, ProposalTokenBond>,
+
+    /// $NEAR proposal bonds by proposal id, as required by the policy when the proposal was added.
+    /// Proposals added before this was introduced use the current policy's bond.
+    pub proposal_bonds: LookupMap<u64, Balance>,
+
+    /// Remaining budget of renewable bounties in the bounty's token, by bounty id.
+    pub bounty_pools: LookupMap<u64, Balance>,
+
+    /// Group roles each account was added to or removed from by proposals.
+    pub membership_history: LookupMap<AccountId, Vec<MembershipChange>>,
+
+    /// Index of the next token or NFT to sweep for approved `MigrateTreasury` proposals with
+    /// some left.
+    pub treasury_migrations: LookupMap<u64, u64>,
+
+    /// Number of distinct voters per role per decision: yes / no / remove, by proposal id.
+    /// See `VotePolicy::min_voters`.
+    pub proposal_voter_counts: LookupMap<u64, HashMap<String, [u32; 3]>>,
+
+    /// Results of off-chain votes accepted from the tally oracle role, by proposal id.
+    pub offchain_tallies: LookupMap<u64, OffchainTally>,
+    /// Tallies posted by oracle members for proposals without accepted tally, by proposal id.
+    pub offchain_tally_posts: LookupMap<u64, Vec<(OffchainTally, Vec<AccountId>)>>,
+
+    /// Recurring votes, by id of the proposal that added them.
+    pub recurring_votes: LookupMap<u64, RecurringVote>,
+
+    /// Storage balances of proposal and bounty authors. See `Policy::charge_author_storage`.
+    pub author_storage: LookupMap<AccountId, Balance>,
+
+    /// Author and amount charged to their storage balance, by proposal or bounty.
+    /// Also the applicant and storage deposit of grant applications until they are finished.
+    pub storage_charges: LookupMap<StorageChargeKey, (AccountId, Balance)>,
+
+    /// Weight of `FlagSpam` votes per role, by proposal id.
+    pub spam_flags: LookupMap<u64, HashMap<String, Balance>>,
+
+    /// Id of the original proposal, by id of proposals added with `clone_proposal`.
+    pub cloned_from: LookupMap<u64, u64>,
+
+    /// Translations of proposal descriptions, by proposal id.
+    pub proposal_translations: LookupMap<u64, Translations>,
+
+    /// Translations of bounty descriptions, by bounty id.
+    pub bounty_translations: LookupMap<u64, Translations>,
+
+    /// Hour index and number of voting actions in it, by account. Only for rate limited accounts.
+    pub vote_rates: LookupMap<AccountId, (u64, u32)>,
+
+    /// Loans, by id of the `ApproveLoan` proposal that paid them out.
+    pub loans: LookupMap<u64, Loan>,
+
+    /// Commitments to the authors of anonymous proposals, by proposal id.
+    pub anonymous_authors: LookupMap<u64, AnonymousAuthor>,
+
+    /// Time of the last activity ping to the factory.
+    pub last_factory_ping: u64,
+
+    /// Set while the token payouts of a ragequit are in progress. Only one can be at a time, as
+    /// shares are measured against the token balances read before the payouts.
+    pub ragequit_pending: bool,
 }
 
+#[cfg(not(feature = "client"))]
 #[near_bindgen]
 impl Contract {
     #[init]
     pub fn new(config: Config, policy: VersionedPolicy) -> Self {
-        let this = Self {
+        let mut this = Self {
             config: LazyOption::new(StorageKeys::Config, Some(&config)),
             policy: LazyOption::new(StorageKeys::Policy, Some(&policy.upgrade())),
             staking_id: None,
@@ -96,18 +405,72 @@ impl Contract {
             bounty_claims_count: LookupMap::new(StorageKeys::BountyClaimCounts),
             blobs: LookupMap::new(StorageKeys::Blobs),
             locked_amount: 0,
+            last_grant_id: 0,
+            grants: LookupMap::new(StorageKeys::Grants),
+            delegation_accounts: UnorderedSet::new(StorageKeys::DelegationAccounts),
+            receiver_payouts: LookupMap::new(StorageKeys::ReceiverPayouts),
+            bounty_token_bonds: LookupMap::new(StorageKeys::BountyTokenBonds),
+            bounty_bonds: LookupMap::new(StorageKeys::BountyBonds),
+            delegation_timestamps: LookupMap::new(StorageKeys::DelegationTimestamps),
+            approve_votes: LookupMap::new(StorageKeys::ApproveVotes),
+            outflow_period_start: 0,
+            outflow_period_amount: 0,
+            token_outflows: LookupMap::new(StorageKeys::TokenOutflows),
+            proposal_outflows: LookupMap::new(StorageKeys::ProposalOutflows),
+            last_event_seq: 0,
+            alternates: LookupMap::new(StorageKeys::Alternates),
+            member_last_votes: LookupMap::new(StorageKeys::MemberLastVotes),
+            vote_decay_start: 0,
+            proposal_kind_counts: LookupMap::new(StorageKeys::ProposalKindCounts),
+            blob_references: LookupMap::new(StorageKeys::BlobReferences),
+            bounty_proofs: LookupMap::new(StorageKeys::BountyProofs),
+            proposal_fundings: LookupMap::new(StorageKeys::ProposalFundings),
+            active_proposals: UnorderedSet::new(StorageKeys::ActiveProposals),
+            open_bounties: UnorderedSet::new(StorageKeys::OpenBounties),
+            locked_tokens: LookupMap::new(StorageKeys::LockedTokens),
+            vote_mirror: None,
+            mirror_links: LookupMap::new(StorageKeys::MirrorLinks),
+            blocklist: UnorderedSet::new(StorageKeys::Blocklist),
+            full_access_keys: UnorderedSet::new(StorageKeys::FullAccessKeys),
+            vote_options: LookupMap::new(StorageKeys::VoteOptions),
+            vote_option_selections: LookupMap::new(StorageKeys::VoteOptionSelections),
+            member_additions: LookupMap::new(StorageKeys::MemberAdditions),
+            gas_config: GasConfig::default(),
+            proposal_token_bonds: LookupMap::new(StorageKeys::ProposalTokenBonds),
+            proposal_bonds: LookupMap::new(StorageKeys::ProposalBonds),
+            bounty_pools: LookupMap::new(StorageKeys::BountyPools),
+            membership_history: LookupMap::new(StorageKeys::MembershipHistory),
+            treasury_migrations: LookupMap::new(StorageKeys::TreasuryMigrations),
+            proposal_voter_counts: LookupMap::new(StorageKeys::ProposalVoterCounts),
+            offchain_tallies: LookupMap::new(StorageKeys::OffchainTallies),
+            offchain_tally_posts: LookupMap::new(StorageKeys::OffchainTallyPosts),
+            recurring_votes: LookupMap::new(StorageKeys::RecurringVotes),
+            author_storage: LookupMap::new(StorageKeys::AuthorStorage),
+            storage_charges: LookupMap::new(StorageKeys::StorageCharges),
+            spam_flags: LookupMap::new(StorageKeys::SpamFlags),
+            cloned_from: LookupMap::new(StorageKeys::ClonedFrom),
+            proposal_translations: LookupMap::new(StorageKeys::ProposalTranslations),
+            bounty_translations: LookupMap::new(StorageKeys::BountyTranslations),
+            vote_rates: LookupMap::new(StorageKeys::VoteRates),
+            loans: LookupMap::new(StorageKeys::Loans),
+            anonymous_authors: LookupMap::new(StorageKeys::AnonymousAuthors),
+            last_factory_ping: 0,
+            ragequit_pending: false,
         };
         internal_set_factory_info(&FactoryInfo {
             factory_id: env::predecessor_account_id(),
             auto_update: true,
         });
+        this.internal_register_signer_key();
         this
     }
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
-    /// If you have changed state, you need to implement migration from old state (keep the old struct with different name to deserialize it first).
-    /// After migrate goes live on MainNet, return this implementation for next updates.
+    /// Migrates from the state of the previous release (see `OldContract`), initializing new collections
+    /// and converting the policy. Stored proposals are converted when read, see `OldProposal`.
+    /// Proposals in progress and open bounties are counted afterwards with `recount_active`.
+    /// After migrate goes live on MainNet, return the NOOP implementation for next updates:
+    /// `let this: Contract = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");`
     #[init(ignore_state)]
     pub fn migrate() -> Self {
         assert_eq!(
@@ -115,13 +478,81 @@ impl Contract {
             env::current_account_id(),
             "ERR_NOT_ALLOWED"
         );
-        let this: Contract = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
-        this
+        let old: OldContract = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
+        let policy: VersionedPolicy = old.policy.get().expect("ERR_NO_POLICY").into();
+        Self {
+            config: old.config,
+            policy: LazyOption::new(StorageKeys::Policy, Some(&policy)),
+            locked_amount: old.locked_amount,
+            staking_id: old.staking_id,
+            total_delegation_amount: old.total_delegation_amount,
+            delegations: old.delegations,
+            last_proposal_id: old.last_proposal_id,
+            proposals: old.proposals,
+            last_bounty_id: old.last_bounty_id,
+            bounties: old.bounties,
+            bounty_claimers: old.bounty_claimers,
+            bounty_claims_count: old.bounty_claims_count,
+            blobs: old.blobs,
+            last_grant_id: 0,
+            grants: LookupMap::new(StorageKeys::Grants),
+            delegation_accounts: UnorderedSet::new(StorageKeys::DelegationAccounts),
+            receiver_payouts: LookupMap::new(StorageKeys::ReceiverPayouts),
+            bounty_token_bonds: LookupMap::new(StorageKeys::BountyTokenBonds),
+            bounty_bonds: LookupMap::new(StorageKeys::BountyBonds),
+            delegation_timestamps: LookupMap::new(StorageKeys::DelegationTimestamps),
+            approve_votes: LookupMap::new(StorageKeys::ApproveVotes),
+            outflow_period_start: 0,
+            outflow_period_amount: 0,
+            token_outflows: LookupMap::new(StorageKeys::TokenOutflows),
+            proposal_outflows: LookupMap::new(StorageKeys::ProposalOutflows),
+            last_event_seq: 0,
+            alternates: LookupMap::new(StorageKeys::Alternates),
+            member_last_votes: LookupMap::new(StorageKeys::MemberLastVotes),
+            vote_decay_start: 0,
+            proposal_kind_counts: LookupMap::new(StorageKeys::ProposalKindCounts),
+            blob_references: LookupMap::new(StorageKeys::BlobReferences),
+            bounty_proofs: LookupMap::new(StorageKeys::BountyProofs),
+            proposal_fundings: LookupMap::new(StorageKeys::ProposalFundings),
+            active_proposals: UnorderedSet::new(StorageKeys::ActiveProposals),
+            open_bounties: UnorderedSet::new(StorageKeys::OpenBounties),
+            locked_tokens: LookupMap::new(StorageKeys::LockedTokens),
+            vote_mirror: None,
+            mirror_links: LookupMap::new(StorageKeys::MirrorLinks),
+            blocklist: UnorderedSet::new(StorageKeys::Blocklist),
+            full_access_keys: UnorderedSet::new(StorageKeys::FullAccessKeys),
+            vote_options: LookupMap::new(StorageKeys::VoteOptions),
+            vote_option_selections: LookupMap::new(StorageKeys::VoteOptionSelections),
+            member_additions: LookupMap::new(StorageKeys::MemberAdditions),
+            gas_config: GasConfig::default(),
+            proposal_token_bonds: LookupMap::new(StorageKeys::ProposalTokenBonds),
+            proposal_bonds: LookupMap::new(StorageKeys::ProposalBonds),
+            bounty_pools: LookupMap::new(StorageKeys::BountyPools),
+            membership_history: LookupMap::new(StorageKeys::MembershipHistory),
+            treasury_migrations: LookupMap::new(StorageKeys::TreasuryMigrations),
+            proposal_voter_counts: LookupMap::new(StorageKeys::ProposalVoterCounts),
+            offchain_tallies: LookupMap::new(StorageKeys::OffchainTallies),
+            offchain_tally_posts: LookupMap::new(StorageKeys::OffchainTallyPosts),
+            recurring_votes: LookupMap::new(StorageKeys::RecurringVotes),
+            author_storage: LookupMap::new(StorageKeys::AuthorStorage),
+            storage_charges: LookupMap::new(StorageKeys::StorageCharges),
+            spam_flags: LookupMap::new(StorageKeys::SpamFlags),
+            cloned_from: LookupMap::new(StorageKeys::ClonedFrom),
+            proposal_translations: LookupMap::new(StorageKeys::ProposalTranslations),
+            bounty_translations: LookupMap::new(StorageKeys::BountyTranslations),
+            vote_rates: LookupMap::new(StorageKeys::VoteRates),
+            loans: LookupMap::new(StorageKeys::Loans),
+            anonymous_authors: LookupMap::new(StorageKeys::AnonymousAuthors),
+            last_factory_ping: 0,
+            ragequit_pending: false,
+        }
     }
 
     /// Remove blob from contract storage and pay back to original storer.
     /// Only original storer can call this.
+    /// Fails if the blob is referenced by upgrade proposals that are still pending.
     pub fn remove_blob(&mut self, hash: Base58CryptoHash) -> Promise {
+        let blob_hash = hash;
         let hash: CryptoHash = hash.into();
         let account_id = self.blobs.remove(&hash).expect("ERR_NO_BLOB");
         assert_eq!(
@@ -129,6 +560,19 @@ impl Contract {
             account_id,
             "ERR_INVALID_CALLER"
         );
+        let policy = self.policy.get().unwrap().to_policy();
+        if let Some(references) = self.blob_references.remove(&hash) {
+            assert!(
+                references
+                    .iter()
+                    .all(|id| !self.internal_is_proposal_pending(&policy, *id)),
+                "ERR_BLOB_REFERENCED"
+            );
+        }
+        self.internal_emit_event(Event::RemoveBlob {
+            hash: blob_hash,
+            account_id: account_id.clone(),
+        });
         env::storage_remove(&hash);
         let blob_len = env::register_len(u64::MAX - 1).unwrap();
         let storage_cost = ((blob_len + 32) as u128) * env::storage_byte_cost();
@@ -141,8 +585,28 @@ impl Contract {
     }
 }
 
+impl Contract {
+    /// Reports activity of this DAO to its factory, at most once per `FACTORY_PING_INTERVAL`.
+    /// Called when proposals are added or acted on. This contract has no pause switch, so the DAO
+    /// reports itself paused when its policy leaves nobody able to add or approve proposals.
+    pub(crate) fn internal_ping_factory(&mut self, policy: &Policy) {
+        let now = env::block_timestamp();
+        if now < self.last_factory_ping + FACTORY_PING_INTERVAL {
+            return;
+        }
+        self.last_factory_ping = now;
+        ext_factory::ping(
+            !(policy.has_proposers() && policy.has_voters()),
+            internal_get_factory_info().factory_id,
+            0,
+            self.gas_config.factory_ping,
+        );
+    }
+}
+
 /// Stores attached data into blob store and returns hash of it.
 /// Implemented to avoid loading the data into WASM for optimal gas usage.
+#[cfg(not(feature = "client"))]
 #[no_mangle]
 pub extern "C" fn store_blob() {
     env::setup_panic_hook();
@@ -179,7 +643,7 @@ mod tests {
     use near_sdk::testing_env;
     use near_sdk_sim::to_yocto;
 
-    use crate::proposals::ProposalStatus;
+    use crate::proposals::{ProposalStatus, Vote};
 
     use super::*;
 
@@ -240,6 +704,83 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_read_baseline_proposal() {
+        use crate::policy::WeightKind;
+        use crate::upgrade::{OldActionCall, OldProposal, OldProposalKind, OldVotePolicy};
+        use near_sdk::json_types::{Base64VecU8, U64};
+
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        // Stored by the previous release, before `ActionCall::funding_source` and
+        // `VotePolicy::min_voters`.
+        contract.proposals.insert(
+            &0,
+            &VersionedProposal::Default(OldProposal {
+                proposer: accounts(2),
+                description: "old".to_string(),
+                kind: OldProposalKind::FunctionCall {
+                    receiver_id: accounts(3),
+                    actions: vec![OldActionCall {
+                        method_name: "go".to_string(),
+                        args: vec![].into(),
+                        deposit: U128(1),
+                        gas: U64(10u64.pow(13)),
+                    }],
+                },
+                status: ProposalStatus::InProgress,
+                vote_counts: HashMap::default(),
+                votes: HashMap::default(),
+                submission_time: U64(0),
+            }),
+        );
+        contract.proposals.insert(
+            &1,
+            &VersionedProposal::Default(OldProposal {
+                proposer: accounts(2),
+                description: "old policy".to_string(),
+                kind: OldProposalKind::ChangePolicyUpdateDefaultVotePolicy {
+                    vote_policy: OldVotePolicy {
+                        weight_kind: WeightKind::RoleWeight,
+                        quorum: U128(0),
+                        threshold: WeightOrRatio::Ratio(2, 3),
+                    },
+                },
+                status: ProposalStatus::InProgress,
+                vote_counts: HashMap::default(),
+                votes: HashMap::default(),
+                submission_time: U64(0),
+            }),
+        );
+        contract.last_proposal_id = 2;
+        contract.locked_amount = to_yocto("2");
+        contract.act_proposal(1, Action::VoteApprove, None);
+        let policy = contract.get_policy();
+        assert_eq!(
+            policy.default_vote_policy.threshold,
+            WeightOrRatio::Ratio(2, 3)
+        );
+        assert_eq!(policy.default_vote_policy.min_voters, 0);
+
+        let proposal = contract.get_proposal(0).proposal;
+        match proposal.kind {
+            ProposalKind::FunctionCall { actions, .. } => {
+                assert_eq!(actions[0].funding_source, FundingSource::Treasury)
+            }
+            _ => panic!("wrong kind"),
+        }
+        assert_eq!(contract.get_proposals(0, 10).len(), 2);
+        contract.act_proposal(0, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(0).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
     #[test]
     #[should_panic(expected = "ERR_PERMISSION_DENIED")]
     fn test_remove_proposal_denied() {
@@ -320,6 +861,388 @@ mod tests {
         assert_eq!(x.roles.len(), 2);
     }
 
+    #[test]
+    #[should_panic(expected = "ERR_TRANSFER_ZERO_AMOUNT")]
+    fn test_transfer_zero_amount() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(0),
+                msg: None,
+            },
+        });
+    }
+
+    #[test]
+    fn test_validate_transfer() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.current_account_id(accounts(0)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)])
+            .upgrade()
+            .to_policy();
+        let token = accounts(3).to_string();
+        let validate = |policy: &Policy, token: &str, receiver_id: AccountId, msg: Option<&str>| {
+            proposals::validate_transfer(policy, &token.to_string(), &receiver_id, 1, msg)
+        };
+        assert_eq!(validate(&policy, &token, accounts(2), None), Ok(()));
+        assert_eq!(
+            validate(&policy, "INVALID TOKEN", accounts(2), None),
+            Err(proposals::TransferError::InvalidToken)
+        );
+        assert_eq!(
+            validate(&policy, OLD_BASE_TOKEN, accounts(2), Some("msg")),
+            Err(proposals::TransferError::BaseTokenMsg)
+        );
+        assert_eq!(
+            validate(
+                &policy,
+                &token,
+                accounts(2),
+                Some(&"x".repeat(proposals::MAX_TRANSFER_MSG_LEN + 1))
+            ),
+            Err(proposals::TransferError::MsgTooLong)
+        );
+        assert_eq!(
+            validate(&policy, &token, accounts(0), None),
+            Err(proposals::TransferError::SelfTransfer)
+        );
+        policy.allow_self_transfers = true;
+        assert_eq!(validate(&policy, &token, accounts(0), None), Ok(()));
+    }
+
+    #[test]
+    fn test_trusted_transfer_rule() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]).upgrade();
+        policy
+            .to_policy_mut()
+            .trusted_transfer_rules
+            .push(TrustedTransferRule {
+                token_id: String::from(OLD_BASE_TOKEN),
+                min_payouts: 3,
+                min_payout_amount: U128(to_yocto("10")),
+                max_amount: U128(to_yocto("1000")),
+                threshold: WeightOrRatio::Weight(U128(1)),
+            });
+        let mut contract = Contract::new(Config::test_config(), policy);
+
+        // Receiver without history needs 1/2 of the council.
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+
+        contract
+            .receiver_payouts
+            .insert(&(accounts(2), String::from(OLD_BASE_TOKEN)), &3);
+        assert_eq!(
+            contract.get_receiver_payouts(accounts(2), String::from(OLD_BASE_TOKEN)),
+            3
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_trusted_transfer_rule_counts_payouts() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("1000"))
+            .build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1)]).upgrade();
+        policy
+            .to_policy_mut()
+            .trusted_transfer_rules
+            .push(TrustedTransferRule {
+                token_id: String::from(OLD_BASE_TOKEN),
+                min_payouts: 3,
+                min_payout_amount: U128(to_yocto("10")),
+                max_amount: U128(to_yocto("1000")),
+                threshold: WeightOrRatio::Weight(U128(1)),
+            });
+        let mut contract = Contract::new(Config::test_config(), policy);
+        let mut pay = |token_id: &str, amount: Balance| {
+            testing_env!(context.attached_deposit(to_yocto("1")).build());
+            let id = contract.add_proposal(ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Transfer {
+                    token_id: token_id.to_string(),
+                    receiver_id: accounts(2),
+                    amount: U128(amount),
+                    msg: None,
+                },
+            });
+            contract.act_proposal(id, Action::VoteApprove, None);
+            testing_env!(
+                context.attached_deposit(0).build(),
+                near_sdk::VMConfig::test(),
+                near_sdk::RuntimeFeesConfig::test(),
+                Default::default(),
+                vec![PromiseResult::Successful(vec![])],
+            );
+            contract.on_proposal_callback(id);
+            (
+                contract.get_receiver_payouts(accounts(2), String::from(OLD_BASE_TOKEN)),
+                contract.get_receiver_payouts(accounts(2), token_id.to_string()),
+            )
+        };
+        assert_eq!(pay(OLD_BASE_TOKEN, to_yocto("10")), (1, 1));
+        // Payouts below the minimum amount and of other tokens don't count.
+        assert_eq!(pay(OLD_BASE_TOKEN, to_yocto("1")), (1, 1));
+        assert_eq!(pay(accounts(3).as_str(), to_yocto("10")), (1, 0));
+    }
+
+    #[test]
+    fn test_transfer_tiers() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy =
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]).upgrade();
+        // Listed out of order, the lowest matching tier applies.
+        policy.to_policy_mut().transfer_tiers = vec![
+            TransferTier {
+                token_id: String::from(OLD_BASE_TOKEN),
+                max_amount: U128(to_yocto("1000")),
+                threshold: WeightOrRatio::Ratio(2, 3),
+            },
+            TransferTier {
+                token_id: String::from(OLD_BASE_TOKEN),
+                max_amount: U128(to_yocto("10")),
+                threshold: WeightOrRatio::Weight(U128(1)),
+            },
+        ];
+        let mut contract = Contract::new(Config::test_config(), policy);
+
+        // Transfer of 100 NEAR needs all 3 council members instead of 2.
+        let id = create_proposal(&mut context, &mut contract);
+        for account_id in [accounts(1), accounts(2)] {
+            testing_env!(context
+                .predecessor_account_id(account_id)
+                .attached_deposit(0)
+                .build());
+            contract.act_proposal(id, Action::VoteApprove, None);
+        }
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "petty cash".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("5")),
+                msg: None,
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    fn function_call_proposal(funding_source: FundingSource) -> ProposalInput {
+        ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::FunctionCall {
+                receiver_id: accounts(3),
+                actions: vec![ActionCall {
+                    method_name: "register".to_string(),
+                    args: vec![].into(),
+                    deposit: U128(to_yocto("100")),
+                    gas: 10_000_000_000_000.into(),
+                    funding_source,
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn test_function_call_proposer_escrow() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(function_call_proposal(FundingSource::Treasury));
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+
+        testing_env!(context.attached_deposit(to_yocto("101")).build());
+        let id = contract.add_proposal(function_call_proposal(FundingSource::ProposerEscrow));
+        assert_eq!(contract.locked_amount, to_yocto("102"));
+
+        // Rejected proposal returns the bond and the escrow.
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MIN_BOND")]
+    fn test_function_call_proposer_escrow_not_attached() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(function_call_proposal(FundingSource::ProposerEscrow));
+    }
+
+    #[test]
+    fn test_propose_with_deposit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("41")).build());
+        let id = contract.propose_with_deposit(function_call_proposal(FundingSource::Treasury));
+        assert_eq!(contract.locked_amount, to_yocto("41"));
+        assert_eq!(
+            contract.get_proposal(id).funding,
+            Some(U128(to_yocto("40")))
+        );
+
+        // Executed proposal spends the funding.
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(id);
+        assert_eq!(contract.locked_amount, 0);
+        assert_eq!(contract.get_proposal(id).funding, None);
+
+        // Rejected proposal returns the funding.
+        let id = contract.propose_with_deposit(function_call_proposal(FundingSource::Treasury));
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_FUNDING_AMOUNT")]
+    fn test_propose_with_deposit_too_much() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("102")).build());
+        contract.propose_with_deposit(function_call_proposal(FundingSource::Treasury));
+    }
+
+    #[test]
+    fn test_export_state_chunk() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        for _ in 0..3 {
+            create_proposal(&mut context, &mut contract);
+        }
+        let chunk = contract.export_state_chunk(StateSection::Proposals, 1, 10);
+        assert_eq!(chunk.from_index, 1);
+        assert_eq!(chunk.total, 3);
+        match chunk.data {
+            StateChunkData::Proposals(proposals) => {
+                assert_eq!(
+                    proposals.iter().map(|p| p.id).collect::<Vec<_>>(),
+                    vec![1, 2]
+                );
+            }
+            _ => panic!("wrong section"),
+        }
+        match contract
+            .export_state_chunk(StateSection::Policy, 0, 10)
+            .data
+        {
+            StateChunkData::Policy(policy) => assert_eq!(policy, contract.get_policy()),
+            _ => panic!("wrong section"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_KIND_LABEL")]
+    fn test_fails_adding_role_vote_policy_for_unknown_kind() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangeRoleVotePolicy {
+                role: "council".to_string(),
+                kind_label: "transfers".to_string(),
+                vote_policy: None,
+            },
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_DUPLICATE_TRANSFER_TIER")]
+    fn test_fails_adding_duplicate_transfer_tiers() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.get_policy();
+        let tier = TransferTier {
+            token_id: String::from(OLD_BASE_TOKEN),
+            max_amount: U128(to_yocto("10")),
+            threshold: WeightOrRatio::Weight(U128(1)),
+        };
+        policy.transfer_tiers = vec![tier.clone(), tier];
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangePolicy {
+                policy: VersionedPolicy::Current(policy),
+            },
+        });
+    }
+
     #[test]
     #[should_panic(expected = "ERR_INVALID_POLICY")]
     fn test_fails_adding_invalid_policy() {
@@ -337,4 +1260,857 @@ mod tests {
             },
         });
     }
+
+    fn set_avatar_nft(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        owner_id: AccountId,
+    ) -> u64 {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "avatar".to_string(),
+            kind: ProposalKind::SetAvatarNft {
+                contract_id: accounts(3),
+                token_id: "1".to_string(),
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let token = format!("{{\"token_id\":\"1\",\"owner_id\":\"{}\"}}", owner_id);
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(token.into_bytes())],
+        );
+        contract.on_proposal_callback(id);
+        id
+    }
+
+    #[test]
+    fn test_set_avatar_nft() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = set_avatar_nft(&mut context, &mut contract, accounts(2));
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Failed
+        );
+        assert!(contract.get_config().metadata.0.is_empty());
+
+        let id = set_avatar_nft(&mut context, &mut contract, accounts(0));
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        let metadata = contract.get_config().metadata_object().unwrap();
+        assert_eq!(metadata["avatar_nft"]["token_id"], "1");
+    }
+
+    #[test]
+    fn test_set_avatar_nft_metadata_changed() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "avatar".to_string(),
+            kind: ProposalKind::SetAvatarNft {
+                contract_id: accounts(3),
+                token_id: "1".to_string(),
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        // Config changed to metadata that isn't JSON while `nft_token` was pending.
+        let mut config = contract.get_config();
+        config.metadata = near_sdk::json_types::Base64VecU8(b"not json".to_vec());
+        contract.config.set(&config);
+
+        let token = format!("{{\"token_id\":\"1\",\"owner_id\":\"{}\"}}", accounts(0));
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(token.into_bytes())],
+        );
+        contract.on_proposal_callback(id);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert_eq!(contract.get_config().metadata.0, b"not json".to_vec());
+    }
+
+    fn approve_transfer(context: &mut VMContextBuilder, contract: &mut Contract, amount: &str) {
+        approve_token_transfer(context, contract, OLD_BASE_TOKEN, amount);
+    }
+
+    fn approve_token_transfer(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        token_id: &str,
+        amount: &str,
+    ) -> u64 {
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(token_id),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto(amount)),
+                msg: None,
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        id
+    }
+
+    fn set_spend_limits(
+        contract: &mut Contract,
+        max_token_period_outflows: Vec<(AccountId, U128)>,
+    ) {
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.spend_limits = Some(SpendLimits {
+            max_transfer_bps: 1_000,
+            max_period_outflow: U128(to_yocto("15")),
+            max_token_period_outflows,
+        });
+        contract.policy.set(&VersionedPolicy::Current(policy));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SPEND_LIMIT_PERIOD")]
+    fn test_spend_limits() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        set_spend_limits(&mut contract, vec![]);
+        approve_transfer(&mut context, &mut contract, "8");
+        assert_eq!(contract.outflow_period_amount, to_yocto("8"));
+        approve_transfer(&mut context, &mut contract, "8");
+    }
+
+    #[test]
+    fn test_spend_limits_failed_payout() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        set_spend_limits(&mut contract, vec![(accounts(4), U128(to_yocto("10")))]);
+        let id = approve_token_transfer(&mut context, &mut contract, accounts(4).as_str(), "8");
+        assert_eq!(
+            contract.token_outflows.get(&accounts(4)).unwrap().1,
+            to_yocto("8")
+        );
+
+        // Failed payout is taken back out of the period outflow.
+        testing_env!(
+            context.attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_proposal_callback(id);
+        assert_eq!(contract.token_outflows.get(&accounts(4)).unwrap().1, 0);
+        assert!(contract.proposal_outflows.get(&id).is_none());
+
+        let id = approve_token_transfer(&mut context, &mut contract, accounts(4).as_str(), "8");
+        testing_env!(
+            context.attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(id);
+        assert_eq!(
+            contract.token_outflows.get(&accounts(4)).unwrap().1,
+            to_yocto("8")
+        );
+        assert!(contract.proposal_outflows.get(&id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SPEND_LIMIT_NO_TOKEN")]
+    fn test_spend_limits_token_without_limit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        set_spend_limits(&mut contract, vec![]);
+        approve_token_transfer(&mut context, &mut contract, accounts(4).as_str(), "1");
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SPEND_LIMIT_TRANSFER")]
+    fn test_spend_limits_function_call_deposit() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("500"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        set_spend_limits(&mut contract, vec![]);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(function_call_proposal(FundingSource::Treasury));
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    fn test_event_seq() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        assert_eq!(contract.get_last_event_seq(), 0);
+        let id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert_eq!(contract.get_last_event_seq(), 3);
+        let logs = near_sdk::test_utils::get_logs();
+        assert!(logs[0].starts_with("EVENT_JSON:"));
+        assert!(logs[0].contains("\"seq\":1,\"event\":\"add_proposal\""));
+        assert!(logs[1].contains("\"seq\":2,\"event\":\"act_proposal\""));
+        assert!(logs[2].contains("\"seq\":3,\"event\":\"proposal_finalized\""));
+    }
+
+    #[test]
+    fn test_hash_proposal() {
+        let context = VMContextBuilder::new();
+        testing_env!(context.build());
+        let contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let transfer = |description: &str| ProposalInput {
+            description: description.to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(100),
+                msg: None,
+            },
+        };
+        let output = contract.hash_proposal(transfer("test"));
+        assert_eq!(output.kind, "transfer");
+        assert_eq!(output.summary, "Transfer 100 yoctoNEAR to charlie");
+        assert_eq!(
+            String::from(&output.hash),
+            String::from(&contract.hash_proposal(transfer("test")).hash)
+        );
+        assert_ne!(
+            String::from(&output.hash),
+            String::from(&contract.hash_proposal(transfer("other")).hash)
+        );
+    }
+
+    #[test]
+    fn test_vote_decay() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.vote_decay_after = Some(1);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        let vote_count = |contract: &Contract, id: u64| {
+            contract.get_proposal(id).proposal.vote_counts["council"][Vote::Approve as usize]
+        };
+
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(vote_count(&contract, id), 1);
+
+        // Member 3 skipped proposal 0, so their vote doesn't count.
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(vote_count(&contract, id), 0);
+
+        // Voting again restores the weight.
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(vote_count(&contract, id), 1);
+    }
+
+    #[test]
+    fn test_vote_decay_older_proposal() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.vote_decay_after = Some(10);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        let old_id = create_proposal(&mut context, &mut contract);
+        let id = create_proposal(&mut context, &mut contract);
+
+        // Voting on an older proposal doesn't move the last vote back.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        contract.act_proposal(old_id, Action::VoteApprove, None);
+        assert_eq!(contract.member_last_votes.get(&accounts(2)), Some(id));
+    }
+
+    #[test]
+    fn test_vote_decay_total_weight() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.vote_decay_after = Some(1);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+
+        // Members 1 and 3 skipped proposal 0, so only member 2 counts towards the threshold.
+        let id = create_proposal(&mut context, &mut contract);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+
+    #[test]
+    fn test_vote_decay_only_counts_votable_proposals() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut policy = VersionedPolicy::Default(vec![accounts(1), accounts(2)]).upgrade();
+        policy.to_policy_mut().add_or_update_role(&RolePermission {
+            name: "pollsters".to_string(),
+            kind: RoleKind::Group(vec![accounts(3)].into_iter().collect()),
+            permissions: vec!["vote:*".to_string()].into_iter().collect(),
+            vote_policy: HashMap::default(),
+        });
+        policy.to_policy_mut().vote_decay_after = Some(1);
+        let mut contract = Contract::new(Config::test_config(), policy);
+
+        // Member 3 can't vote on transfers, so skipping one doesn't decay their votes.
+        create_proposal(&mut context, &mut contract);
+        let id = contract.add_proposal(ProposalInput {
+            description: "poll".to_string(),
+            kind: ProposalKind::Vote,
+        });
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.vote_counts["pollsters"][Vote::Approve as usize],
+            1
+        );
+    }
+
+    #[test]
+    fn test_min_voters() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.default_vote_policy.threshold = WeightOrRatio::Weight(U128(1));
+        policy.default_vote_policy.min_voters = 2;
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+
+        // Weight threshold is met, but not the number of voters.
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+        // Opposing vote doesn't count as a voter of the approval.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let output = contract.get_proposal(id);
+        assert_eq!(output.proposal.status, ProposalStatus::Approved);
+        assert_eq!(output.voter_counts.unwrap()["council"], [2, 1, 0]);
+    }
+
+    #[test]
+    fn test_flag_spam() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.spam_threshold = Some(WeightOrRatio::Weight(U128(2)));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+
+        // Flags are tallied separately and don't count as removal votes.
+        contract.act_proposal(id, Action::FlagSpam, None);
+        let output = contract.get_proposal(id);
+        assert_eq!(output.proposal.status, ProposalStatus::InProgress);
+        assert_eq!(output.spam_flags.unwrap()["council"], U128(1));
+        assert!(output.proposal.vote_counts.is_empty());
+        let available = contract.get_available_amount().0;
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::FlagSpam, None);
+        assert!(contract.proposals.get(&id).is_none());
+        // Bond is forfeited to the treasury.
+        assert_eq!(contract.locked_amount, 0);
+        assert!(contract.get_available_amount().0 >= available + to_yocto("1"));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOO_MANY_ACTIVE_PROPOSALS")]
+    fn test_max_active_proposals() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.max_active_proposals = Some(1);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(contract.get_active_proposal_count(), 1);
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert_eq!(contract.get_active_proposal_count(), 0);
+
+        create_proposal(&mut context, &mut contract);
+        create_proposal(&mut context, &mut contract);
+    }
+
+    #[test]
+    fn test_active_proposals_expiry_and_recount() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.max_active_proposals = Some(1);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        let id = create_proposal(&mut context, &mut contract);
+
+        // Expired proposal releases its slot without being finalized.
+        testing_env!(context
+            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+            .build());
+        let new_id = create_proposal(&mut context, &mut contract);
+        assert_eq!(contract.get_active_proposal_count(), 1);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+
+        // Proposals stored before migration are counted by `recount_active`.
+        contract.active_proposals.clear();
+        contract.recount_active(0, 10);
+        assert_eq!(contract.active_proposals.to_vec(), vec![new_id]);
+    }
+
+    #[test]
+    fn test_reclaim_expired_bonds() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        let approved_id = create_proposal(&mut context, &mut contract);
+        contract.act_proposal(approved_id, Action::VoteApprove, None);
+        assert_eq!(contract.reclaim_expired_bonds(0, 10), 0);
+        // The bond escrowed when the proposal was added is returned, not the current one.
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.proposal_bond = U128(to_yocto("2"));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        let locked_amount = contract.locked_amount;
+
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(1_000_000_000 * 24 * 60 * 60 * 8)
+            .build());
+        assert_eq!(contract.reclaim_expired_bonds(0, 10), 1);
+        assert_eq!(contract.locked_amount, locked_amount - to_yocto("1"));
+        assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains(
+            "\"event\":\"reclaim_bond\",\"data\":{\"proposal_id\":0,\"proposer\":\"bob\",\"token_id\":null,\"amount\":\"1000000000000000000000000\"}"
+        )));
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Expired
+        );
+        assert_eq!(contract.get_active_proposal_count(), 0);
+        assert_eq!(contract.reclaim_expired_bonds(0, 10), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_BLOB_REFERENCED")]
+    fn test_remove_referenced_blob() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let hash = [1u8; 32];
+        env::storage_write(&hash, &[0u8; 8]);
+        contract.blobs.insert(&hash, &accounts(1));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "upgrade".to_string(),
+            kind: ProposalKind::UpgradeSelf { hash: hash.into() },
+        });
+        assert_eq!(contract.get_blob_references(hash.into()), vec![(id, true)]);
+        contract.remove_blob(hash.into());
+    }
+
+    #[test]
+    fn test_deploy_executor() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let hash = [1u8; 32];
+        env::storage_write(&hash, &[0u8; 8]);
+        contract.blobs.insert(&hash, &accounts(1));
+        let executor_id: AccountId = format!("exec.{}", accounts(0)).parse().unwrap();
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "deploy executor".to_string(),
+            kind: ProposalKind::DeployExecutor {
+                executor_id: executor_id.clone(),
+                hash: hash.into(),
+                amount: U128(to_yocto("5")),
+            },
+        });
+        assert_eq!(contract.get_blob_references(hash.into()), vec![(id, true)]);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let receipt = near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == executor_id)
+            .unwrap();
+        assert!(matches!(
+            receipt.actions[0],
+            near_sdk::mock::VmAction::CreateAccount
+        ));
+        assert!(
+            matches!(receipt.actions[1], near_sdk::mock::VmAction::Transfer { deposit } if deposit == to_yocto("5"))
+        );
+        assert!(matches!(
+            receipt.actions[2],
+            near_sdk::mock::VmAction::DeployContract { .. }
+        ));
+        assert!(matches!(
+            &receipt.actions[3],
+            near_sdk::mock::VmAction::FunctionCall { function_name, .. } if function_name == "new"
+        ));
+    }
+
+    #[test]
+    fn test_executor_call() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let executor_id: AccountId = format!("exec.{}", accounts(0)).parse().unwrap();
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy
+            .executors
+            .insert("call".to_string(), executor_id.clone());
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "call".to_string(),
+            kind: ProposalKind::FunctionCall {
+                receiver_id: accounts(2),
+                actions: vec![ActionCall {
+                    method_name: "ping".to_string(),
+                    args: vec![].into(),
+                    deposit: U128(to_yocto("2")),
+                    gas: 10_000_000_000_000.into(),
+                    funding_source: FundingSource::Treasury,
+                }],
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let receipt = near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == executor_id)
+            .unwrap();
+        match &receipt.actions[0] {
+            near_sdk::mock::VmAction::FunctionCall {
+                function_name,
+                args,
+                deposit,
+                ..
+            } => {
+                assert_eq!(function_name, "proxy_call");
+                assert_eq!(*deposit, to_yocto("2"));
+                let args: near_sdk::serde_json::Value =
+                    near_sdk::serde_json::from_slice(args).unwrap();
+                assert_eq!(args["receiver_id"], accounts(2).as_str());
+                assert_eq!(args["method_name"], "ping");
+            }
+            _ => panic!("Unexpected action"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_EXECUTOR")]
+    fn test_invalid_executor() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.executors.insert("call".to_string(), accounts(2));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::ChangePolicy {
+                policy: VersionedPolicy::Current(policy),
+            },
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_GAS_CONFIG")]
+    fn test_set_gas_config() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut gas_config = contract.get_gas_config();
+        gas_config.ft_transfer = near_sdk::Gas(20_000_000_000_000);
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "gas".to_string(),
+            kind: ProposalKind::SetGasConfig {
+                gas_config: gas_config.clone(),
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(contract.get_gas_config(), gas_config);
+
+        gas_config.ft_transfer = near_sdk::Gas(200_000_000_000_000);
+        contract.add_proposal(ProposalInput {
+            description: "gas".to_string(),
+            kind: ProposalKind::SetGasConfig { gas_config },
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_add_proposal_from_factory() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id("dao.factory.near".parse().unwrap())
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let proposal = || ProposalInput {
+            description: "staking".to_string(),
+            kind: ProposalKind::SetStakingContract {
+                staking_id: "dao-staking.factory.near".parse().unwrap(),
+            },
+        };
+        testing_env!(context
+            .predecessor_account_id("factory.near".parse().unwrap())
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal_from_factory(proposal(), accounts(1));
+        assert_eq!(contract.get_proposal(id).proposal.proposer, accounts(1));
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.add_proposal_from_factory(proposal(), accounts(1));
+    }
+
+    /// Returns the `paused` argument of the ping to the factory created by the last call, if any.
+    fn factory_ping() -> Option<bool> {
+        let factory_id = internal_get_factory_info().factory_id;
+        near_sdk::test_utils::get_created_receipts()
+            .into_iter()
+            .filter(|receipt| receipt.receiver_id == factory_id)
+            .find_map(|receipt| match &receipt.actions[0] {
+                near_sdk::mock::VmAction::FunctionCall {
+                    function_name,
+                    args,
+                    ..
+                } if function_name == "ping" => {
+                    let args: near_sdk::serde_json::Value =
+                        near_sdk::serde_json::from_slice(args).unwrap();
+                    args["paused"].as_bool()
+                }
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_ping_factory() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .block_timestamp(FACTORY_PING_INTERVAL)
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        assert_eq!(factory_ping(), Some(false));
+
+        // At most one ping per interval.
+        testing_env!(context
+            .block_timestamp(FACTORY_PING_INTERVAL * 2 - 1)
+            .attached_deposit(0)
+            .build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(factory_ping(), None);
+
+        // Without members able to approve proposals, the DAO reports itself paused.
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.roles.retain(|role| role.name != "council");
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.block_timestamp(FACTORY_PING_INTERVAL * 2).build());
+        create_proposal(&mut context, &mut contract);
+        assert_eq!(factory_ping(), Some(true));
+    }
+
+    #[test]
+    fn test_act_proposal_result() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let id = create_proposal(&mut context, &mut contract);
+        let result = contract.act_proposal(id, Action::VoteReject, None);
+        assert_eq!(
+            result,
+            ActProposalResult {
+                proposal_id: id,
+                counted_weights: vec![("council".to_string(), U128(1))].into_iter().collect(),
+                vote_counts: vec![("council".to_string(), [U128(0), U128(1), U128(0)])]
+                    .into_iter()
+                    .collect(),
+                status: ProposalStatus::InProgress,
+            }
+        );
+    }
+
+    #[test]
+    fn test_proposal_token_bond() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.proposal_token_bonds.insert(
+            consts::KIND_VOTE.to_string(),
+            ProposalTokenBond {
+                token_id: accounts(3),
+                amount: U128(100),
+            },
+        );
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        let msg = r#"{"proposal": {"description": "poll", "kind": "Vote"}}"#;
+        contract.ft_on_transfer(accounts(1), U128(100), msg.to_string());
+        let id = contract.get_last_proposal_id() - 1;
+        assert_eq!(contract.get_proposal(id).proposal.proposer, accounts(1));
+        assert_eq!(contract.locked_amount, 0);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert!(contract.proposal_token_bonds.get(&id).is_none());
+        assert_eq!(contract.locked_amount, 0);
+    }
 }
@@ -0,0 +1,272 @@
+//! Interest-free loans from the treasury, approved with `ApproveLoan` proposals and repaid in
+//! the lent token. Overdue loans are escalated with `LoanDefault` proposals.
+
+use near_sdk::json_types::{U128, U64};
+
+use crate::types::{convert_old_to_new_token, OldAccountId};
+use crate::*;
+
+/// Status of the loan.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum LoanStatus {
+    /// Loan was paid out and is not fully repaid yet.
+    Active,
+    /// Outstanding amount was repaid.
+    Repaid,
+    /// Loan was declared in default by a `LoanDefault` proposal. Can still be repaid.
+    Defaulted,
+}
+
+/// Loan paid out by an `ApproveLoan` proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Loan {
+    pub borrower: AccountId,
+    /// Can be "" for $NEAR or a valid account id.
+    pub token_id: OldAccountId,
+    pub amount: U128,
+    /// Amount left to repay.
+    pub outstanding: U128,
+    /// Time after which the loan is overdue.
+    pub due: U64,
+    /// Reference to the collateral agreed off-chain, e.g. a link or an escrow account.
+    pub collateral: Option<String>,
+    pub status: LoanStatus,
+    /// Pending `LoanDefault` proposal, if any.
+    pub default_proposal_id: Option<u64>,
+}
+
+/// Message passed to `ft_on_transfer` to repay a loan in the lent token.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LoanRepayMsg {
+    pub loan_id: u64,
+}
+
+impl Contract {
+    pub(crate) fn internal_get_loan(&self, id: u64) -> Loan {
+        self.loans.get(&id).expect("ERR_NO_LOAN")
+    }
+
+    /// Records loan paid out by given `ApproveLoan` proposal.
+    pub(crate) fn internal_add_loan(&mut self, proposal_id: u64, kind: &ProposalKind) {
+        if let ProposalKind::ApproveLoan {
+            borrower,
+            token_id,
+            amount,
+            due,
+            collateral,
+        } = kind
+        {
+            self.loans.insert(
+                &proposal_id,
+                &Loan {
+                    borrower: borrower.clone(),
+                    token_id: token_id.clone(),
+                    amount: *amount,
+                    outstanding: *amount,
+                    due: *due,
+                    collateral: collateral.clone(),
+                    status: LoanStatus::Active,
+                    default_proposal_id: None,
+                },
+            );
+        }
+    }
+
+    /// Checks that a `LoanDefault` proposal can be added for given loan.
+    pub(crate) fn internal_assert_loan_overdue(&self, id: u64) {
+        let loan = self.internal_get_loan(id);
+        assert_eq!(loan.status, LoanStatus::Active, "ERR_LOAN_NOT_ACTIVE");
+        assert!(env::block_timestamp() > loan.due.0, "ERR_LOAN_NOT_OVERDUE");
+        assert!(
+            loan.default_proposal_id.is_none(),
+            "ERR_LOAN_DEFAULT_PENDING"
+        );
+    }
+
+    /// Sets or clears the pending `LoanDefault` proposal of given loan.
+    pub(crate) fn internal_set_loan_default_proposal(&mut self, id: u64, proposal_id: Option<u64>) {
+        let mut loan = self.internal_get_loan(id);
+        loan.default_proposal_id = proposal_id;
+        self.loans.insert(&id, &loan);
+    }
+
+    /// Called when `LoanDefault` proposal is approved.
+    pub(crate) fn internal_default_loan(&mut self, id: u64) {
+        let mut loan = self.internal_get_loan(id);
+        loan.default_proposal_id = None;
+        if loan.status == LoanStatus::Active {
+            loan.status = LoanStatus::Defaulted;
+        } else {
+            // Repaid while the proposal was voted on.
+            env::log_str(&format!("ERR_LOAN_NOT_ACTIVE:{}", id));
+        }
+        self.loans.insert(&id, &loan);
+    }
+
+    /// Repays given loan in `token_id`. Returns the amount exceeding the outstanding balance.
+    pub(crate) fn internal_repay_loan(
+        &mut self,
+        id: u64,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) -> Balance {
+        let mut loan = self.internal_get_loan(id);
+        assert_ne!(loan.status, LoanStatus::Repaid, "ERR_LOAN_REPAID");
+        assert_eq!(
+            &convert_old_to_new_token(&loan.token_id),
+            token_id,
+            "ERR_LOAN_WRONG_TOKEN"
+        );
+        let repaid = std::cmp::min(amount, loan.outstanding.0);
+        loan.outstanding.0 -= repaid;
+        if loan.outstanding.0 == 0 {
+            loan.status = LoanStatus::Repaid;
+        }
+        self.loans.insert(&id, &loan);
+        amount - repaid
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Repays given $NEAR loan with the attached deposit. Anyone can repay.
+    /// Deposit exceeding the outstanding balance is returned.
+    #[payable]
+    pub fn loan_repay(&mut self, id: u64) {
+        let excess = self.internal_repay_loan(id, &None, env::attached_deposit());
+        if excess > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(excess);
+        }
+    }
+
+    /// Adds `LoanDefault` proposal for given overdue loan. Anyone can call it.
+    /// The DAO itself is the proposer, so it must be allowed to add `loan_default` proposals.
+    /// The bond is paid from the treasury. Returns id of the new proposal.
+    pub fn loan_check_overdue(&mut self, id: u64) -> u64 {
+        let policy = self.policy.get().unwrap().to_policy();
+        let bond = policy.proposal_bond.0;
+        assert!(
+            self.internal_available_balance() >= bond,
+            "ERR_NOT_ENOUGH_BALANCE_FOR_BOND"
+        );
+        self.internal_add_proposal(
+            &policy,
+            env::current_account_id(),
+            ProposalInput {
+                description: format!("Loan {} is overdue", id),
+                kind: ProposalKind::LoanDefault { loan_id: id },
+            },
+            bond,
+        )
+    }
+
+    pub fn get_loan(&self, id: u64) -> Option<Loan> {
+        self.loans.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    /// Approves a loan of 10 $NEAR to `accounts(2)`, due at 100.
+    fn setup_loan(context: &mut VMContextBuilder) -> (Contract, u64) {
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "loan".to_string(),
+            kind: ProposalKind::ApproveLoan {
+                borrower: accounts(2),
+                token_id: String::from(OLD_BASE_TOKEN),
+                amount: U128(to_yocto("10")),
+                due: U64(100),
+                collateral: None,
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(contract.get_loan(id).is_none());
+        testing_env!(
+            context.attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        contract.on_proposal_callback(id);
+        (contract, id)
+    }
+
+    #[test]
+    fn test_loan_repay() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup_loan(&mut context);
+        assert_eq!(contract.get_loan(id).unwrap().status, LoanStatus::Active);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(to_yocto("4"))
+            .build());
+        contract.loan_repay(id);
+        assert_eq!(
+            contract.get_loan(id).unwrap().outstanding,
+            U128(to_yocto("6"))
+        );
+        testing_env!(context.attached_deposit(to_yocto("7")).build());
+        contract.loan_repay(id);
+        let loan = contract.get_loan(id).unwrap();
+        assert_eq!(loan.outstanding, U128(0));
+        assert_eq!(loan.status, LoanStatus::Repaid);
+    }
+
+    #[test]
+    fn test_loan_default() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup_loan(&mut context);
+        testing_env!(context
+            .predecessor_account_id(accounts(3))
+            .block_timestamp(101)
+            .build());
+        let default_id = contract.loan_check_overdue(id);
+        assert_eq!(
+            contract.get_proposal(default_id).proposal.proposer,
+            accounts(0)
+        );
+        assert_eq!(
+            contract.get_loan(id).unwrap().default_proposal_id,
+            Some(default_id)
+        );
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.act_proposal(default_id, Action::VoteApprove, None);
+        let loan = contract.get_loan(id).unwrap();
+        assert_eq!(loan.status, LoanStatus::Defaulted);
+        assert_eq!(loan.default_proposal_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_LOAN_NOT_OVERDUE")]
+    fn test_loan_not_overdue() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup_loan(&mut context);
+        contract.loan_check_overdue(id);
+    }
+}
@@ -0,0 +1,82 @@
+use near_sdk::PublicKey;
+
+use crate::*;
+
+impl Contract {
+    /// Records the key of the transaction if it was signed by this account.
+    pub(crate) fn internal_register_signer_key(&mut self) -> bool {
+        if env::signer_account_id() != env::current_account_id() {
+            return false;
+        }
+        self.full_access_keys.insert(&env::signer_account_pk());
+        true
+    }
+
+    /// Deletes all registered full access keys from this account.
+    /// Each key is deleted in a separate promise, so a key that was already removed
+    /// doesn't prevent deletion of the others.
+    pub(crate) fn internal_lockdown(&mut self) {
+        for public_key in self.full_access_keys.to_vec() {
+            Promise::new(env::current_account_id()).delete_key(public_key);
+        }
+        self.full_access_keys.clear();
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Registers the full access key that signed this transaction, so `Lockdown` can delete it.
+    /// Must be signed with a key of this account.
+    pub fn register_full_access_key(&mut self) {
+        assert!(self.internal_register_signer_key(), "ERR_NOT_ACCOUNT_KEY");
+    }
+
+    /// Full access keys of this account that `Lockdown` will delete.
+    pub fn get_full_access_keys(&self) -> Vec<PublicKey> {
+        self.full_access_keys.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::proposals::{ProposalInput, ProposalKind};
+    use crate::{Action, Config};
+
+    use super::*;
+
+    #[test]
+    fn test_lockdown() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .signer_account_id(accounts(0))
+            .signer_account_pk(
+                "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+                    .parse()
+                    .unwrap()
+            )
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        assert_eq!(contract.get_full_access_keys().len(), 1);
+
+        testing_env!(context
+            .signer_account_id(accounts(1))
+            .attached_deposit(to_yocto("1"))
+            .build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Lockdown,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(contract.get_full_access_keys().is_empty());
+    }
+}
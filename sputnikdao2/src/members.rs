@@ -0,0 +1,282 @@
+//! Chunked execution of `AddMembersToRole` proposals and history of role membership.
+
+use near_sdk::json_types::U64;
+
+use crate::*;
+
+/// Number of members added to the role per call.
+pub const MEMBERS_PER_CHUNK: usize = 100;
+
+/// Addition or removal of an account to a group role by a proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MembershipChange {
+    pub role: String,
+    /// True if the account was added to the role, false if removed.
+    pub added: bool,
+    pub proposal_id: u64,
+    pub timestamp: U64,
+}
+
+impl Contract {
+    /// Records accounts added to or removed from group roles between the two policies.
+    pub(crate) fn internal_record_membership_changes(
+        &mut self,
+        proposal_id: u64,
+        old_policy: &Policy,
+        new_policy: &Policy,
+    ) {
+        for (from, to, added) in [
+            (old_policy, new_policy, true),
+            (new_policy, old_policy, false),
+        ] {
+            for role in to.roles.iter() {
+                let members = match &role.kind {
+                    RoleKind::Group(members) => members,
+                    _ => continue,
+                };
+                let previous = match from.internal_get_role(&role.name).map(|r| &r.kind) {
+                    Some(RoleKind::Group(previous)) => Some(previous),
+                    _ => None,
+                };
+                for account_id in members {
+                    if matches!(previous, Some(previous) if previous.contains(account_id)) {
+                        continue;
+                    }
+                    let mut history = self.membership_history.get(account_id).unwrap_or_default();
+                    history.push(MembershipChange {
+                        role: role.name.clone(),
+                        added,
+                        proposal_id,
+                        timestamp: U64(env::block_timestamp()),
+                    });
+                    self.membership_history.insert(account_id, &history);
+                }
+            }
+        }
+    }
+
+    /// Adds chunk of members starting at `from_index` to the role.
+    /// Records the index of the next chunk if some members are left.
+    pub(crate) fn internal_add_members_chunk(
+        &mut self,
+        proposal_id: u64,
+        role: &String,
+        members: &[AccountId],
+        from_index: usize,
+    ) {
+        let to_index = std::cmp::min(from_index + MEMBERS_PER_CHUNK, members.len());
+        let old_policy = self.policy.get().unwrap().to_policy();
+        let mut policy = old_policy.clone();
+        policy.add_members_to_role(role, &members[from_index..to_index]);
+        self.internal_record_membership_changes(proposal_id, &old_policy, &policy);
+        self.policy.set(&VersionedPolicy::Current(policy));
+        if to_index < members.len() {
+            self.member_additions
+                .insert(&proposal_id, &(to_index as u64));
+        } else {
+            self.member_additions.remove(&proposal_id);
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Adds the next chunk of members of an approved `AddMembersToRole` proposal.
+    pub fn continue_add_members(&mut self, id: u64) {
+        let from_index = self
+            .member_additions
+            .get(&id)
+            .expect("ERR_NO_PENDING_MEMBERS");
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        match &proposal.kind {
+            ProposalKind::AddMembersToRole { role, members } => {
+                self.internal_add_members_chunk(id, role, members, from_index as usize)
+            }
+            _ => env::panic_str("ERR_WRONG_KIND"),
+        }
+    }
+
+    /// Group roles the account was added to or removed from by proposals, oldest first.
+    pub fn get_membership_history(&self, account_id: AccountId) -> Vec<MembershipChange> {
+        self.membership_history.get(&account_id).unwrap_or_default()
+    }
+
+    /// Index of the next member to add for given `AddMembersToRole` proposal, if any are left.
+    pub fn get_pending_members_index(&self, id: u64) -> Option<u64> {
+        self.member_additions.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    #[test]
+    fn test_add_members_in_chunks() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let members: Vec<AccountId> = (0..MEMBERS_PER_CHUNK + 10)
+            .map(|i| format!("member{}.near", i).parse().unwrap())
+            .collect();
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddMembersToRole {
+                role: "council".to_string(),
+                members: members.clone(),
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let council = |contract: &Contract| match &contract
+            .get_policy()
+            .internal_get_role(&"council".to_string())
+            .unwrap()
+            .kind
+        {
+            RoleKind::Group(members) => members.len(),
+            _ => unreachable!(),
+        };
+        assert_eq!(council(&contract), MEMBERS_PER_CHUNK + 1);
+        assert_eq!(
+            contract.get_pending_members_index(id),
+            Some(MEMBERS_PER_CHUNK as u64)
+        );
+        contract.continue_add_members(id);
+        assert_eq!(council(&contract), MEMBERS_PER_CHUNK + 11);
+        assert_eq!(contract.get_pending_members_index(id), None);
+        assert_eq!(
+            contract.get_membership_history(members[MEMBERS_PER_CHUNK].clone()),
+            vec![MembershipChange {
+                role: "council".to_string(),
+                added: true,
+                proposal_id: id,
+                timestamp: U64(0),
+            }]
+        );
+    }
+
+    fn replace_council_proposal(contract: &mut Contract, new_members: Vec<AccountId>) -> u64 {
+        contract.add_proposal(ProposalInput {
+            description: "handover".to_string(),
+            kind: ProposalKind::ReplaceRoleMembers {
+                role: "council".to_string(),
+                new_members,
+            },
+        })
+    }
+
+    #[test]
+    fn test_replace_role_members() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = replace_council_proposal(&mut contract, vec![accounts(2), accounts(3)]);
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let role = contract
+            .get_policy()
+            .internal_get_role(&"council".to_string())
+            .unwrap()
+            .kind
+            .clone();
+        assert_eq!(
+            role,
+            RoleKind::Group(vec![accounts(2), accounts(3)].into_iter().collect())
+        );
+        assert!(!contract.get_membership_history(accounts(1))[0].added);
+        assert!(contract.get_membership_history(accounts(2))[0].added);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_PROPOSERS_LEFT")]
+    fn test_replace_role_members_no_proposers() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        // Only the council can add proposals.
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.roles.retain(|role| role.name == "council");
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        replace_council_proposal(&mut contract, vec![]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NO_VOTERS_LEFT")]
+    fn test_replace_role_members_no_voters() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        // Everyone can still add proposals, but only the council can vote.
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        replace_council_proposal(&mut contract, vec![]);
+    }
+
+    /// Adds proposal to add or remove `accounts(2)` from the council at given time.
+    fn add_member_proposal(
+        context: &mut VMContextBuilder,
+        contract: &mut Contract,
+        block_timestamp: u64,
+        add: bool,
+    ) -> u64 {
+        testing_env!(context
+            .attached_deposit(to_yocto("1"))
+            .block_timestamp(block_timestamp)
+            .build());
+        let (member_id, role) = (accounts(2), "council".to_string());
+        contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: if add {
+                ProposalKind::AddMemberToRole { member_id, role }
+            } else {
+                ProposalKind::RemoveMemberFromRole { member_id, role }
+            },
+        })
+    }
+
+    #[test]
+    fn test_membership_history() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let add_id = add_member_proposal(&mut context, &mut contract, 10, true);
+        contract.act_proposal(add_id, Action::VoteApprove, None);
+        let remove_id = add_member_proposal(&mut context, &mut contract, 20, false);
+        contract.act_proposal(remove_id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(remove_id, Action::VoteApprove, None);
+        let history = contract.get_membership_history(accounts(2));
+        assert_eq!(
+            history
+                .iter()
+                .map(|change| (change.added, change.proposal_id, change.timestamp.0))
+                .collect::<Vec<_>>(),
+            vec![(true, add_id, 10), (false, remove_id, 20)]
+        );
+    }
+}
@@ -0,0 +1,322 @@
+//! Mirroring of proposals as advisory votes in a parent DAO.
+
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::Value;
+
+use crate::*;
+
+/// Configuration of which proposals are mirrored to the parent DAO.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteMirror {
+    /// DAO where advisory `Vote` proposals are created.
+    pub parent_id: AccountId,
+    /// Policy labels of the proposal kinds to mirror, e.g. `transfer`.
+    pub kind_labels: Vec<String>,
+    /// Bond attached to the parent proposal, paid by the proposer on top of the proposal bond.
+    pub bond: U128,
+}
+
+/// Advisory proposal in the parent DAO linked to a proposal of this DAO.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MirrorLink {
+    pub parent_id: AccountId,
+    pub parent_proposal_id: u64,
+    /// Last status of the parent proposal recorded with `sync_mirror`.
+    pub parent_status: Option<ProposalStatus>,
+    /// Account that attached the parent proposal's bond.
+    pub proposer: AccountId,
+    /// Bond to refund to the proposer once the parent DAO returned it, 0 once refunded or if the
+    /// treasury paid it.
+    pub bond: U128,
+}
+
+impl Contract {
+    /// Bond of the parent proposal if given kind is mirrored, 0 otherwise.
+    pub(crate) fn internal_mirror_bond(&self, kind: &ProposalKind) -> Balance {
+        match &self.vote_mirror {
+            Some(mirror)
+                if mirror
+                    .kind_labels
+                    .iter()
+                    .any(|kind_label| kind_label == kind.to_policy_label()) =>
+            {
+                mirror.bond.0
+            }
+            _ => 0,
+        }
+    }
+
+    /// Creates advisory proposal in the parent DAO if the proposal's kind is mirrored.
+    /// The proposer attached the parent's bond, unless it's the DAO itself: then the treasury
+    /// pays it, and mirroring is skipped if the treasury can't.
+    /// The parent DAO returns the bond to this DAO, which refunds it to the proposer, see
+    /// `on_mirror_proposal` and `on_mirror_status`.
+    pub(crate) fn internal_mirror_proposal(
+        &self,
+        proposal_id: u64,
+        proposer: &AccountId,
+        proposal: &ProposalInput,
+    ) {
+        let mirror = match &self.vote_mirror {
+            Some(mirror) => mirror,
+            None => return,
+        };
+        let label = proposal.kind.to_policy_label();
+        if !mirror
+            .kind_labels
+            .iter()
+            .any(|kind_label| kind_label == label)
+        {
+            return;
+        }
+        let refund = if proposer == &env::current_account_id() {
+            if self.internal_available_balance() < mirror.bond.0 {
+                env::log_str("ERR_NOT_ENOUGH_BALANCE_FOR_MIRROR");
+                return;
+            }
+            0
+        } else {
+            mirror.bond.0
+        };
+        let description = format!(
+            "Advisory vote on proposal {} of {}: {}",
+            proposal_id,
+            env::current_account_id(),
+            proposal.kind.summary()
+        );
+        ext_parent_dao::add_proposal(
+            ProposalInput {
+                description,
+                kind: ProposalKind::Vote,
+            },
+            mirror.parent_id.clone(),
+            mirror.bond.0,
+            self.gas_config.mirror_proposal,
+        )
+        .then(ext_self::on_mirror_proposal(
+            proposal_id,
+            mirror.parent_id.clone(),
+            proposer.clone(),
+            U128(refund),
+            env::current_account_id(),
+            0,
+            self.gas_config.mirror_callback,
+        ));
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Records id of the advisory proposal created in the parent DAO, together with the bond
+    /// `proposer` attached for it. If the parent DAO failed to add the proposal, the bond came
+    /// back with the failed call and is refunded right away.
+    #[private]
+    pub fn on_mirror_proposal(
+        &mut self,
+        proposal_id: u64,
+        parent_id: AccountId,
+        proposer: AccountId,
+        bond: U128,
+    ) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                let parent_proposal_id: u64 =
+                    near_sdk::serde_json::from_slice(&value).expect("ERR_INVALID_PROPOSAL_ID");
+                self.mirror_links.insert(
+                    &proposal_id,
+                    &MirrorLink {
+                        parent_id,
+                        parent_proposal_id,
+                        parent_status: None,
+                        proposer,
+                        bond,
+                    },
+                );
+            }
+            _ => {
+                if bond.0 > 0 {
+                    Promise::new(proposer).transfer(bond.0);
+                }
+            }
+        }
+    }
+
+    /// Fetches status of the advisory proposal in the parent DAO and records it.
+    pub fn sync_mirror(&mut self, proposal_id: u64) -> Promise {
+        let link = self.mirror_links.get(&proposal_id).expect("ERR_NO_MIRROR");
+        ext_parent_dao::get_proposal(
+            link.parent_proposal_id,
+            link.parent_id,
+            0,
+            self.gas_config.mirror_status,
+        )
+        .then(ext_self::on_mirror_status(
+            proposal_id,
+            env::current_account_id(),
+            0,
+            self.gas_config.mirror_callback,
+        ))
+    }
+
+    /// Records status of the advisory proposal returned by the parent DAO.
+    #[private]
+    pub fn on_mirror_status(&mut self, proposal_id: u64) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        if let PromiseResult::Successful(value) = env::promise_result(0) {
+            let output: Value =
+                near_sdk::serde_json::from_slice(&value).expect("ERR_INVALID_PROPOSAL");
+            let status: ProposalStatus = near_sdk::serde_json::from_value(output["status"].clone())
+                .expect("ERR_INVALID_PROPOSAL");
+            let mut link = self.mirror_links.get(&proposal_id).expect("ERR_NO_MIRROR");
+            if link.bond.0 > 0
+                && matches!(
+                    status,
+                    ProposalStatus::Approved | ProposalStatus::Rejected | ProposalStatus::Expired
+                )
+            {
+                // Parent DAO returned the bond when it finalized the proposal.
+                Promise::new(link.proposer.clone()).transfer(link.bond.0);
+                link.bond = U128(0);
+            }
+            link.parent_status = Some(status);
+            self.mirror_links.insert(&proposal_id, &link);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Config, OLD_BASE_TOKEN};
+
+    use super::*;
+
+    fn bond_refunds() -> Vec<(AccountId, Balance)> {
+        get_created_receipts()
+            .into_iter()
+            .filter_map(|receipt| match receipt.actions[0] {
+                VmAction::Transfer { deposit } => Some((receipt.receiver_id, deposit)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn transfer_proposal() -> ProposalInput {
+        ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::from(OLD_BASE_TOKEN),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+            },
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_MIN_BOND")]
+    fn test_vote_mirroring_without_bond() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.vote_mirror = Some(VoteMirror {
+            parent_id: accounts(3),
+            kind_labels: vec!["transfer".to_string()],
+            bond: U128(to_yocto("1")),
+        });
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_proposal(transfer_proposal());
+    }
+
+    #[test]
+    fn test_vote_mirroring() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.vote_mirror = Some(VoteMirror {
+            parent_id: accounts(3),
+            kind_labels: vec!["transfer".to_string()],
+            bond: U128(to_yocto("1")),
+        });
+        // Proposer pays the parent's bond on top of the proposal bond.
+        testing_env!(context.attached_deposit(to_yocto("2")).build());
+        let id = contract.add_proposal(transfer_proposal());
+        assert_eq!(contract.locked_amount, to_yocto("1"));
+
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"7".to_vec())],
+        );
+        contract.on_mirror_proposal(id, accounts(3), accounts(1), U128(to_yocto("1")));
+        assert_eq!(
+            contract.get_proposal(id).mirror.unwrap().parent_proposal_id,
+            7
+        );
+
+        testing_env!(
+            context.build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(
+                br#"{"id":7,"status":"Approved"}"#.to_vec()
+            )],
+        );
+        contract.on_mirror_status(id);
+        let mirror = contract.get_proposal(id).mirror.unwrap();
+        assert_eq!(mirror.parent_status, Some(ProposalStatus::Approved));
+        // Bond returned by the parent DAO is refunded to the proposer once.
+        assert_eq!(mirror.bond, U128(0));
+        assert_eq!(bond_refunds(), vec![(accounts(1), to_yocto("1"))]);
+    }
+
+    #[test]
+    fn test_vote_mirroring_failed() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_mirror_proposal(0, accounts(3), accounts(1), U128(to_yocto("1")));
+        assert!(contract.mirror_links.get(&0).is_none());
+        assert_eq!(bond_refunds(), vec![(accounts(1), to_yocto("1"))]);
+    }
+}
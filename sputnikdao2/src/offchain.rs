@@ -0,0 +1,251 @@
+//! Results of off-chain snapshot votes, posted by the policy's tally oracle role.
+
+use near_sdk::json_types::{Base58CryptoHash, U128};
+
+use crate::consts::OFFCHAIN_TALLY_KINDS;
+use crate::events::Event;
+use crate::*;
+
+/// Result of an off-chain snapshot vote on a proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct OffchainTally {
+    /// Merkle root of the snapshot's voter weights and votes, for verification off-chain.
+    pub merkle_root: Base58CryptoHash,
+    /// Total weight of the snapshot.
+    pub total_weight: U128,
+    pub approve_weight: U128,
+    pub reject_weight: U128,
+}
+
+/// Minimum number of oracle members that must post the same tally.
+pub const MIN_TALLY_ORACLES: usize = 2;
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Posts result of the off-chain vote on given proposal, once per oracle member.
+    /// Only members of the policy's `tally_oracle_role` can call this, which must be a group,
+    /// and only for proposal kinds listed in the policy's `offchain_tally_kinds`.
+    /// Posts are counted as votes of the oracle role: the tally is accepted once enough members
+    /// posted the same tally to reach the threshold of the role's vote policy for the proposal
+    /// kind, and at least `MIN_TALLY_ORACLES`. The accepted tally approves and executes, or
+    /// rejects the proposal if it reaches the same threshold measured against the snapshot's
+    /// total weight.
+    pub fn post_offchain_tally(&mut self, id: u64, tally: OffchainTally) -> ProposalStatus {
+        let policy = self.policy.get().unwrap().to_policy();
+        let oracle_role = policy
+            .tally_oracle_role
+            .as_ref()
+            .expect("ERR_NO_TALLY_ORACLE");
+        let role = policy
+            .internal_get_role(oracle_role)
+            .expect("ERR_MISSING_ROLE");
+        let oracle_count = role
+            .kind
+            .get_role_size()
+            .expect("ERR_TALLY_ORACLE_NOT_GROUP");
+        let sender_id = env::predecessor_account_id();
+        assert!(
+            role.kind
+                .match_user(&self.internal_account_info(sender_id.clone())),
+            "ERR_PERMISSION_DENIED"
+        );
+        assert!(
+            tally.approve_weight.0 + tally.reject_weight.0 <= tally.total_weight.0,
+            "ERR_INVALID_TALLY"
+        );
+        assert!(
+            !self.offchain_tallies.contains_key(&id),
+            "ERR_TALLY_ALREADY_ACCEPTED"
+        );
+        let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let kind_label = proposal.kind.to_policy_label();
+        assert!(
+            OFFCHAIN_TALLY_KINDS.contains(&kind_label)
+                && policy.offchain_tally_kinds.iter().any(|k| k == kind_label),
+            "ERR_OFFCHAIN_TALLY_NOT_ALLOWED"
+        );
+        assert_eq!(
+            proposal.status,
+            ProposalStatus::InProgress,
+            "ERR_PROPOSAL_NOT_IN_PROGRESS"
+        );
+        assert!(
+            proposal.submission_time.0 + policy.proposal_period.0 >= env::block_timestamp(),
+            "ERR_PROPOSAL_EXPIRED"
+        );
+
+        // Record the post as a vote of the oracle member for this tally.
+        let mut posts = self.offchain_tally_posts.get(&id).unwrap_or_default();
+        assert!(
+            posts
+                .iter()
+                .all(|(_, oracles)| !oracles.contains(&sender_id)),
+            "ERR_TALLY_ALREADY_POSTED"
+        );
+        let agreeing = match posts.iter_mut().find(|(posted, _)| *posted == tally) {
+            Some((_, oracles)) => {
+                oracles.push(sender_id.clone());
+                oracles.len()
+            }
+            None => {
+                posts.push((tally.clone(), vec![sender_id.clone()]));
+                1
+            }
+        };
+        let vote_policy = role
+            .vote_policy
+            .get(kind_label)
+            .unwrap_or(&policy.default_vote_policy);
+        let oracle_threshold = std::cmp::max(
+            vote_policy.quorum.0,
+            vote_policy.threshold.to_weight(oracle_count as Balance),
+        );
+        if (agreeing as Balance) < oracle_threshold || agreeing < MIN_TALLY_ORACLES {
+            self.offchain_tally_posts.insert(&id, &posts);
+            return proposal.status;
+        }
+        self.offchain_tally_posts.remove(&id);
+        self.offchain_tallies.insert(&id, &tally);
+
+        let threshold = vote_policy.threshold.to_weight(tally.total_weight.0);
+        let mut pending_execution = false;
+        if tally.approve_weight.0 >= threshold {
+            proposal.status = ProposalStatus::Approved;
+            pending_execution = self.internal_execute_proposal(&policy, &proposal, id);
+        } else if tally.reject_weight.0 >= threshold {
+            proposal.status = ProposalStatus::Rejected;
+            self.internal_reject_proposal(&policy, id, &proposal, true);
+        } else {
+            // Not decisive, the proposal can only expire.
+            return proposal.status;
+        }
+        self.internal_emit_event(Event::OffchainTally {
+            proposal_id: id,
+            account_id: sender_id,
+            status: proposal.status.clone(),
+        });
+        self.active_proposals.remove(&id);
+        if !pending_execution {
+            self.internal_publish_to_social(&policy, id, &proposal);
+            self.internal_notify_proposer(&policy, id, &proposal);
+        }
+        let status = proposal.status.clone();
+        self.proposals
+            .insert(&id, &VersionedProposal::Current(proposal));
+        status
+    }
+
+    /// Off-chain tally accepted for given proposal, if any.
+    pub fn get_offchain_tally(&self, proposal_id: u64) -> Option<OffchainTally> {
+        self.offchain_tallies.get(&proposal_id)
+    }
+
+    /// Whether the tally accepted for given proposal is for the snapshot with given merkle root
+    /// and total weight.
+    pub fn verify_offchain_tally(
+        &self,
+        proposal_id: u64,
+        merkle_root: Base58CryptoHash,
+        total_weight: U128,
+    ) -> bool {
+        matches!(
+            self.offchain_tallies.get(&proposal_id),
+            Some(tally) if tally.merkle_root == merkle_root && tally.total_weight == total_weight
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::Config;
+
+    use super::*;
+
+    fn setup(context: &mut VMContextBuilder, kind: ProposalKind) -> (Contract, u64) {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.tally_oracle_role = Some("council".to_string());
+        policy.offchain_tally_kinds = vec!["vote".to_string(), "transfer".to_string()];
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind,
+        });
+        testing_env!(context.attached_deposit(0).build());
+        (contract, id)
+    }
+
+    fn tally(merkle_root: Base58CryptoHash, approve_weight: u128) -> OffchainTally {
+        OffchainTally {
+            merkle_root,
+            total_weight: U128(100),
+            approve_weight: U128(approve_weight),
+            reject_weight: U128(10),
+        }
+    }
+
+    #[test]
+    fn test_post_offchain_tally() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup(&mut context, ProposalKind::Vote);
+        let merkle_root = Base58CryptoHash::from([1u8; 32]);
+
+        // A single oracle member can't decide the vote.
+        let status = contract.post_offchain_tally(id, tally(merkle_root, 60));
+        assert_eq!(status, ProposalStatus::InProgress);
+        assert!(contract.get_offchain_tally(id).is_none());
+        // Disagreeing tallies are counted separately.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        let status = contract.post_offchain_tally(id, tally(merkle_root, 90));
+        assert_eq!(status, ProposalStatus::InProgress);
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        let status = contract.post_offchain_tally(id, tally(merkle_root, 60));
+        assert_eq!(status, ProposalStatus::Approved);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+        assert!(contract.verify_offchain_tally(id, merkle_root, U128(100)));
+        assert!(!contract.verify_offchain_tally(id, merkle_root, U128(99)));
+        assert!(!contract.verify_offchain_tally(id, Base58CryptoHash::from([2u8; 32]), U128(100)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TALLY_ALREADY_POSTED")]
+    fn test_post_offchain_tally_twice() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup(&mut context, ProposalKind::Vote);
+        let merkle_root = Base58CryptoHash::from([1u8; 32]);
+        contract.post_offchain_tally(id, tally(merkle_root, 60));
+        contract.post_offchain_tally(id, tally(merkle_root, 60));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OFFCHAIN_TALLY_NOT_ALLOWED")]
+    fn test_post_offchain_tally_treasury_kind() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, id) = setup(
+            &mut context,
+            ProposalKind::Transfer {
+                token_id: String::new(),
+                receiver_id: accounts(2),
+                amount: U128(1),
+                msg: None,
+            },
+        );
+        contract.post_offchain_tally(id, tally(Base58CryptoHash::from([1u8; 32]), 60));
+    }
+}
@@ -6,8 +6,9 @@ use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{env, AccountId, Balance};
 
+use crate::consts::*;
 use crate::proposals::{PolicyParameters, Proposal, ProposalKind, ProposalStatus, Vote};
-use crate::types::Action;
+use crate::types::{Action, OldAccountId};
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
@@ -75,6 +76,7 @@ pub struct RolePermission {
     pub vote_policy: HashMap<String, VotePolicy>,
 }
 
+#[derive(Clone)]
 pub struct UserInfo {
     pub account_id: AccountId,
     pub amount: Balance,
@@ -129,6 +131,10 @@ pub struct VotePolicy {
     pub quorum: U128,
     /// How many votes to pass this vote.
     pub threshold: WeightOrRatio,
+    /// Minimum number of distinct voters of the role, regardless of their weight.
+    /// Avoids a single large holder satisfying the quorum of token weighted votes alone.
+    #[serde(default)]
+    pub min_voters: u32,
 }
 
 impl Default for VotePolicy {
@@ -137,10 +143,94 @@ impl Default for VotePolicy {
             weight_kind: WeightKind::RoleWeight,
             quorum: U128(0),
             threshold: WeightOrRatio::Ratio(1, 2),
+            min_voters: 0,
         }
     }
 }
 
+/// Period over which `SpendLimits::max_period_outflow` is accumulated: 30 days.
+pub const SPEND_LIMIT_PERIOD: u64 = 1_000_000_000 * 60 * 60 * 24 * 30;
+
+/// Limits on treasury outflows of proposals, enforced on execution: transfers, function call
+/// deposits, bounty and grant payouts and loans. Ragequit payouts are counted in the period
+/// outflow, but never rejected.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SpendLimits {
+    /// Max share of the available $NEAR balance a single proposal can pay out, in basis points.
+    pub max_transfer_bps: u32,
+    /// Max total amount of $NEAR paid out in a single `SPEND_LIMIT_PERIOD`.
+    pub max_period_outflow: U128,
+    /// Max total amount of each token paid out in a single `SPEND_LIMIT_PERIOD`.
+    /// Tokens not listed can't be paid out by proposals.
+    #[serde(default)]
+    pub max_token_period_outflows: Vec<(AccountId, U128)>,
+}
+
+/// Outflow of a proposal counted against the spend limits until its payout is done.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RecordedOutflow {
+    pub token_id: Option<AccountId>,
+    pub amount: Balance,
+    /// Start of the spend limit period it was counted in.
+    pub period_start: u64,
+}
+
+/// Proposal bond paid in a fungible token via `ft_transfer_call`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalTokenBond {
+    pub token_id: AccountId,
+    pub amount: U128,
+}
+
+/// Opt-in member exit for token weighted DAOs.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RagequitPolicy {
+    /// Treasury tokens paid out pro-rata on ragequit. "" for $NEAR.
+    pub tokens: Vec<OldAccountId>,
+    /// Minimum time between the last delegation to the member and ragequit.
+    pub cooldown: U64,
+}
+
+/// Minimum threshold of `MigrateTreasury` proposals, applied on top of the vote policy.
+pub const MIGRATE_TREASURY_MIN_THRESHOLD: WeightOrRatio = WeightOrRatio::Ratio(2, 3);
+
+/// Relaxed threshold for `Transfer` proposals to receivers with a history of successful payouts.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TrustedTransferRule {
+    /// Token this rule applies to. Can be "" for $NEAR or a valid account id.
+    pub token_id: OldAccountId,
+    /// Minimum number of successful past payouts of the token to the receiver.
+    pub min_payouts: u32,
+    /// Payouts of less than the lowest `min_payout_amount` of the token's rules are not counted.
+    pub min_payout_amount: U128,
+    /// Rule applies only to transfers of less than this amount.
+    pub max_amount: U128,
+    /// Threshold used instead of the one from the role's vote policy. Weight kind and quorum are kept.
+    pub threshold: WeightOrRatio,
+}
+
+/// Threshold for `Transfer` proposals of a token up to a given amount.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferTier {
+    /// Token this tier applies to. Can be "" for $NEAR or a valid account id.
+    pub token_id: OldAccountId,
+    /// Tier applies only to transfers of less than this amount. If several tiers of the token
+    /// match, the one with the lowest `max_amount` applies.
+    pub max_amount: U128,
+    /// Threshold used instead of the one from the role's vote policy. Weight kind and quorum are kept.
+    pub threshold: WeightOrRatio,
+}
+
 /// Defines voting / decision making policy of this DAO.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
@@ -158,12 +248,83 @@ pub struct Policy {
     pub bounty_bond: U128,
     /// Period in which giving up on bounty is not punished.
     pub bounty_forgiveness_period: U64,
+    /// Allows `Transfer` proposals with this DAO as the receiver.
+    #[serde(default)]
+    pub allow_self_transfers: bool,
+    /// Rules for trusted receivers, first matching rule is applied.
+    #[serde(default)]
+    pub trusted_transfer_rules: Vec<TrustedTransferRule>,
+    /// Bond for claiming a bounty paid out in given token, denominated in that token.
+    /// Bounties in tokens without an entry can only be claimed with the $NEAR `bounty_bond`.
+    #[serde(default)]
+    pub bounty_token_bonds: HashMap<OldAccountId, U128>,
+    /// Ragequit settings. Ragequit is disabled if not set.
+    #[serde(default)]
+    pub ragequit: Option<RagequitPolicy>,
+    /// Treasury spend limits. No limits if not set.
+    #[serde(default)]
+    pub spend_limits: Option<SpendLimits>,
+    /// Number of proposals in a row a member can skip voting on before their role weight
+    /// votes stop counting. Voting again restores them for the next proposals.
+    #[serde(default)]
+    pub vote_decay_after: Option<u64>,
+    /// Maximum number of proposals in progress at the same time. No limit if not set.
+    #[serde(default)]
+    pub max_active_proposals: Option<u64>,
+    /// Maximum number of open bounties. No limit if not set.
+    #[serde(default)]
+    pub max_open_bounties: Option<u64>,
+    /// SocialDB contract where outcomes of proposals are published. Not published if not set.
+    #[serde(default)]
+    pub social_db_id: Option<AccountId>,
+    /// Bond for adding proposals of given kind label that can be paid in a token instead of
+    /// the $NEAR `proposal_bond`.
+    #[serde(default)]
+    pub proposal_token_bonds: HashMap<String, ProposalTokenBond>,
+    /// Role whose members post results of off-chain votes with `post_offchain_tally`.
+    /// Off-chain votes are not possible if not set.
+    #[serde(default)]
+    pub tally_oracle_role: Option<String>,
+    /// Proposal kind labels that can be decided by off-chain votes, out of
+    /// `consts::OFFCHAIN_TALLY_KINDS`.
+    #[serde(default)]
+    pub offchain_tally_kinds: Vec<String>,
+    /// If set, storage used by proposals and bounties is charged to their authors' storage
+    /// balances, funded with `author_storage_deposit`, and returned when they are removed.
+    #[serde(default)]
+    pub charge_author_storage: bool,
+    /// Weight or ratio of `FlagSpam` votes in a role to remove a proposal as spam, forfeiting
+    /// its bonds. The threshold of the role's vote policy for the proposal kind if not set.
+    #[serde(default)]
+    pub spam_threshold: Option<WeightOrRatio>,
+    /// If set, proposers are called with `on_proposal_finalized` when their proposals are finalized.
+    #[serde(default)]
+    pub notify_proposer: bool,
+    /// Thresholds of `Transfer` proposals by amount, the tier with the smallest `max_amount`
+    /// covering the amount applies.
+    /// Transfers above all tiers use the role's vote policy. Trusted transfer rules take precedence.
+    #[serde(default)]
+    pub transfer_tiers: Vec<TransferTier>,
+    /// Maximum number of voting actions per account per hour, by role. Accounts are limited
+    /// by the highest limit of their roles allowing the action, unless one of them has no limit.
+    /// Finalizing proposals is not limited.
+    #[serde(default)]
+    pub vote_rate_limits: HashMap<String, u32>,
+    /// Role whose members add proposals on behalf of anonymous authors with
+    /// `add_anonymous_proposal`. Anonymous proposals are not possible if not set.
+    #[serde(default)]
+    pub sponsor_role: Option<String>,
+    /// Executors deployed with `DeployExecutor` that make the calls of given proposal kinds
+    /// instead of the DAO, by kind label. Currently applies to `FunctionCall` proposals.
+    #[serde(default)]
+    pub executors: HashMap<String, AccountId>,
 }
 
 /// Versioned policy.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 #[serde(crate = "near_sdk::serde", untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum VersionedPolicy {
     /// Default policy with given accounts as council.
     Default(Vec<AccountId>),
@@ -176,25 +337,38 @@ pub enum VersionedPolicy {
 ///     - non token weighted voting, requires 1/2 of the group to vote
 ///     - proposal & bounty bond is 1N
 ///     - proposal & bounty forgiveness period is 1 day
-fn default_policy(council: Vec<AccountId>) -> Policy {
+/// Returns true if the role has a permission for given action on some kind of proposal.
+fn role_allows_action(role: &RolePermission, action: &str) -> bool {
+    role.permissions.iter().any(|permission| {
+        matches!(
+            permission.split_once(':'),
+            Some((_, permission_action)) if permission_action == action || permission_action == WILDCARD
+        )
+    })
+}
+
+pub fn default_policy(council: Vec<AccountId>) -> Policy {
     Policy {
         roles: vec![
             RolePermission {
-                name: "all".to_string(),
+                name: ROLE_ALL.to_string(),
                 kind: RoleKind::Everyone,
-                permissions: vec!["*:AddProposal".to_string()].into_iter().collect(),
+                permissions: vec![permission(WILDCARD, ACTION_ADD_PROPOSAL)]
+                    .into_iter()
+                    .collect(),
                 vote_policy: HashMap::default(),
             },
             RolePermission {
-                name: "council".to_string(),
+                name: ROLE_COUNCIL.to_string(),
                 kind: RoleKind::Group(council.into_iter().collect()),
                 // All actions except RemoveProposal are allowed by council.
                 permissions: vec![
-                    "*:AddProposal".to_string(),
-                    "*:VoteApprove".to_string(),
-                    "*:VoteReject".to_string(),
-                    "*:VoteRemove".to_string(),
-                    "*:Finalize".to_string(),
+                    permission(WILDCARD, ACTION_ADD_PROPOSAL),
+                    permission(WILDCARD, ACTION_VOTE_APPROVE),
+                    permission(WILDCARD, ACTION_VOTE_REJECT),
+                    permission(WILDCARD, ACTION_VOTE_REMOVE),
+                    permission(WILDCARD, ACTION_FINALIZE),
+                    permission(WILDCARD, ACTION_FLAG_SPAM),
                 ]
                 .into_iter()
                 .collect(),
@@ -206,6 +380,25 @@ fn default_policy(council: Vec<AccountId>) -> Policy {
         proposal_period: U64::from(1_000_000_000 * 60 * 60 * 24 * 7),
         bounty_bond: U128(10u128.pow(24)),
         bounty_forgiveness_period: U64::from(1_000_000_000 * 60 * 60 * 24),
+        allow_self_transfers: false,
+        trusted_transfer_rules: vec![],
+        bounty_token_bonds: HashMap::default(),
+        ragequit: None,
+        spend_limits: None,
+        vote_decay_after: None,
+        max_active_proposals: None,
+        max_open_bounties: None,
+        social_db_id: None,
+        proposal_token_bonds: HashMap::default(),
+        tally_oracle_role: None,
+        offchain_tally_kinds: vec![],
+        charge_author_storage: false,
+        spam_threshold: None,
+        notify_proposer: false,
+        transfer_tiers: vec![],
+        vote_rate_limits: HashMap::default(),
+        sponsor_role: None,
+        executors: HashMap::default(),
     }
 }
 
@@ -267,6 +460,27 @@ impl Policy {
         env::log_str("Successfully updated the default vote policy.");
     }
 
+    /// Sets the vote policy of given role for given proposal kind label.
+    /// Removing it makes the role use the default vote policy for that kind.
+    pub fn update_role_vote_policy(
+        &mut self,
+        role: &String,
+        kind_label: &String,
+        vote_policy: &Option<VotePolicy>,
+    ) {
+        match self.roles.iter_mut().find(|r| &r.name == role) {
+            Some(role_permission) => {
+                match vote_policy {
+                    Some(vote_policy) => role_permission
+                        .vote_policy
+                        .insert(kind_label.clone(), vote_policy.clone()),
+                    None => role_permission.vote_policy.remove(kind_label),
+                };
+            }
+            None => env::log_str(&format!("ERR_ROLE_NOT_FOUND:{}", role)),
+        }
+    }
+
     pub fn update_parameters(&mut self, parameters: &PolicyParameters) {
         if parameters.proposal_bond.is_some() {
             self.proposal_bond = parameters.proposal_bond.unwrap();
@@ -298,6 +512,20 @@ impl Policy {
         env::log_str(&format!("ERR_ROLE_NOT_FOUND:{}", role));
     }
 
+    /// Adds members to the group role. Unlike `add_member_to_role`, panics if it's not a group role.
+    pub fn add_members_to_role(&mut self, role: &String, members: &[AccountId]) {
+        let role = self
+            .roles
+            .iter_mut()
+            .find(|r| &r.name == role)
+            .expect("ERR_ROLE_NOT_FOUND");
+        for member_id in members {
+            role.kind
+                .add_member_to_group(member_id)
+                .unwrap_or_else(|()| env::panic_str("ERR_ROLE_WRONG_KIND"));
+        }
+    }
+
     pub fn remove_member_from_role(&mut self, role: &String, member_id: &AccountId) {
         for i in 0..self.roles.len() {
             if &self.roles[i].name == role {
@@ -313,6 +541,47 @@ impl Policy {
         env::log_str(&format!("ERR_ROLE_NOT_FOUND:{}", role));
     }
 
+    /// Returns copy of this policy with the members of the group role replaced.
+    /// Panics if it's not a group role, or if with the new members no account could add proposals
+    /// or no member of a group or token role could vote to approve them.
+    pub fn with_role_members(&self, role: &String, members: &[AccountId]) -> Policy {
+        let mut policy = self.clone();
+        let role = policy
+            .roles
+            .iter_mut()
+            .find(|r| &r.name == role)
+            .expect("ERR_ROLE_NOT_FOUND");
+        assert!(
+            matches!(role.kind, RoleKind::Group(_)),
+            "ERR_ROLE_NOT_GROUP"
+        );
+        role.kind = RoleKind::Group(members.iter().cloned().collect());
+        assert!(policy.has_proposers(), "ERR_NO_PROPOSERS_LEFT");
+        assert!(policy.has_voters(), "ERR_NO_VOTERS_LEFT");
+        policy
+    }
+
+    /// Returns true if any account can add some kind of proposal.
+    pub(crate) fn has_proposers(&self) -> bool {
+        self.roles.iter().any(|role| {
+            !matches!(&role.kind, RoleKind::Group(members) if members.is_empty())
+                && role_allows_action(role, ACTION_ADD_PROPOSAL)
+        })
+    }
+
+    /// Returns true if members of a non-empty group role or of a token role can vote to approve
+    /// some kind of proposal.
+    pub(crate) fn has_voters(&self) -> bool {
+        self.roles.iter().any(|role| {
+            let has_members = match &role.kind {
+                RoleKind::Everyone => false,
+                RoleKind::Member(_) => true,
+                RoleKind::Group(members) => !members.is_empty(),
+            };
+            has_members && role_allows_action(role, ACTION_VOTE_APPROVE)
+        })
+    }
+
     /// Returns set of roles that this user is member of permissions for given user across all the roles it's member of.
     fn get_user_roles(&self, user: UserInfo) -> HashMap<String, &HashSet<String>> {
         let mut roles = HashMap::default();
@@ -331,19 +600,31 @@ impl Policy {
         user: UserInfo,
         proposal_kind: &ProposalKind,
         action: &Action,
+    ) -> (Vec<String>, bool) {
+        self.can_execute_label(
+            user,
+            proposal_kind.to_policy_label(),
+            &action.to_policy_label(),
+        )
+    }
+
+    /// Can given user execute given `<label>:<action>` permission.
+    /// Used for permissions that are not tied to a proposal kind (e.g. reviewing grants).
+    /// Returns all roles that allow this action.
+    pub fn can_execute_label(
+        &self,
+        user: UserInfo,
+        label: &str,
+        action_label: &str,
     ) -> (Vec<String>, bool) {
         let roles = self.get_user_roles(user);
         let mut allowed = false;
         let allowed_roles = roles
             .into_iter()
             .filter_map(|(role, permissions)| {
-                let allowed_role = permissions.contains(&format!(
-                    "{}:{}",
-                    proposal_kind.to_policy_label(),
-                    action.to_policy_label()
-                )) || permissions
-                    .contains(&format!("{}:*", proposal_kind.to_policy_label()))
-                    || permissions.contains(&format!("*:{}", action.to_policy_label()))
+                let allowed_role = permissions.contains(&format!("{}:{}", label, action_label))
+                    || permissions.contains(&format!("{}:*", label))
+                    || permissions.contains(&format!("*:{}", action_label))
                     || permissions.contains("*:*");
                 allowed = allowed || allowed_role;
                 if allowed_role {
@@ -370,7 +651,7 @@ impl Policy {
         }
     }
 
-    fn internal_get_role(&self, name: &String) -> Option<&RolePermission> {
+    pub(crate) fn internal_get_role(&self, name: &String) -> Option<&RolePermission> {
         for role in self.roles.iter() {
             if role.name == *name {
                 return Some(role);
@@ -379,13 +660,69 @@ impl Policy {
         None
     }
 
+    /// Returns threshold of the first trusted transfer rule matching given proposal,
+    /// given number of successful past payouts of the token to its receiver.
+    fn trusted_transfer_threshold(
+        &self,
+        proposal_kind: &ProposalKind,
+        receiver_payouts: u32,
+    ) -> Option<&WeightOrRatio> {
+        match proposal_kind {
+            ProposalKind::Transfer {
+                token_id, amount, ..
+            } => self
+                .trusted_transfer_rules
+                .iter()
+                .find(|rule| {
+                    &rule.token_id == token_id
+                        && receiver_payouts >= rule.min_payouts
+                        && amount.0 < rule.max_amount.0
+                })
+                .map(|rule| &rule.threshold),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowest `min_payout_amount` of the trusted transfer rules of given token.
+    /// Payouts of the token count towards `min_payouts` only if they reach it.
+    pub(crate) fn trusted_min_payout_amount(&self, token_id: &OldAccountId) -> Option<Balance> {
+        self.trusted_transfer_rules
+            .iter()
+            .filter(|rule| &rule.token_id == token_id)
+            .map(|rule| rule.min_payout_amount.0)
+            .min()
+    }
+
+    /// Returns threshold of the transfer tier with the lowest `max_amount` matching given proposal.
+    fn transfer_tier_threshold(&self, proposal_kind: &ProposalKind) -> Option<&WeightOrRatio> {
+        match proposal_kind {
+            ProposalKind::Transfer {
+                token_id, amount, ..
+            } => self
+                .transfer_tiers
+                .iter()
+                .filter(|tier| &tier.token_id == token_id && amount.0 < tier.max_amount.0)
+                .min_by_key(|tier| tier.max_amount.0)
+                .map(|tier| &tier.threshold),
+            _ => None,
+        }
+    }
+
     /// Get proposal status for given proposal.
     /// Usually is called after changing it's state.
+    /// `receiver_payouts` is number of successful past payouts of the token to the receiver of
+    /// `Transfer` proposal.
+    /// `voter_counts` is number of distinct voters per role per decision.
+    /// `decayed_members` is number of members per group role whose votes don't count on the
+    /// proposal because of vote decay, left out of the role's total weight.
     pub fn proposal_status(
         &self,
         proposal: &Proposal,
         roles: Vec<String>,
         total_supply: Balance,
+        receiver_payouts: u32,
+        voter_counts: &HashMap<String, [u32; 3]>,
+        decayed_members: &HashMap<String, Balance>,
     ) -> ProposalStatus {
         assert!(
             matches!(
@@ -398,35 +735,40 @@ impl Policy {
             // Proposal expired.
             return ProposalStatus::Expired;
         };
+        let trusted_threshold = self
+            .trusted_transfer_threshold(&proposal.kind, receiver_payouts)
+            .or_else(|| self.transfer_tier_threshold(&proposal.kind));
         for role in roles {
-            let role_info = self.internal_get_role(&role).expect("ERR_MISSING_ROLE");
-            let vote_policy = role_info
-                .vote_policy
-                .get(&proposal.kind.to_policy_label().to_string())
-                .unwrap_or(&self.default_vote_policy);
-            let total_weight = match &role_info.kind {
-                // Skip role that covers everyone as it doesn't provide a total size.
-                RoleKind::Everyone => continue,
-                RoleKind::Group(group) => {
-                    if vote_policy.weight_kind == WeightKind::RoleWeight {
-                        group.len() as Balance
-                    } else {
-                        total_supply
-                    }
-                }
-                RoleKind::Member(_) => total_supply,
-            };
-            let threshold = std::cmp::max(
+            let (vote_policy, total_weight) =
+                match self.role_vote_policy(&role, &proposal.kind, total_supply, decayed_members) {
+                    Some(role_vote_policy) => role_vote_policy,
+                    None => continue,
+                };
+            let mut threshold = std::cmp::max(
                 vote_policy.quorum.0,
-                vote_policy.threshold.to_weight(total_weight),
+                trusted_threshold
+                    .unwrap_or(&vote_policy.threshold)
+                    .to_weight(total_weight),
             );
-            // Check if there is anything voted above the threshold specified by policy for given role.
+            if let ProposalKind::MigrateTreasury { .. } = proposal.kind {
+                threshold = std::cmp::max(
+                    threshold,
+                    MIGRATE_TREASURY_MIN_THRESHOLD.to_weight(total_weight),
+                );
+            }
+            // Check if there is anything voted above the threshold specified by policy for given role,
+            // by enough distinct voters.
             let vote_counts = proposal.vote_counts.get(&role).unwrap_or(&[0u128; 3]);
-            if vote_counts[Vote::Approve as usize] >= threshold {
+            let voters = voter_counts.get(&role).unwrap_or(&[0u32; 3]);
+            let passes = |vote: Vote| {
+                vote_counts[vote.clone() as usize] >= threshold
+                    && voters[vote as usize] >= vote_policy.min_voters
+            };
+            if passes(Vote::Approve) {
                 return ProposalStatus::Approved;
-            } else if vote_counts[Vote::Reject as usize] >= threshold {
+            } else if passes(Vote::Reject) {
                 return ProposalStatus::Rejected;
-            } else if vote_counts[Vote::Remove as usize] >= threshold {
+            } else if passes(Vote::Remove) {
                 return ProposalStatus::Removed;
             } else {
                 // continue to next role.
@@ -434,6 +776,73 @@ impl Policy {
         }
         proposal.status.clone()
     }
+
+    /// Vote policy of the role for given proposal kind, with the total weight votes are measured
+    /// against. None for the role that covers everyone, as it doesn't provide a total size.
+    fn role_vote_policy(
+        &self,
+        role: &str,
+        kind: &ProposalKind,
+        total_supply: Balance,
+        decayed_members: &HashMap<String, Balance>,
+    ) -> Option<(&VotePolicy, Balance)> {
+        let role_info = self
+            .internal_get_role(&role.to_string())
+            .expect("ERR_MISSING_ROLE");
+        let vote_policy = role_info
+            .vote_policy
+            .get(kind.to_policy_label())
+            .unwrap_or(&self.default_vote_policy);
+        let total_weight = match &role_info.kind {
+            RoleKind::Everyone => return None,
+            RoleKind::Group(group) => {
+                if vote_policy.weight_kind == WeightKind::RoleWeight {
+                    (group.len() as Balance)
+                        .saturating_sub(decayed_members.get(role).copied().unwrap_or(0))
+                } else {
+                    total_supply
+                }
+            }
+            RoleKind::Member(_) => total_supply,
+        };
+        Some((vote_policy, total_weight))
+    }
+
+    /// Maximum number of voting actions per hour of an account acting with given roles.
+    /// None if any of the roles is not limited.
+    pub fn vote_rate_limit(&self, roles: &[String]) -> Option<u32> {
+        roles
+            .iter()
+            .map(|role| self.vote_rate_limits.get(role).copied())
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .max()
+    }
+
+    /// Returns true if `FlagSpam` votes reached the spam threshold in any of given roles.
+    pub fn is_spam(
+        &self,
+        proposal: &Proposal,
+        roles: &[String],
+        total_supply: Balance,
+        spam_flags: &HashMap<String, Balance>,
+    ) -> bool {
+        roles.iter().any(|role| {
+            match self.role_vote_policy(role, &proposal.kind, total_supply, &HashMap::default()) {
+                Some((vote_policy, total_weight)) => {
+                    let threshold = std::cmp::max(
+                        vote_policy.quorum.0,
+                        self.spam_threshold
+                            .as_ref()
+                            .unwrap_or(&vote_policy.threshold)
+                            .to_weight(total_weight),
+                    );
+                    spam_flags.get(role).copied().unwrap_or(0) >= threshold
+                }
+                None => false,
+            }
+        })
+    }
 }
 
 #[cfg(test)]
@@ -499,6 +908,7 @@ mod tests {
             "*:VoteReject".to_string(),
             "*:VoteRemove".to_string(),
             "*:Finalize".to_string(),
+            "*:FlagSpam".to_string(),
         ]
         .into_iter()
         .collect();
@@ -570,6 +980,7 @@ mod tests {
             weight_kind: WeightKind::TokenWeight,
             quorum: U128(100),
             threshold: WeightOrRatio::Ratio(1, 4),
+            min_voters: 0,
         };
         policy.update_default_vote_policy(&new_default_vote_policy);
         assert_eq!(
@@ -586,6 +997,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_update_role_vote_policy() {
+        let mut policy = default_policy(vec![accounts(0), accounts(1)]);
+        let council = "council".to_string();
+        let transfer = "transfer".to_string();
+        let vote_policy = VotePolicy {
+            weight_kind: WeightKind::RoleWeight,
+            quorum: U128(0),
+            threshold: WeightOrRatio::Ratio(2, 3),
+            min_voters: 0,
+        };
+        policy.update_role_vote_policy(&council, &transfer, &Some(vote_policy.clone()));
+        let role = policy.internal_get_role(&council).unwrap();
+        assert_eq!(role.vote_policy.get(&transfer), Some(&vote_policy));
+        assert_eq!(role.vote_policy.len(), 1);
+
+        policy.update_role_vote_policy(&council, &transfer, &None);
+        assert!(policy
+            .internal_get_role(&council)
+            .unwrap()
+            .vote_policy
+            .is_empty());
+    }
+
     #[test]
     fn test_update_parameters() {
         let council = vec![accounts(0), accounts(1)];
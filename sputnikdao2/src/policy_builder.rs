@@ -0,0 +1,260 @@
+//! Builder of validated policies, e.g. for tests and deploy scripts instead of hand-written JSON.
+
+use std::collections::HashMap;
+
+use near_sdk::json_types::{U128, U64};
+use near_sdk::AccountId;
+
+use crate::consts::*;
+use crate::grants::{GRANT_PERMISSION_LABEL, GRANT_REVIEW_ACTION_LABEL};
+use crate::policy::{default_policy, WeightKind};
+use crate::{Policy, RoleKind, RolePermission, VersionedPolicy, VotePolicy, WeightOrRatio};
+
+/// Errors of building a policy.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PolicyError {
+    /// Role with this name was already added.
+    DuplicateRole(String),
+    /// Role with this name was not added before setting its vote policy.
+    RoleNotFound(String),
+    /// Permission is not `<kind label>:<action label>`, see `permission`.
+    InvalidPermission(String),
+    /// Vote policy is set for an unknown proposal kind label.
+    InvalidKindLabel(String),
+    /// Ratio threshold with zero denominator or above 1.
+    InvalidThreshold,
+    /// No account could add proposals.
+    NoProposers,
+}
+
+/// Builds a policy starting from the parameters of the default policy, without any roles.
+/// Roles are added with `add_role` / `add_group` and the result is validated by `build`.
+pub struct PolicyBuilder {
+    policy: Policy,
+    error: Option<PolicyError>,
+}
+
+impl Default for PolicyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PolicyBuilder {
+    pub fn new() -> Self {
+        let mut policy = default_policy(vec![]);
+        policy.roles.clear();
+        Self {
+            policy,
+            error: None,
+        }
+    }
+
+    /// Adds role with given permissions, e.g. `permission(KIND_TRANSFER, ACTION_VOTE_APPROVE)`.
+    pub fn add_role(mut self, name: &str, kind: RoleKind, permissions: &[String]) -> Self {
+        if self.policy.roles.iter().any(|role| role.name == name) {
+            self.fail(PolicyError::DuplicateRole(name.to_string()));
+        }
+        self.policy.roles.push(RolePermission {
+            name: name.to_string(),
+            kind,
+            permissions: permissions.iter().cloned().collect(),
+            vote_policy: HashMap::default(),
+        });
+        self
+    }
+
+    /// Adds group role with given members and permissions.
+    pub fn add_group(self, name: &str, members: &[AccountId], permissions: &[String]) -> Self {
+        self.add_role(
+            name,
+            RoleKind::Group(members.iter().cloned().collect()),
+            permissions,
+        )
+    }
+
+    /// Makes votes of the default vote policy token weighted, with at least `quorum` tokens.
+    pub fn token_weighted(mut self, quorum: U128) -> Self {
+        self.policy.default_vote_policy.weight_kind = WeightKind::TokenWeight;
+        self.policy.default_vote_policy.quorum = quorum;
+        self
+    }
+
+    /// Sets threshold of the default vote policy.
+    pub fn threshold(mut self, threshold: WeightOrRatio) -> Self {
+        self.policy.default_vote_policy.threshold = threshold;
+        self
+    }
+
+    /// Sets vote policy of an added role for given proposal kind label.
+    pub fn role_vote_policy(
+        mut self,
+        role: &str,
+        kind_label: &str,
+        vote_policy: VotePolicy,
+    ) -> Self {
+        match self.policy.roles.iter_mut().find(|r| r.name == role) {
+            Some(role) => {
+                role.vote_policy.insert(kind_label.to_string(), vote_policy);
+            }
+            None => self.fail(PolicyError::RoleNotFound(role.to_string())),
+        }
+        self
+    }
+
+    pub fn proposal_bond(mut self, proposal_bond: U128) -> Self {
+        self.policy.proposal_bond = proposal_bond;
+        self
+    }
+
+    pub fn proposal_period(mut self, proposal_period: U64) -> Self {
+        self.policy.proposal_period = proposal_period;
+        self
+    }
+
+    pub fn bounty_bond(mut self, bounty_bond: U128) -> Self {
+        self.policy.bounty_bond = bounty_bond;
+        self
+    }
+
+    pub fn bounty_forgiveness_period(mut self, bounty_forgiveness_period: U64) -> Self {
+        self.policy.bounty_forgiveness_period = bounty_forgiveness_period;
+        self
+    }
+
+    /// Returns the policy, or the first error found.
+    pub fn build(self) -> Result<VersionedPolicy, PolicyError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let policy = self.policy;
+        for role in policy.roles.iter() {
+            if let Some(permission) = role.permissions.iter().find(|p| !is_valid_permission(p)) {
+                return Err(PolicyError::InvalidPermission(permission.clone()));
+            }
+            for (kind_label, vote_policy) in role.vote_policy.iter() {
+                if !PROPOSAL_KIND_LABELS.contains(&kind_label.as_str()) {
+                    return Err(PolicyError::InvalidKindLabel(kind_label.clone()));
+                }
+                assert_valid_threshold(&vote_policy.threshold)?;
+            }
+        }
+        assert_valid_threshold(&policy.default_vote_policy.threshold)?;
+        if !policy.has_proposers() {
+            return Err(PolicyError::NoProposers);
+        }
+        Ok(VersionedPolicy::Current(policy))
+    }
+
+    fn fail(&mut self, error: PolicyError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+fn is_valid_permission(permission: &str) -> bool {
+    match permission.split_once(':') {
+        Some((kind_label, action_label)) => {
+            (kind_label == WILDCARD
+                || kind_label == GRANT_PERMISSION_LABEL
+                || PROPOSAL_KIND_LABELS.contains(&kind_label))
+                && (action_label == WILDCARD
+                    || action_label == GRANT_REVIEW_ACTION_LABEL
+                    || ACTION_LABELS.contains(&action_label))
+        }
+        None => false,
+    }
+}
+
+fn assert_valid_threshold(threshold: &WeightOrRatio) -> Result<(), PolicyError> {
+    match threshold {
+        WeightOrRatio::Ratio(num, denom) if *denom == 0 || num > denom => {
+            Err(PolicyError::InvalidThreshold)
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    #[test]
+    fn test_policy_builder() {
+        let policy = PolicyBuilder::new()
+            .add_role(
+                ROLE_ALL,
+                RoleKind::Everyone,
+                &[permission(WILDCARD, ACTION_ADD_PROPOSAL)],
+            )
+            .add_group(
+                ROLE_COUNCIL,
+                &[accounts(1)],
+                &[
+                    permission(WILDCARD, ACTION_ADD_PROPOSAL),
+                    permission(WILDCARD, ACTION_VOTE_APPROVE),
+                    permission(WILDCARD, ACTION_VOTE_REJECT),
+                    permission(WILDCARD, ACTION_VOTE_REMOVE),
+                    permission(WILDCARD, ACTION_FINALIZE),
+                    permission(WILDCARD, ACTION_FLAG_SPAM),
+                ],
+            )
+            .build()
+            .unwrap();
+        assert_eq!(policy.to_policy(), default_policy(vec![accounts(1)]));
+
+        let policy = PolicyBuilder::new()
+            .add_group(
+                ROLE_COUNCIL,
+                &[accounts(1)],
+                &[permission(WILDCARD, WILDCARD)],
+            )
+            .token_weighted(U128(100))
+            .role_vote_policy(ROLE_COUNCIL, KIND_TRANSFER, VotePolicy::default())
+            .build()
+            .unwrap()
+            .to_policy();
+        assert!(policy.is_token_weighted(&ROLE_COUNCIL.to_string(), &KIND_CALL.to_string()));
+        assert!(!policy.is_token_weighted(&ROLE_COUNCIL.to_string(), &KIND_TRANSFER.to_string()));
+    }
+
+    #[test]
+    fn test_policy_builder_errors() {
+        let council = |permissions: &[String]| {
+            PolicyBuilder::new().add_group(ROLE_COUNCIL, &[accounts(1)], permissions)
+        };
+        let all = [permission(WILDCARD, WILDCARD)];
+        assert_eq!(
+            council(&all)
+                .add_group(ROLE_COUNCIL, &[], &[])
+                .build()
+                .err(),
+            Some(PolicyError::DuplicateRole(ROLE_COUNCIL.to_string()))
+        );
+        assert_eq!(
+            council(&["transfer:Vote".to_string()]).build().err(),
+            Some(PolicyError::InvalidPermission("transfer:Vote".to_string()))
+        );
+        assert_eq!(
+            council(&all)
+                .role_vote_policy(ROLE_ALL, KIND_TRANSFER, VotePolicy::default())
+                .build()
+                .err(),
+            Some(PolicyError::RoleNotFound(ROLE_ALL.to_string()))
+        );
+        assert_eq!(
+            council(&all)
+                .threshold(WeightOrRatio::Ratio(3, 2))
+                .build()
+                .err(),
+            Some(PolicyError::InvalidThreshold)
+        );
+        assert_eq!(
+            council(&[permission(WILDCARD, ACTION_VOTE_APPROVE)])
+                .build()
+                .err(),
+            Some(PolicyError::NoProposers)
+        );
+    }
+}
@@ -1,16 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::{log, AccountId, Balance, Gas, PromiseOrValue};
 
-use crate::policy::UserInfo;
+use crate::consts::*;
+use crate::events::{BountyUpdate, Event};
+use crate::policy::{UserInfo, SPEND_LIMIT_PERIOD};
 use crate::types::{
-    convert_old_to_new_token, Action, Config, OldAccountId, GAS_FOR_FT_TRANSFER, OLD_BASE_TOKEN,
-    ONE_YOCTO_NEAR,
+    convert_old_to_new_token, Action, Config, OldAccountId, AVATAR_NFT_METADATA_KEY,
+    OLD_BASE_TOKEN, ONE_YOCTO_NEAR,
+};
+use crate::upgrade::{
+    internal_get_factory_info, upgrade_remote, upgrade_using_factory, OldProposal,
 };
-use crate::upgrade::{upgrade_remote, upgrade_using_factory};
 use crate::*;
 
 /// Status of a proposal.
@@ -22,7 +26,8 @@ pub enum ProposalStatus {
     Approved,
     /// If quorum voted no, this proposal is rejected. Bond is returned.
     Rejected,
-    /// If quorum voted to remove (e.g. spam), this proposal is rejected and bond is not returned.
+    /// If quorum voted to remove, this proposal is removed and bond is returned.
+    /// If flagged as spam or removed with `RemoveProposal`, bond is forfeited to the treasury.
     /// Interfaces shouldn't show removed proposals.
     Removed,
     /// Expired after period of time.
@@ -33,15 +38,30 @@ pub enum ProposalStatus {
     Failed,
 }
 
+/// Who pays the deposit attached to the function call.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum FundingSource {
+    /// Deposit is paid from the DAO's treasury.
+    #[default]
+    Treasury,
+    /// Deposit is attached by the proposer on top of the bond and held in escrow.
+    /// Returned to the proposer if the proposal is not executed.
+    ProposerEscrow,
+}
+
 /// Function call arguments.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
 #[serde(crate = "near_sdk::serde")]
 pub struct ActionCall {
-    method_name: String,
-    args: Base64VecU8,
-    deposit: U128,
-    gas: U64,
+    pub method_name: String,
+    pub args: Base64VecU8,
+    pub deposit: U128,
+    pub gas: U64,
+    #[serde(default)]
+    pub funding_source: FundingSource,
 }
 
 /// Function call arguments.
@@ -59,6 +79,7 @@ pub struct PolicyParameters {
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
 #[serde(crate = "near_sdk::serde")]
+#[allow(clippy::large_enum_variant)]
 pub enum ProposalKind {
     /// Change the DAO config.
     ChangeConfig { config: Config },
@@ -113,31 +134,341 @@ pub enum ProposalKind {
     ChangePolicyUpdateDefaultVotePolicy { vote_policy: VotePolicy },
     /// Update the parameters from the policy. This is short cut to updating the whole policy.
     ChangePolicyUpdateParameters { parameters: PolicyParameters },
+    /// Approve submitted grant application, turning it into a funded agreement.
+    ApproveGrant { grant_id: u64 },
+    /// Indicates that given milestone of the approved grant is done and should be paid out.
+    GrantMilestoneDone { grant_id: u64, milestone: u32 },
+    /// Set the DAO avatar to the given NFT. The DAO must own or be approved for the token.
+    /// Stored under `avatar_nft` key of the config metadata, which must be a JSON object.
+    SetAvatarNft {
+        contract_id: AccountId,
+        token_id: String,
+    },
+    /// Set the vote policy of a single role for given proposal kind label, or remove it to fall
+    /// back to the default vote policy. This is short cut to updating the whole policy.
+    ChangeRoleVotePolicy {
+        role: String,
+        kind_label: String,
+        vote_policy: Option<VotePolicy>,
+    },
+    /// Set which proposals are mirrored as advisory votes in a parent DAO, or stop mirroring.
+    SetVoteMirror { mirror: Option<VoteMirror> },
+    /// Add and remove accounts from the blocklist. Blocked accounts can't add proposals or
+    /// claim bounties.
+    UpdateBlocklist {
+        add: Vec<AccountId>,
+        remove: Vec<AccountId>,
+    },
+    /// Delete all registered full access keys of the DAO account, leaving it governed only by
+    /// this contract.
+    Lockdown,
+    /// Add members to the group role. Large lists are added in chunks, see `continue_add_members`.
+    AddMembersToRole {
+        role: String,
+        members: Vec<AccountId>,
+    },
+    /// Set gas attached to the DAO's cross-contract calls.
+    SetGasConfig { gas_config: GasConfig },
+    /// Set budget that reopens the bounty after its last completion, drawing the bounty's
+    /// `amount` per renewal. Zero budget removes the pool.
+    SetBountyPool { bounty_id: u64, budget: U128 },
+    /// Transfer all $NEAR and given tokens not locked for bonds or storage, and given NFTs
+    /// to another DAO, e.g. in response to an irrecoverable bug. Tokens and NFTs are swept in
+    /// chunks, see `continue_migrate_treasury`. Needs `MIGRATE_TREASURY_MIN_THRESHOLD` of votes
+    /// whatever the vote policy.
+    MigrateTreasury {
+        target_dao: AccountId,
+        tokens: Vec<AccountId>,
+        /// Pairs of NFT contract and token id.
+        #[serde(default)]
+        nfts: Vec<(AccountId, String)>,
+    },
+    /// Add recurring `Vote` proposal that anyone can create every `interval` from `first_time`,
+    /// with the bonds paid from `pool`. See `trigger_recurring_vote`.
+    AddRecurringVote {
+        description: String,
+        interval: U64,
+        first_time: U64,
+        pool: U128,
+    },
+    /// Replace all members of the group role at once, e.g. for a council handover.
+    /// No members dissolve the role. Fails if no account could add proposals afterwards.
+    ReplaceRoleMembers {
+        role: String,
+        new_members: Vec<AccountId>,
+    },
+    /// Lend given amount of `token_id` from this DAO to `borrower`, interest-free until `due`.
+    /// The loan is recorded once paid out, under the id of the proposal. See `get_loan`.
+    ApproveLoan {
+        borrower: AccountId,
+        /// Can be "" for $NEAR or a valid account id.
+        token_id: OldAccountId,
+        amount: U128,
+        due: U64,
+        collateral: Option<String>,
+    },
+    /// Declare given overdue loan in default. Added by `loan_check_overdue`.
+    LoanDefault { loan_id: u64 },
+    /// Stop given recurring vote and unlock the rest of its pool.
+    RemoveRecurringVote { recurring_vote_id: u64 },
+    /// Create `executor_id`, a sub-account of this DAO, with `amount` from the treasury to cover
+    /// its storage, and deploy the executor contract with given hash from blob store to it.
+    /// Calls are routed through it once it's set in the policy's `executors`.
+    DeployExecutor {
+        executor_id: AccountId,
+        hash: Base58CryptoHash,
+        amount: U128,
+    },
 }
 
 impl ProposalKind {
+    /// Returns amount of deposits that proposer must escrow on top of the bond.
+    pub fn proposer_escrow(&self) -> Balance {
+        match self {
+            ProposalKind::FunctionCall { actions, .. } => actions
+                .iter()
+                .filter(|action| action.funding_source == FundingSource::ProposerEscrow)
+                .map(|action| action.deposit.0)
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /// Returns amount of $NEAR the proposal spends from the treasury that can be funded by the
+    /// proposer via `propose_with_deposit`, or `None` if such funding is not supported.
+    pub fn fundable_amount(&self) -> Option<Balance> {
+        match self {
+            ProposalKind::Transfer {
+                token_id, amount, ..
+            } if token_id == OLD_BASE_TOKEN => Some(amount.0),
+            ProposalKind::FunctionCall { actions, .. } => Some(
+                actions
+                    .iter()
+                    .filter(|action| action.funding_source == FundingSource::Treasury)
+                    .map(|action| action.deposit.0)
+                    .sum(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns human readable summary of what the proposal does, e.g. for signing displays.
+    pub fn summary(&self) -> String {
+        let token_name = |token_id: &OldAccountId| {
+            if token_id == OLD_BASE_TOKEN {
+                "yoctoNEAR".to_string()
+            } else {
+                format!("of {}", token_id)
+            }
+        };
+        match self {
+            ProposalKind::ChangeConfig { config } => format!("Change config, name {}", config.name),
+            ProposalKind::ChangePolicy { .. } => "Replace the full policy".to_string(),
+            ProposalKind::AddMemberToRole { member_id, role } => {
+                format!("Add {} to role {}", member_id, role)
+            }
+            ProposalKind::RemoveMemberFromRole { member_id, role } => {
+                format!("Remove {} from role {}", member_id, role)
+            }
+            ProposalKind::FunctionCall {
+                receiver_id,
+                actions,
+            } => format!(
+                "Call {} on {} with total deposit {} yoctoNEAR",
+                actions
+                    .iter()
+                    .map(|action| action.method_name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                receiver_id,
+                actions
+                    .iter()
+                    .map(|action| action.deposit.0)
+                    .sum::<Balance>()
+            ),
+            ProposalKind::UpgradeSelf { hash } => {
+                format!("Upgrade this DAO to code {}", String::from(hash))
+            }
+            ProposalKind::UpgradeRemote {
+                receiver_id,
+                method_name,
+                hash,
+            } => format!(
+                "Upgrade {} via {} to code {}",
+                receiver_id,
+                method_name,
+                String::from(hash)
+            ),
+            ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                ..
+            } => format!(
+                "Transfer {} {} to {}",
+                amount.0,
+                token_name(token_id),
+                receiver_id
+            ),
+            ProposalKind::SetStakingContract { staking_id } => {
+                format!("Set staking contract to {}", staking_id)
+            }
+            ProposalKind::AddBounty { bounty } => format!(
+                "Add bounty of {} {}, {} times",
+                bounty.amount.0,
+                token_name(&bounty.token),
+                bounty.times
+            ),
+            ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+            } => format!("Pay out bounty {} to {}", bounty_id, receiver_id),
+            ProposalKind::Vote => "Signaling vote".to_string(),
+            ProposalKind::FactoryInfoUpdate { factory_info } => format!(
+                "Set factory to {}, auto update {}",
+                factory_info.factory_id, factory_info.auto_update
+            ),
+            ProposalKind::ChangePolicyAddOrUpdateRole { role } => {
+                format!("Add or update role {}", role.name)
+            }
+            ProposalKind::ChangePolicyRemoveRole { role } => format!("Remove role {}", role),
+            ProposalKind::ChangePolicyUpdateDefaultVotePolicy { .. } => {
+                "Update default vote policy".to_string()
+            }
+            ProposalKind::ChangePolicyUpdateParameters { .. } => {
+                "Update policy parameters".to_string()
+            }
+            ProposalKind::ApproveGrant { grant_id } => format!("Approve grant {}", grant_id),
+            ProposalKind::GrantMilestoneDone {
+                grant_id,
+                milestone,
+            } => format!("Pay out milestone {} of grant {}", milestone, grant_id),
+            ProposalKind::SetAvatarNft {
+                contract_id,
+                token_id,
+            } => format!("Set avatar to NFT {} of {}", token_id, contract_id),
+            ProposalKind::ChangeRoleVotePolicy {
+                role,
+                kind_label,
+                vote_policy,
+            } => match vote_policy {
+                Some(_) => format!("Update vote policy of role {} for {}", role, kind_label),
+                None => format!("Remove vote policy of role {} for {}", role, kind_label),
+            },
+            ProposalKind::SetVoteMirror { mirror } => match mirror {
+                Some(mirror) => format!(
+                    "Mirror {} proposals to {}",
+                    mirror.kind_labels.join(", "),
+                    mirror.parent_id
+                ),
+                None => "Stop mirroring proposals".to_string(),
+            },
+            ProposalKind::UpdateBlocklist { add, remove } => {
+                format!("Block {} and unblock {} accounts", add.len(), remove.len())
+            }
+            ProposalKind::Lockdown => "Delete all full access keys of the DAO".to_string(),
+            ProposalKind::AddMembersToRole { role, members } => {
+                format!("Add {} members to {}", members.len(), role)
+            }
+            ProposalKind::SetGasConfig { .. } => "Update gas of cross-contract calls".to_string(),
+            ProposalKind::SetBountyPool { bounty_id, budget } => {
+                format!("Set pool of bounty {} to {}", bounty_id, budget.0)
+            }
+            ProposalKind::MigrateTreasury {
+                target_dao,
+                tokens,
+                nfts,
+            } => format!(
+                "Move $NEAR, {} tokens and {} NFTs of the treasury to {}",
+                tokens.len(),
+                nfts.len(),
+                target_dao
+            ),
+            ProposalKind::AddRecurringVote {
+                description,
+                interval,
+                pool,
+                ..
+            } => format!(
+                "Add vote \"{}\" recurring every {} ns with pool of {} yoctoNEAR",
+                description, interval.0, pool.0
+            ),
+            ProposalKind::ReplaceRoleMembers { role, new_members } => {
+                format!(
+                    "Replace members of {} with {} accounts",
+                    role,
+                    new_members.len()
+                )
+            }
+            ProposalKind::ApproveLoan {
+                borrower,
+                token_id,
+                amount,
+                due,
+                ..
+            } => format!(
+                "Lend {} {} to {} until {}",
+                amount.0,
+                token_name(token_id),
+                borrower,
+                due.0
+            ),
+            ProposalKind::LoanDefault { loan_id } => format!("Declare loan {} in default", loan_id),
+            ProposalKind::RemoveRecurringVote { recurring_vote_id } => {
+                format!("Remove recurring vote {}", recurring_vote_id)
+            }
+            ProposalKind::DeployExecutor {
+                executor_id,
+                hash,
+                amount,
+            } => format!(
+                "Deploy executor {} with code {} and {} yoctoNEAR",
+                executor_id,
+                String::from(hash),
+                amount.0
+            ),
+        }
+    }
+
     /// Returns label of policy for given type of proposal.
     pub fn to_policy_label(&self) -> &str {
         match self {
-            ProposalKind::ChangeConfig { .. } => "config",
-            ProposalKind::ChangePolicy { .. } => "policy",
-            ProposalKind::AddMemberToRole { .. } => "add_member_to_role",
-            ProposalKind::RemoveMemberFromRole { .. } => "remove_member_from_role",
-            ProposalKind::FunctionCall { .. } => "call",
-            ProposalKind::UpgradeSelf { .. } => "upgrade_self",
-            ProposalKind::UpgradeRemote { .. } => "upgrade_remote",
-            ProposalKind::Transfer { .. } => "transfer",
-            ProposalKind::SetStakingContract { .. } => "set_vote_token",
-            ProposalKind::AddBounty { .. } => "add_bounty",
-            ProposalKind::BountyDone { .. } => "bounty_done",
-            ProposalKind::Vote => "vote",
-            ProposalKind::FactoryInfoUpdate { .. } => "factory_info_update",
-            ProposalKind::ChangePolicyAddOrUpdateRole { .. } => "policy_add_or_update_role",
-            ProposalKind::ChangePolicyRemoveRole { .. } => "policy_remove_role",
+            ProposalKind::ChangeConfig { .. } => KIND_CONFIG,
+            ProposalKind::ChangePolicy { .. } => KIND_POLICY,
+            ProposalKind::AddMemberToRole { .. } => KIND_ADD_MEMBER_TO_ROLE,
+            ProposalKind::RemoveMemberFromRole { .. } => KIND_REMOVE_MEMBER_FROM_ROLE,
+            ProposalKind::FunctionCall { .. } => KIND_CALL,
+            ProposalKind::UpgradeSelf { .. } => KIND_UPGRADE_SELF,
+            ProposalKind::UpgradeRemote { .. } => KIND_UPGRADE_REMOTE,
+            ProposalKind::Transfer { .. } => KIND_TRANSFER,
+            ProposalKind::SetStakingContract { .. } => KIND_SET_VOTE_TOKEN,
+            ProposalKind::AddBounty { .. } => KIND_ADD_BOUNTY,
+            ProposalKind::BountyDone { .. } => KIND_BOUNTY_DONE,
+            ProposalKind::Vote => KIND_VOTE,
+            ProposalKind::FactoryInfoUpdate { .. } => KIND_FACTORY_INFO_UPDATE,
+            ProposalKind::ChangePolicyAddOrUpdateRole { .. } => KIND_POLICY_ADD_OR_UPDATE_ROLE,
+            ProposalKind::ChangePolicyRemoveRole { .. } => KIND_POLICY_REMOVE_ROLE,
             ProposalKind::ChangePolicyUpdateDefaultVotePolicy { .. } => {
-                "policy_update_default_vote_policy"
+                KIND_POLICY_UPDATE_DEFAULT_VOTE_POLICY
             }
-            ProposalKind::ChangePolicyUpdateParameters { .. } => "policy_update_parameters",
+            ProposalKind::ChangePolicyUpdateParameters { .. } => KIND_POLICY_UPDATE_PARAMETERS,
+            ProposalKind::ApproveGrant { .. } => KIND_APPROVE_GRANT,
+            ProposalKind::GrantMilestoneDone { .. } => KIND_GRANT_MILESTONE_DONE,
+            ProposalKind::SetAvatarNft { .. } => KIND_SET_AVATAR_NFT,
+            ProposalKind::ChangeRoleVotePolicy { .. } => KIND_POLICY_CHANGE_ROLE_VOTE_POLICY,
+            ProposalKind::SetVoteMirror { .. } => KIND_SET_VOTE_MIRROR,
+            ProposalKind::UpdateBlocklist { .. } => KIND_UPDATE_BLOCKLIST,
+            ProposalKind::Lockdown => KIND_LOCKDOWN,
+            ProposalKind::AddMembersToRole { .. } => KIND_ADD_MEMBERS_TO_ROLE,
+            ProposalKind::SetGasConfig { .. } => KIND_SET_GAS_CONFIG,
+            ProposalKind::SetBountyPool { .. } => KIND_SET_BOUNTY_POOL,
+            ProposalKind::MigrateTreasury { .. } => KIND_MIGRATE_TREASURY,
+            ProposalKind::AddRecurringVote { .. } => KIND_ADD_RECURRING_VOTE,
+            ProposalKind::ReplaceRoleMembers { .. } => KIND_REPLACE_ROLE_MEMBERS,
+            ProposalKind::ApproveLoan { .. } => KIND_APPROVE_LOAN,
+            ProposalKind::LoanDefault { .. } => KIND_LOAN_DEFAULT,
+            ProposalKind::RemoveRecurringVote { .. } => KIND_REMOVE_RECURRING_VOTE,
+            ProposalKind::DeployExecutor { .. } => KIND_DEPLOY_EXECUTOR,
         }
     }
 }
@@ -149,6 +480,8 @@ pub enum Vote {
     Approve = 0x0,
     Reject = 0x1,
     Remove = 0x2,
+    /// Not counted in `vote_counts`, see `Contract::spam_flags`.
+    Spam = 0x3,
 }
 
 impl From<Action> for Vote {
@@ -157,6 +490,7 @@ impl From<Action> for Vote {
             Action::VoteApprove => Vote::Approve,
             Action::VoteReject => Vote::Reject,
             Action::VoteRemove => Vote::Remove,
+            Action::FlagSpam => Vote::Spam,
             _ => unreachable!(),
         }
     }
@@ -175,7 +509,7 @@ pub struct Proposal {
     pub kind: ProposalKind,
     /// Current status of the proposal.
     pub status: ProposalStatus,
-    /// Count of votes per role per decision: yes / no / spam.
+    /// Count of votes per role per decision: yes / no / remove.
     pub vote_counts: HashMap<String, [Balance; 3]>,
     /// Map of who voted and how.
     pub votes: HashMap<AccountId, Vote>,
@@ -183,17 +517,19 @@ pub struct Proposal {
     pub submission_time: U64,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
-#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
-#[serde(crate = "near_sdk::serde")]
+/// Proposal as stored. Proposals of the previous release are converted when read.
+#[derive(BorshSerialize, BorshDeserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum VersionedProposal {
-    Default(Proposal),
+    Default(OldProposal),
+    Current(Proposal),
 }
 
 impl From<VersionedProposal> for Proposal {
     fn from(v: VersionedProposal) -> Self {
         match v {
-            VersionedProposal::Default(p) => p,
+            VersionedProposal::Default(p) => p.into(),
+            VersionedProposal::Current(p) => p,
         }
     }
 }
@@ -207,25 +543,45 @@ impl Proposal {
         vote: Vote,
         policy: &Policy,
         user_weight: Balance,
-    ) {
+        role_weight: Balance,
+    ) -> HashMap<String, Balance> {
+        let mut counted_weights = HashMap::default();
         for role in roles {
             let amount = if policy.is_token_weighted(role, &self.kind.to_policy_label().to_string())
             {
                 user_weight
             } else {
-                1
+                role_weight
             };
-            self.vote_counts.entry(role.clone()).or_insert([0u128; 3])[vote.clone() as usize] +=
-                amount;
+            if !matches!(vote, Vote::Spam) {
+                self.vote_counts.entry(role.clone()).or_insert([0u128; 3])
+                    [vote.clone() as usize] += amount;
+            }
+            counted_weights.insert(role.clone(), amount);
         }
         assert!(
             self.votes.insert(account_id.clone(), vote).is_none(),
             "ERR_ALREADY_VOTED"
         );
+        counted_weights
     }
 }
 
+/// Outcome of acting on a proposal, returned by `act_proposal`.
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActProposalResult {
+    pub proposal_id: u64,
+    /// Weight of the vote counted per role of the voter. Empty for actions other than votes.
+    pub counted_weights: HashMap<String, U128>,
+    /// Count of votes per role after the action: yes / no / remove.
+    pub vote_counts: HashMap<String, [U128; 3]>,
+    /// Status of the proposal after the action.
+    pub status: ProposalStatus,
+}
+
+#[derive(BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct ProposalInput {
     /// Description of this proposal.
@@ -234,6 +590,13 @@ pub struct ProposalInput {
     pub kind: ProposalKind,
 }
 
+/// Message passed to `ft_on_transfer` to add a proposal with the bond paid in the token.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalBondMsg {
+    pub proposal: ProposalInput,
+}
+
 impl From<ProposalInput> for Proposal {
     fn from(input: ProposalInput) -> Self {
         Self {
@@ -248,6 +611,91 @@ impl From<ProposalInput> for Proposal {
     }
 }
 
+/// Errors of validating `Transfer` proposal when it is added.
+#[derive(Clone, PartialEq, Debug)]
+pub enum TransferError {
+    /// Token is neither "" for $NEAR nor a valid account id.
+    InvalidToken,
+    /// Amount to transfer is zero.
+    ZeroAmount,
+    /// Receiver is this DAO and the policy doesn't allow self transfers.
+    SelfTransfer,
+    /// Message is set for $NEAR transfer.
+    BaseTokenMsg,
+    /// Message is longer than `MAX_TRANSFER_MSG_LEN`.
+    MsgTooLong,
+}
+
+impl TransferError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransferError::InvalidToken => "ERR_INVALID_TOKEN_ID",
+            TransferError::ZeroAmount => "ERR_TRANSFER_ZERO_AMOUNT",
+            TransferError::SelfTransfer => "ERR_TRANSFER_TO_SELF",
+            TransferError::BaseTokenMsg => "ERR_BASE_TOKEN_NO_MSG",
+            TransferError::MsgTooLong => "ERR_TRANSFER_MSG_TOO_LONG",
+        }
+    }
+}
+
+/// Maximum length of `msg` passed to `ft_transfer_call`.
+pub const MAX_TRANSFER_MSG_LEN: usize = 1024;
+
+/// Checks that `Transfer` proposal can be executed if approved.
+pub fn validate_transfer(
+    policy: &Policy,
+    token_id: &OldAccountId,
+    receiver_id: &AccountId,
+    amount: Balance,
+    msg: Option<&str>,
+) -> Result<(), TransferError> {
+    if token_id != OLD_BASE_TOKEN && token_id.parse::<AccountId>().is_err() {
+        return Err(TransferError::InvalidToken);
+    }
+    if amount == 0 {
+        return Err(TransferError::ZeroAmount);
+    }
+    if !policy.allow_self_transfers && *receiver_id == env::current_account_id() {
+        return Err(TransferError::SelfTransfer);
+    }
+    if let Some(msg) = msg {
+        if token_id == OLD_BASE_TOKEN {
+            return Err(TransferError::BaseTokenMsg);
+        }
+        if msg.len() > MAX_TRANSFER_MSG_LEN {
+            return Err(TransferError::MsgTooLong);
+        }
+    }
+    Ok(())
+}
+
+/// Whether given account is a direct sub-account of this DAO, which only the DAO can create.
+fn is_sub_account(account_id: &AccountId) -> bool {
+    matches!(
+        account_id
+            .as_str()
+            .strip_suffix(&format!(".{}", env::current_account_id())),
+        Some(name) if !name.contains('.')
+    )
+}
+
+/// Checks the result of the executed promise for kinds that depend on it.
+/// `SetAvatarNft` requires the token to be owned by or approved for this DAO.
+fn internal_is_valid_callback_result(kind: &ProposalKind, value: &[u8]) -> bool {
+    match kind {
+        ProposalKind::SetAvatarNft { .. } => {
+            let token: near_sdk::serde_json::Value = match near_sdk::serde_json::from_slice(value) {
+                Ok(token) => token,
+                Err(_) => return false,
+            };
+            let dao_id = env::current_account_id().to_string();
+            token["owner_id"].as_str() == Some(dao_id.as_str())
+                || token["approved_account_ids"].get(&dao_id).is_some()
+        }
+        _ => true,
+    }
+}
+
 impl Contract {
     /// Execute payout of given token to given user.
     pub(crate) fn internal_payout(
@@ -269,7 +717,7 @@ impl Contract {
                     msg,
                     token_id.as_ref().unwrap().clone(),
                     ONE_YOCTO_NEAR,
-                    GAS_FOR_FT_TRANSFER,
+                    self.gas_config.ft_transfer,
                 )
             } else {
                 ext_fungible_token::ft_transfer(
@@ -278,40 +726,230 @@ impl Contract {
                     Some(memo),
                     token_id.as_ref().unwrap().clone(),
                     ONE_YOCTO_NEAR,
-                    GAS_FOR_FT_TRANSFER,
+                    self.gas_config.ft_transfer,
                 )
             }
             .into()
         }
     }
 
-    fn internal_return_bonds(&mut self, policy: &Policy, proposal: &Proposal) -> Promise {
+    /// Weight of `FlagSpam` votes per role for given proposal, if any.
+    pub(crate) fn internal_spam_flags(&self, id: u64) -> Option<HashMap<String, U128>> {
+        self.spam_flags.get(&id).map(|spam_flags| {
+            spam_flags
+                .into_iter()
+                .map(|(role, weight)| (role, U128(weight)))
+                .collect()
+        })
+    }
+
+    /// Balance of $NEAR not locked for bonds or storage.
+    pub(crate) fn internal_available_balance(&self) -> Balance {
+        env::account_balance()
+            .saturating_sub(self.locked_amount)
+            .saturating_sub(env::storage_usage() as Balance * env::storage_byte_cost())
+    }
+
+    /// Records token bond held in escrow.
+    pub(crate) fn internal_lock_tokens(&mut self, token_id: &AccountId, amount: Balance) {
+        let locked = self.locked_tokens.get(token_id).unwrap_or_default();
+        self.locked_tokens.insert(token_id, &(locked + amount));
+    }
+
+    /// Releases token bond held in escrow, when it's returned or forfeited to the treasury.
+    pub(crate) fn internal_unlock_tokens(&mut self, token_id: &AccountId, amount: Balance) {
+        let locked = self.locked_tokens.get(token_id).unwrap_or_default() - amount;
+        if locked == 0 {
+            self.locked_tokens.remove(token_id);
+        } else {
+            self.locked_tokens.insert(token_id, &locked);
+        }
+    }
+
+    /// Given balance of the token without the bonds held in escrow.
+    pub(crate) fn internal_available_token_balance(
+        &self,
+        token_id: &AccountId,
+        balance: Balance,
+    ) -> Balance {
+        balance.saturating_sub(self.locked_tokens.get(token_id).unwrap_or_default())
+    }
+
+    /// Checks treasury outflow of given proposal against the policy's spend limits and records it
+    /// in the period outflow of the token, until the payout's callback.
+    pub(crate) fn internal_record_outflow(
+        &mut self,
+        policy: &Policy,
+        proposal_id: u64,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) {
+        let limits = match &policy.spend_limits {
+            Some(limits) => limits,
+            None => return,
+        };
+        if amount == 0 {
+            return;
+        }
+        let max_period_outflow = match token_id {
+            None => {
+                assert!(
+                    amount
+                        <= self.internal_available_balance() / 10_000
+                            * limits.max_transfer_bps as Balance,
+                    "ERR_SPEND_LIMIT_TRANSFER"
+                );
+                limits.max_period_outflow.0
+            }
+            Some(token_id) => {
+                let (_, max_period_outflow) = limits
+                    .max_token_period_outflows
+                    .iter()
+                    .find(|(id, _)| id == token_id)
+                    .expect("ERR_SPEND_LIMIT_NO_TOKEN");
+                max_period_outflow.0
+            }
+        };
+        let (period_start, period_amount) = self.internal_count_outflow(token_id, amount);
+        assert!(
+            period_amount <= max_period_outflow,
+            "ERR_SPEND_LIMIT_PERIOD"
+        );
+        self.proposal_outflows.insert(
+            &proposal_id,
+            &RecordedOutflow {
+                token_id: token_id.clone(),
+                amount,
+                period_start,
+            },
+        );
+    }
+
+    /// Adds given amount to the outflow of the token in the current spend limit period, starting
+    /// a new period if the last one is over. Returns start of the period and its total outflow.
+    pub(crate) fn internal_count_outflow(
+        &mut self,
+        token_id: &Option<AccountId>,
+        amount: Balance,
+    ) -> (u64, Balance) {
+        let (mut start, mut total) = match token_id {
+            None => (self.outflow_period_start, self.outflow_period_amount),
+            Some(token_id) => self.token_outflows.get(token_id).unwrap_or_default(),
+        };
+        let now = env::block_timestamp();
+        if now >= start + SPEND_LIMIT_PERIOD {
+            start = now;
+            total = 0;
+        }
+        total += amount;
+        match token_id {
+            None => {
+                self.outflow_period_start = start;
+                self.outflow_period_amount = total;
+            }
+            Some(token_id) => {
+                self.token_outflows.insert(token_id, &(start, total));
+            }
+        }
+        (start, total)
+    }
+
+    /// Clears outflow recorded for given proposal once its payout is done.
+    /// If the payout failed, the outflow is taken back out of its period, unless that's over.
+    fn internal_release_outflow(&mut self, proposal_id: u64, paid: bool) {
+        let outflow = match self.proposal_outflows.remove(&proposal_id) {
+            Some(outflow) if !paid => outflow,
+            _ => return,
+        };
+        match &outflow.token_id {
+            None => {
+                if self.outflow_period_start == outflow.period_start {
+                    self.outflow_period_amount -= outflow.amount;
+                }
+            }
+            Some(token_id) => {
+                if let Some((start, total)) = self.token_outflows.get(token_id) {
+                    if start == outflow.period_start {
+                        self.token_outflows
+                            .insert(token_id, &(start, total - outflow.amount));
+                    }
+                }
+            }
+        }
+    }
+
+    fn internal_return_bonds(
+        &mut self,
+        policy: &Policy,
+        proposal_id: u64,
+        proposal: &Proposal,
+    ) -> PromiseOrValue<()> {
         match &proposal.kind {
-            ProposalKind::BountyDone { .. } => {
-                self.locked_amount -= policy.bounty_bond.0;
-                Promise::new(proposal.proposer.clone()).transfer(policy.bounty_bond.0);
+            ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+            } => {
+                self.internal_return_bounty_bond(policy, *bounty_id, receiver_id);
             }
             _ => {}
         }
 
-        self.locked_amount -= policy.proposal_bond.0;
-        Promise::new(proposal.proposer.clone()).transfer(policy.proposal_bond.0)
+        let (token_id, amount) = self.internal_proposal_bond(policy, proposal_id);
+        self.proposal_token_bonds.remove(&proposal_id);
+        self.proposal_bonds.remove(&proposal_id);
+        match token_id {
+            Some(token_id) => {
+                self.internal_unlock_tokens(&token_id, amount);
+                self.internal_payout(
+                    &Some(token_id),
+                    &proposal.proposer,
+                    amount,
+                    format!("Proposal {} bond", proposal_id),
+                    None,
+                )
+            }
+            None if amount == 0 => PromiseOrValue::Value(()),
+            None => {
+                self.locked_amount -= amount;
+                Promise::new(proposal.proposer.clone())
+                    .transfer(amount)
+                    .into()
+            }
+        }
+    }
+
+    /// Returns token, `None` for $NEAR, and amount of the bond escrowed for given proposal.
+    fn internal_proposal_bond(
+        &self,
+        policy: &Policy,
+        proposal_id: u64,
+    ) -> (Option<AccountId>, Balance) {
+        match self.proposal_token_bonds.get(&proposal_id) {
+            Some(bond) => (Some(bond.token_id), bond.amount.0),
+            None => (
+                None,
+                self.proposal_bonds
+                    .get(&proposal_id)
+                    .unwrap_or(policy.proposal_bond.0),
+            ),
+        }
     }
 
     /// Executes given proposal and updates the contract's state.
-    fn internal_execute_proposal(
+    /// Returns true if the execution is finished in `on_proposal_callback`.
+    pub(crate) fn internal_execute_proposal(
         &mut self,
         policy: &Policy,
         proposal: &Proposal,
         proposal_id: u64,
-    ) -> PromiseOrValue<()> {
+    ) -> bool {
         let result = match &proposal.kind {
             ProposalKind::ChangeConfig { config } => {
                 self.config.set(config);
                 PromiseOrValue::Value(())
             }
-            ProposalKind::ChangePolicy { policy } => {
-                self.policy.set(policy);
+            ProposalKind::ChangePolicy { policy: new_policy } => {
+                self.policy.set(new_policy);
                 PromiseOrValue::Value(())
             }
             ProposalKind::AddMemberToRole { member_id, role } => {
@@ -330,16 +968,40 @@ impl Contract {
                 receiver_id,
                 actions,
             } => {
-                let mut promise = Promise::new(receiver_id.clone().into());
-                for action in actions {
-                    promise = promise.function_call(
-                        action.method_name.clone().into(),
-                        action.args.clone().into(),
-                        action.deposit.0,
-                        Gas(action.gas.0),
-                    )
+                // Deposits escrowed or funded by the proposer are not treasury outflows.
+                let funding = self.proposal_fundings.get(&proposal_id).unwrap_or_default();
+                let deposits = proposal.kind.fundable_amount().unwrap_or_default();
+                self.internal_record_outflow(policy, proposal_id, &None, deposits - funding);
+                if let Some(executor_id) = policy.executors.get(proposal.kind.to_policy_label()) {
+                    // Calls are made by the executor, receivers see it as the predecessor.
+                    let mut promise = Promise::new(executor_id.clone());
+                    for action in actions {
+                        let args = near_sdk::serde_json::json!({
+                            "receiver_id": receiver_id,
+                            "method_name": action.method_name,
+                            "args": action.args,
+                            "gas": action.gas,
+                        });
+                        promise = promise.function_call(
+                            "proxy_call".to_string(),
+                            args.to_string().into_bytes(),
+                            action.deposit.0,
+                            Gas(action.gas.0) + self.gas_config.proxy_call,
+                        )
+                    }
+                    promise.into()
+                } else {
+                    let mut promise = Promise::new(receiver_id.clone().into());
+                    for action in actions {
+                        promise = promise.function_call(
+                            action.method_name.clone().into(),
+                            action.args.clone().into(),
+                            action.deposit.0,
+                            Gas(action.gas.0),
+                        )
+                    }
+                    promise.into()
                 }
-                promise.into()
             }
             ProposalKind::UpgradeSelf { hash } => {
                 upgrade_using_factory(hash.clone());
@@ -358,26 +1020,50 @@ impl Contract {
                 receiver_id,
                 amount,
                 msg,
-            } => self.internal_payout(
-                &convert_old_to_new_token(token_id),
-                &receiver_id,
-                amount.0,
-                proposal.description.clone(),
-                msg.clone(),
-            ),
+            } => {
+                let token_id = convert_old_to_new_token(token_id);
+                // Part funded by the proposer is not a treasury outflow.
+                let funding = self.proposal_fundings.get(&proposal_id).unwrap_or_default();
+                self.internal_record_outflow(policy, proposal_id, &token_id, amount.0 - funding);
+                self.internal_payout(
+                    &token_id,
+                    &receiver_id,
+                    amount.0,
+                    proposal.description.clone(),
+                    msg.clone(),
+                )
+            }
             ProposalKind::SetStakingContract { staking_id } => {
                 assert!(self.staking_id.is_none(), "ERR_INVALID_STAKING_CHANGE");
                 self.staking_id = Some(staking_id.clone().into());
                 PromiseOrValue::Value(())
             }
             ProposalKind::AddBounty { bounty } => {
-                self.internal_add_bounty(bounty);
+                let initial_storage = env::storage_usage();
+                let bounty_id = self.internal_add_bounty(bounty);
+                self.internal_charge_author_storage(
+                    policy,
+                    StorageChargeKey::Bounty(bounty_id),
+                    &proposal.proposer,
+                    initial_storage,
+                    true,
+                );
+                if let Some(translations) = self.proposal_translations.get(&proposal_id) {
+                    let initial_storage = env::storage_usage();
+                    self.bounty_translations.insert(&bounty_id, &translations);
+                    self.internal_charge_storage(
+                        StorageChargeKey::Bounty(bounty_id),
+                        &proposal.proposer,
+                        initial_storage,
+                        true,
+                    );
+                }
                 PromiseOrValue::Value(())
             }
             ProposalKind::BountyDone {
                 bounty_id,
                 receiver_id,
-            } => self.internal_execute_bounty_payout(*bounty_id, &receiver_id.clone().into(), true),
+            } => self.internal_execute_bounty_payout(policy, proposal_id, *bounty_id, receiver_id),
             ProposalKind::Vote => PromiseOrValue::Value(()),
             ProposalKind::FactoryInfoUpdate { factory_info } => {
                 internal_set_factory_info(factory_info);
@@ -407,116 +1093,659 @@ impl Contract {
                 self.policy.set(&VersionedPolicy::Current(new_policy));
                 PromiseOrValue::Value(())
             }
+            ProposalKind::ChangeRoleVotePolicy {
+                role,
+                kind_label,
+                vote_policy,
+            } => {
+                let mut new_policy = policy.clone();
+                new_policy.update_role_vote_policy(role, kind_label, vote_policy);
+                self.policy.set(&VersionedPolicy::Current(new_policy));
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::SetVoteMirror { mirror } => {
+                self.vote_mirror = mirror.clone();
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::UpdateBlocklist { add, remove } => {
+                self.internal_update_blocklist(add, remove);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::Lockdown => {
+                self.internal_lockdown();
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::AddMembersToRole { role, members } => {
+                self.internal_add_members_chunk(proposal_id, role, members, 0);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::SetGasConfig { gas_config } => {
+                self.gas_config = gas_config.clone();
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::SetBountyPool { bounty_id, budget } => {
+                self.internal_set_bounty_pool(*bounty_id, budget.0);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::MigrateTreasury {
+                target_dao,
+                tokens,
+                nfts,
+            } => {
+                self.internal_migrate_treasury_chunk(proposal_id, target_dao, tokens, nfts, 0);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::AddRecurringVote {
+                description,
+                interval,
+                first_time,
+                pool,
+            } => {
+                self.internal_add_recurring_vote(
+                    proposal_id,
+                    description,
+                    *interval,
+                    *first_time,
+                    *pool,
+                );
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ReplaceRoleMembers { role, new_members } => {
+                // Checked again, as the policy could have changed since the proposal was added.
+                let new_policy = policy.with_role_members(role, new_members);
+                self.policy.set(&VersionedPolicy::Current(new_policy));
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ApproveLoan {
+                borrower,
+                token_id,
+                amount,
+                ..
+            } => {
+                let token_id = convert_old_to_new_token(token_id);
+                self.internal_record_outflow(policy, proposal_id, &token_id, amount.0);
+                self.internal_payout(
+                    &token_id,
+                    borrower,
+                    amount.0,
+                    proposal.description.clone(),
+                    None,
+                )
+            }
+            ProposalKind::LoanDefault { loan_id } => {
+                self.internal_default_loan(*loan_id);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::RemoveRecurringVote { recurring_vote_id } => {
+                self.internal_remove_recurring_vote(*recurring_vote_id);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::DeployExecutor {
+                executor_id,
+                hash,
+                amount,
+            } => {
+                self.internal_record_outflow(policy, proposal_id, &None, amount.0);
+                let code = env::storage_read(&CryptoHash::from(*hash)).expect("ERR_NO_HASH");
+                Promise::new(executor_id.clone())
+                    .create_account()
+                    .transfer(amount.0)
+                    .deploy_contract(code)
+                    .function_call(
+                        "new".to_string(),
+                        near_sdk::serde_json::json!({ "owner_id": env::current_account_id() })
+                            .to_string()
+                            .into_bytes(),
+                        0,
+                        self.gas_config.executor_init,
+                    )
+                    .into()
+            }
+            ProposalKind::ApproveGrant { grant_id } => {
+                self.internal_approve_grant(*grant_id);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::GrantMilestoneDone {
+                grant_id,
+                milestone,
+            } => self.internal_execute_grant_payout(policy, proposal_id, *grant_id, *milestone),
+            ProposalKind::SetAvatarNft {
+                contract_id,
+                token_id,
+            } => ext_non_fungible_token::nft_token(
+                token_id.clone(),
+                contract_id.clone(),
+                0,
+                self.gas_config.nft_token,
+            )
+            .into(),
         };
+        // Policy can be changed by any of the policy-change kinds.
+        let new_policy = self.policy.get().unwrap().to_policy();
+        if policy.vote_decay_after.is_none() && new_policy.vote_decay_after.is_some() {
+            // Absences are counted from the next proposal for members who haven't voted yet.
+            self.vote_decay_start = self.last_proposal_id;
+        }
+        match &proposal.kind {
+            ProposalKind::ChangePolicy { .. }
+            | ProposalKind::AddMemberToRole { .. }
+            | ProposalKind::RemoveMemberFromRole { .. }
+            | ProposalKind::ChangePolicyAddOrUpdateRole { .. }
+            | ProposalKind::ChangePolicyRemoveRole { .. }
+            | ProposalKind::ReplaceRoleMembers { .. } => {
+                self.internal_record_membership_changes(proposal_id, policy, &new_policy);
+            }
+            _ => {}
+        }
         match result {
-            PromiseOrValue::Promise(promise) => promise
-                .then(ext_self::on_proposal_callback(
+            PromiseOrValue::Promise(promise) => {
+                promise.then(ext_self::on_proposal_callback(
                     proposal_id,
                     env::current_account_id(),
                     0,
-                    GAS_FOR_FT_TRANSFER,
-                ))
-                .into(),
-            PromiseOrValue::Value(()) => self.internal_return_bonds(&policy, &proposal).into(),
+                    self.gas_config.proposal_callback,
+                ));
+                true
+            }
+            PromiseOrValue::Value(()) => {
+                self.internal_return_bonds(policy, proposal_id, proposal);
+                false
+            }
         }
     }
 
     pub(crate) fn internal_callback_proposal_success(
         &mut self,
+        proposal_id: u64,
         proposal: &mut Proposal,
     ) -> PromiseOrValue<()> {
+        self.internal_release_outflow(proposal_id, true);
         let policy = self.policy.get().unwrap().to_policy();
-        if let ProposalKind::BountyDone { bounty_id, .. } = proposal.kind {
+        if let ProposalKind::BountyDone {
+            bounty_id,
+            receiver_id,
+        } = &proposal.kind
+        {
+            let bounty_id = *bounty_id;
+            self.internal_remove_claim(bounty_id, receiver_id);
             let mut bounty: Bounty = self.bounties.get(&bounty_id).expect("ERR_NO_BOUNTY").into();
             if bounty.times == 0 {
                 self.bounties.remove(&bounty_id);
+                self.bounty_pools.remove(&bounty_id);
+                self.bounty_translations.remove(&bounty_id);
+                self.internal_refund_author_storage(StorageChargeKey::Bounty(bounty_id));
+                self.open_bounties.remove(&bounty_id);
             } else {
                 bounty.times -= 1;
+                if bounty.times == 0 {
+                    self.internal_renew_bounty(bounty_id, &mut bounty);
+                }
                 self.bounties
-                    .insert(&bounty_id, &VersionedBounty::Default(bounty));
+                    .insert(&bounty_id, &VersionedBounty::Default(bounty.clone()));
             }
+            self.internal_emit_bounty_digest(
+                bounty_id,
+                BountyUpdate::Completed,
+                Some(receiver_id),
+                &bounty,
+            );
+        }
+        if let ProposalKind::GrantMilestoneDone {
+            grant_id,
+            milestone,
+        } = proposal.kind
+        {
+            self.internal_finish_grant_milestone(grant_id, milestone, true);
+        }
+        if let ProposalKind::SetAvatarNft {
+            contract_id,
+            token_id,
+        } = &proposal.kind
+        {
+            let mut config = self.config.get().unwrap();
+            // Metadata was checked when the proposal was added, but the config may have changed
+            // since. The avatar is then skipped instead of panicking, so the proposal is still
+            // approved and its bond returned.
+            match config.metadata_object() {
+                Some(mut metadata) => {
+                    metadata.insert(
+                        AVATAR_NFT_METADATA_KEY.to_string(),
+                        near_sdk::serde_json::json!({
+                            "contract_id": contract_id,
+                            "token_id": token_id,
+                        }),
+                    );
+                    config.metadata = Base64VecU8(
+                        near_sdk::serde_json::to_vec(&near_sdk::serde_json::Value::Object(
+                            metadata,
+                        ))
+                        .unwrap(),
+                    );
+                    self.config.set(&config);
+                }
+                None => log!("Config metadata is not a JSON object, avatar not set"),
+            }
+        }
+        if let ProposalKind::ApproveLoan { .. } = &proposal.kind {
+            self.internal_add_loan(proposal_id, &proposal.kind);
+        }
+        if let ProposalKind::Transfer {
+            token_id,
+            receiver_id,
+            amount,
+            ..
+        } = &proposal.kind
+        {
+            if matches!(policy.trusted_min_payout_amount(token_id), Some(min) if amount.0 >= min) {
+                let key = (receiver_id.clone(), token_id.clone());
+                let payouts = self.receiver_payouts.get(&key).unwrap_or_default();
+                self.receiver_payouts.insert(&key, &(payouts + 1));
+            }
+        }
+        // Escrowed deposits and proposer funding were spent on the executed calls.
+        self.locked_amount -= proposal.kind.proposer_escrow();
+        if let Some(funding) = self.proposal_fundings.remove(&proposal_id) {
+            self.locked_amount -= funding;
         }
         proposal.status = ProposalStatus::Approved;
-        self.internal_return_bonds(&policy, &proposal).into()
+        self.internal_return_bonds(&policy, proposal_id, proposal)
     }
 
     pub(crate) fn internal_callback_proposal_fail(
         &mut self,
+        proposal_id: u64,
         proposal: &mut Proposal,
     ) -> PromiseOrValue<()> {
+        self.internal_release_outflow(proposal_id, false);
         proposal.status = ProposalStatus::Failed;
         PromiseOrValue::Value(())
     }
 
     /// Process rejecting proposal.
-    fn internal_reject_proposal(
+    pub(crate) fn internal_reject_proposal(
         &mut self,
         policy: &Policy,
+        proposal_id: u64,
         proposal: &Proposal,
         return_bonds: bool,
     ) -> PromiseOrValue<()> {
         if return_bonds {
             // Return bond to the proposer.
-            self.internal_return_bonds(policy, proposal);
+            self.internal_return_bonds(policy, proposal_id, proposal);
+        } else {
+            // Bond stays in the DAO's treasury and becomes available to spend.
+            let (token_id, amount) = self.internal_proposal_bond(policy, proposal_id);
+            self.proposal_token_bonds.remove(&proposal_id);
+            self.proposal_bonds.remove(&proposal_id);
+            match token_id {
+                Some(token_id) => self.internal_unlock_tokens(&token_id, amount),
+                None => self.locked_amount -= amount,
+            }
+        }
+        let escrow = proposal.kind.proposer_escrow();
+        if escrow > 0 {
+            // Calls were not executed, return escrowed deposits to the proposer.
+            self.locked_amount -= escrow;
+            Promise::new(proposal.proposer.clone()).transfer(escrow);
+        }
+        if let Some(funding) = self.proposal_fundings.remove(&proposal_id) {
+            // Proposal was not executed, return the funding to the proposer.
+            self.locked_amount -= funding;
+            Promise::new(proposal.proposer.clone()).transfer(funding);
         }
         match &proposal.kind {
             ProposalKind::BountyDone {
                 bounty_id,
                 receiver_id,
             } => {
-                self.internal_execute_bounty_payout(*bounty_id, &receiver_id.clone().into(), false)
+                // Bond was returned with the proposal bond, unless it's forfeited.
+                self.internal_close_bounty_claim(policy, *bounty_id, receiver_id, !return_bonds);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::ApproveGrant { grant_id } => {
+                if proposal.status != ProposalStatus::Expired {
+                    self.internal_reject_grant(*grant_id);
+                }
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::GrantMilestoneDone {
+                grant_id,
+                milestone,
+            } => {
+                self.internal_finish_grant_milestone(*grant_id, *milestone, false);
+                PromiseOrValue::Value(())
+            }
+            ProposalKind::LoanDefault { loan_id } => {
+                // Can be escalated again with `loan_check_overdue`.
+                self.internal_set_loan_default_proposal(*loan_id, None);
+                PromiseOrValue::Value(())
             }
             _ => PromiseOrValue::Value(()),
         }
     }
 
+    /// Returns number of successful past payouts of the token to the receiver of `Transfer` proposal.
+    pub(crate) fn internal_receiver_payouts(&self, proposal_kind: &ProposalKind) -> u32 {
+        match proposal_kind {
+            ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                ..
+            } => self
+                .receiver_payouts
+                .get(&(receiver_id.clone(), token_id.clone()))
+                .unwrap_or_default(),
+            _ => 0,
+        }
+    }
+
+    /// Checks if group member's role weight votes stopped counting, because they didn't vote on
+    /// `vote_decay_after` proposals they could vote on since their last vote, before proposal `id`.
+    /// `kind_counts` are the counts of proposals added before `id`, see `proposal_kind_counts`.
+    fn internal_is_vote_decayed(
+        &self,
+        policy: &Policy,
+        account_id: &AccountId,
+        id: u64,
+        kind_counts: &HashMap<String, u64>,
+    ) -> bool {
+        let absences = match policy.vote_decay_after {
+            Some(absences) => absences,
+            None => return false,
+        };
+        let first_missed = match self.member_last_votes.get(account_id) {
+            Some(last_vote) => std::cmp::max(last_vote + 1, self.vote_decay_start),
+            None => self.vote_decay_start,
+        };
+        if first_missed >= id {
+            return false;
+        }
+        let first_counts = self
+            .proposal_kind_counts
+            .get(&first_missed)
+            .unwrap_or_default();
+        let user = self.internal_account_info(account_id.clone());
+        let vote_label = Action::VoteApprove.to_policy_label();
+        let missed: u64 = kind_counts
+            .iter()
+            .filter(|(label, _)| policy.can_execute_label(user.clone(), label, &vote_label).1)
+            .map(|(label, count)| {
+                count.saturating_sub(first_counts.get(label).copied().unwrap_or(0))
+            })
+            .sum();
+        missed >= absences
+    }
+
+    /// Number of members per group role whose votes don't count on given proposal because of
+    /// vote decay: those who voted while decayed and those who would if they voted now.
+    fn internal_decayed_members(
+        &self,
+        policy: &Policy,
+        id: u64,
+        proposal: &Proposal,
+    ) -> HashMap<String, Balance> {
+        let mut decayed_members = HashMap::default();
+        if policy.vote_decay_after.is_none() {
+            return decayed_members;
+        }
+        let voter_counts = self.proposal_voter_counts.get(&id).unwrap_or_default();
+        let kind_counts = self.proposal_kind_counts.get(&id).unwrap_or_default();
+        for role in &policy.roles {
+            if let RoleKind::Group(members) = &role.kind {
+                // Each vote of a member that wasn't decayed counts with weight 1.
+                let voters: u32 = voter_counts.get(&role.name).map_or(0, |c| c.iter().sum());
+                let counted: Balance = proposal
+                    .vote_counts
+                    .get(&role.name)
+                    .map_or(0, |c| c.iter().sum());
+                let absent = members
+                    .iter()
+                    .filter(|member| {
+                        !proposal.votes.contains_key(*member)
+                            && self.internal_is_vote_decayed(policy, member, id, &kind_counts)
+                    })
+                    .count();
+                decayed_members.insert(
+                    role.name.clone(),
+                    (voters as Balance).saturating_sub(counted) + absent as Balance,
+                );
+            }
+        }
+        decayed_members
+    }
+
+    /// Checks if given proposal can still be voted on or re-executed.
+    pub(crate) fn internal_is_proposal_pending(&self, policy: &Policy, id: u64) -> bool {
+        match self.proposals.get(&id) {
+            Some(proposal) => {
+                let proposal: Proposal = proposal.into();
+                matches!(
+                    proposal.status,
+                    ProposalStatus::InProgress | ProposalStatus::Failed
+                ) && proposal.submission_time.0 + policy.proposal_period.0 >= env::block_timestamp()
+            }
+            None => false,
+        }
+    }
+
+    /// Stops counting proposals that expired without being finalized as active.
+    fn internal_release_expired_proposals(&mut self, policy: &Policy) {
+        let expired: Vec<u64> = self
+            .active_proposals
+            .iter()
+            .filter(|id| !self.internal_is_proposal_pending(policy, *id))
+            .collect();
+        for id in expired {
+            self.active_proposals.remove(&id);
+        }
+    }
+
     pub(crate) fn internal_user_info(&self) -> UserInfo {
-        let account_id = env::predecessor_account_id();
+        self.internal_account_info(env::predecessor_account_id())
+    }
+
+    pub(crate) fn internal_account_info(&self, account_id: AccountId) -> UserInfo {
         UserInfo {
             amount: self.get_user_weight(&account_id),
             account_id,
         }
     }
-}
 
-#[near_bindgen]
-impl Contract {
-    /// Add proposal to this DAO.
-    #[payable]
-    pub fn add_proposal(&mut self, proposal: ProposalInput) -> u64 {
-        // 0. validate bond attached.
-        // TODO: consider bond in the token of this DAO.
+    /// Adds proposal of `sender_id` with the bond paid in `token_id` via `ft_transfer_call`.
+    /// Amount must be equal to the policy's `proposal_token_bonds` entry for the proposal's kind.
+    pub(crate) fn internal_add_proposal_with_token_bond(
+        &mut self,
+        sender_id: AccountId,
+        token_id: AccountId,
+        amount: Balance,
+        proposal: ProposalInput,
+    ) -> u64 {
         let policy = self.policy.get().unwrap().to_policy();
-        assert!(
-            env::attached_deposit() >= policy.proposal_bond.0,
-            "ERR_MIN_BOND"
+        let bond = policy
+            .proposal_token_bonds
+            .get(proposal.kind.to_policy_label())
+            .expect("ERR_NO_PROPOSAL_TOKEN_BOND");
+        assert_eq!(bond.token_id, token_id, "ERR_PROPOSAL_WRONG_BOND_TOKEN");
+        assert_eq!(bond.amount.0, amount, "ERR_PROPOSAL_WRONG_BOND");
+        // Escrowed deposits and mirror bonds can only be attached in $NEAR.
+        assert_eq!(
+            proposal.kind.proposer_escrow() + self.internal_mirror_bond(&proposal.kind),
+            0,
+            "ERR_PROPOSAL_TOKEN_BOND_ESCROW"
+        );
+        let id = self.internal_add_proposal(&policy, sender_id, proposal, 0);
+        self.internal_lock_tokens(&token_id, amount);
+        self.proposal_token_bonds.insert(
+            &id,
+            &ProposalTokenBond {
+                token_id,
+                amount: U128(amount),
+            },
         );
+        id
+    }
 
+    /// Validates and adds proposal of `proposer`, whose bond and escrowed deposits were checked
+    /// by the caller. `deposit` is the $NEAR to lock for them.
+    pub(crate) fn internal_add_proposal(
+        &mut self,
+        policy: &Policy,
+        proposer: AccountId,
+        proposal: ProposalInput,
+        deposit: Balance,
+    ) -> u64 {
         // 1. Validate proposal.
         match &proposal.kind {
             ProposalKind::ChangePolicy { policy } => match policy {
-                VersionedPolicy::Current(_) => {}
+                VersionedPolicy::Current(policy) => {
+                    assert!(
+                        policy
+                            .offchain_tally_kinds
+                            .iter()
+                            .all(|kind| OFFCHAIN_TALLY_KINDS.contains(&kind.as_str())),
+                        "ERR_INVALID_OFFCHAIN_TALLY_KIND"
+                    );
+                    let tiers: HashSet<(&String, u128)> = policy
+                        .transfer_tiers
+                        .iter()
+                        .map(|tier| (&tier.token_id, tier.max_amount.0))
+                        .collect();
+                    assert_eq!(
+                        tiers.len(),
+                        policy.transfer_tiers.len(),
+                        "ERR_DUPLICATE_TRANSFER_TIER"
+                    );
+                    assert!(
+                        policy.executors.values().all(is_sub_account),
+                        "ERR_INVALID_EXECUTOR"
+                    );
+                }
                 _ => panic!("ERR_INVALID_POLICY"),
             },
-            ProposalKind::Transfer { token_id, msg, .. } => {
-                assert!(
-                    !(token_id == OLD_BASE_TOKEN) || msg.is_none(),
-                    "ERR_BASE_TOKEN_NO_MSG"
-                );
+            ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+            } => {
+                if let Err(err) =
+                    validate_transfer(policy, token_id, receiver_id, amount.0, msg.as_deref())
+                {
+                    panic!("{}", err.as_str());
+                }
             }
             ProposalKind::SetStakingContract { .. } => assert!(
                 self.staking_id.is_none(),
                 "ERR_STAKING_CONTRACT_CANT_CHANGE"
             ),
+            ProposalKind::ApproveGrant { grant_id } => assert_eq!(
+                self.internal_get_grant(*grant_id).status,
+                GrantStatus::Submitted,
+                "ERR_GRANT_NOT_SUBMITTED"
+            ),
+            ProposalKind::GrantMilestoneDone {
+                grant_id,
+                milestone,
+            } => self.internal_start_grant_milestone(*grant_id, *milestone),
+            ProposalKind::SetAvatarNft { .. } => assert!(
+                self.config.get().unwrap().metadata_object().is_some(),
+                "ERR_CONFIG_METADATA_NOT_JSON"
+            ),
+            ProposalKind::ChangeRoleVotePolicy {
+                role, kind_label, ..
+            } => {
+                assert!(
+                    policy.internal_get_role(role).is_some(),
+                    "ERR_ROLE_NOT_FOUND"
+                );
+                assert!(
+                    PROPOSAL_KIND_LABELS.contains(&kind_label.as_str()),
+                    "ERR_INVALID_KIND_LABEL"
+                );
+            }
+            ProposalKind::SetVoteMirror {
+                mirror: Some(mirror),
+            } => assert_ne!(
+                mirror.parent_id,
+                env::current_account_id(),
+                "ERR_INVALID_VOTE_MIRROR"
+            ),
+            ProposalKind::Lockdown => {
+                assert!(!self.full_access_keys.is_empty(), "ERR_NO_FULL_ACCESS_KEYS")
+            }
+            ProposalKind::AddMembersToRole { role, members } => {
+                assert!(!members.is_empty(), "ERR_NO_MEMBERS");
+                assert!(
+                    matches!(
+                        policy.internal_get_role(role).map(|role| &role.kind),
+                        Some(RoleKind::Group(_))
+                    ),
+                    "ERR_ROLE_NOT_GROUP"
+                );
+            }
+            ProposalKind::SetGasConfig { gas_config } => gas_config.assert_valid(),
+            ProposalKind::SetBountyPool { bounty_id, .. } => {
+                assert!(self.bounties.get(bounty_id).is_some(), "ERR_NO_BOUNTY")
+            }
+            ProposalKind::MigrateTreasury { target_dao, .. } => assert_ne!(
+                *target_dao,
+                env::current_account_id(),
+                "ERR_INVALID_TARGET_DAO"
+            ),
+            ProposalKind::AddRecurringVote { interval, pool, .. } => {
+                assert!(interval.0 > 0, "ERR_INVALID_INTERVAL");
+                assert!(
+                    pool.0 >= policy.proposal_bond.0,
+                    "ERR_RECURRING_POOL_TOO_SMALL"
+                );
+            }
+            ProposalKind::ReplaceRoleMembers { role, new_members } => {
+                policy.with_role_members(role, new_members);
+            }
+            ProposalKind::ApproveLoan {
+                borrower,
+                token_id,
+                amount,
+                due,
+                ..
+            } => {
+                if let Err(err) = validate_transfer(policy, token_id, borrower, amount.0, None) {
+                    panic!("{}", err.as_str());
+                }
+                assert!(due.0 > env::block_timestamp(), "ERR_INVALID_LOAN_DUE");
+            }
+            ProposalKind::LoanDefault { loan_id } => self.internal_assert_loan_overdue(*loan_id),
+            ProposalKind::RemoveRecurringVote { recurring_vote_id } => assert!(
+                self.recurring_votes.get(recurring_vote_id).is_some(),
+                "ERR_NO_RECURRING_VOTE"
+            ),
+            ProposalKind::DeployExecutor { executor_id, .. } => {
+                assert!(is_sub_account(executor_id), "ERR_INVALID_EXECUTOR")
+            }
+            ProposalKind::AddBounty { .. } => {
+                if let Some(max) = policy.max_open_bounties {
+                    assert!(self.open_bounties.len() < max, "ERR_TOO_MANY_OPEN_BOUNTIES");
+                }
+            }
             // TODO: add more verifications.
             _ => {}
         };
 
+        if let Some(max) = policy.max_active_proposals {
+            if self.active_proposals.len() >= max {
+                self.internal_release_expired_proposals(policy);
+            }
+            assert!(
+                self.active_proposals.len() < max,
+                "ERR_TOO_MANY_ACTIVE_PROPOSALS"
+            );
+        }
+
         // 2. Check permission of caller to add this type of proposal.
         assert!(
             policy
                 .can_execute_action(
-                    self.internal_user_info(),
+                    self.internal_account_info(proposer.clone()),
                     &proposal.kind,
                     &Action::AddProposal
                 )
@@ -526,54 +1755,348 @@ impl Contract {
 
         // 3. Actually add proposal to the current list of proposals.
         let id = self.last_proposal_id;
+        let initial_storage = env::storage_usage();
+        self.internal_emit_event(Event::AddProposal {
+            proposal_id: id,
+            proposer: proposer.clone(),
+            kind: proposal.kind.to_policy_label().to_string(),
+        });
+        match &proposal.kind {
+            ProposalKind::UpgradeSelf { hash }
+            | ProposalKind::UpgradeRemote { hash, .. }
+            | ProposalKind::DeployExecutor { hash, .. } => {
+                let hash = CryptoHash::from(*hash);
+                let mut references = self.blob_references.get(&hash).unwrap_or_default();
+                references.push(id);
+                self.blob_references.insert(&hash, &references);
+            }
+            ProposalKind::LoanDefault { loan_id } => {
+                self.internal_set_loan_default_proposal(*loan_id, Some(id))
+            }
+            _ => {}
+        }
+        if policy.vote_decay_after.is_some() {
+            // Absences are counted from these instead of going through the past proposals.
+            let mut kind_counts = self.proposal_kind_counts.get(&id).unwrap_or_default();
+            self.proposal_kind_counts.insert(&id, &kind_counts);
+            *kind_counts
+                .entry(proposal.kind.to_policy_label().to_string())
+                .or_insert(0) += 1;
+            self.proposal_kind_counts.insert(&(id + 1), &kind_counts);
+        }
+        // Mirror bond attached by the proposer is sent to the parent DAO.
+        let mirror_bond = if proposer == env::current_account_id() {
+            0
+        } else {
+            self.internal_mirror_bond(&proposal.kind)
+        };
+        self.internal_mirror_proposal(id, &proposer, &proposal);
+        self.internal_ping_factory(policy);
+        let mut proposal = Proposal::from(proposal);
+        proposal.proposer = proposer.clone();
         self.proposals
-            .insert(&id, &VersionedProposal::Default(proposal.into()));
+            .insert(&id, &VersionedProposal::Current(proposal));
         self.last_proposal_id += 1;
-        self.locked_amount += env::attached_deposit();
+        self.active_proposals.insert(&id);
+        // Zero for proposals with a token bond or added by the DAO without a bond.
+        self.proposal_bonds
+            .insert(&id, &std::cmp::min(deposit, policy.proposal_bond.0));
+        self.locked_amount += deposit - mirror_bond;
+        self.internal_charge_author_storage(
+            policy,
+            StorageChargeKey::Proposal(id),
+            &proposer,
+            initial_storage,
+            false,
+        );
         id
     }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Add proposal to this DAO.
+    #[payable]
+    pub fn add_proposal(&mut self, proposal: ProposalInput) -> u64 {
+        self.assert_not_blocked(&env::predecessor_account_id());
+
+        // 0. validate bond and escrowed deposits attached.
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            env::attached_deposit()
+                >= policy.proposal_bond.0
+                    + proposal.kind.proposer_escrow()
+                    + self.internal_mirror_bond(&proposal.kind),
+            "ERR_MIN_BOND"
+        );
+        self.internal_add_proposal(
+            &policy,
+            env::predecessor_account_id(),
+            proposal,
+            env::attached_deposit(),
+        )
+    }
+
+    /// Adds `SetStakingContract` proposal on behalf of `proposer`, who gets the bond back.
+    /// Only the factory that created this DAO can call it, to propose the staking contract
+    /// it deployed together with the DAO.
+    #[payable]
+    pub fn add_proposal_from_factory(
+        &mut self,
+        proposal: ProposalInput,
+        proposer: AccountId,
+    ) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            internal_get_factory_info().factory_id,
+            "ERR_NOT_ALLOWED"
+        );
+        assert!(
+            matches!(proposal.kind, ProposalKind::SetStakingContract { .. }),
+            "ERR_NOT_ALLOWED"
+        );
+        self.assert_not_blocked(&proposer);
+        let policy = self.policy.get().unwrap().to_policy();
+        assert!(
+            env::attached_deposit()
+                >= policy.proposal_bond.0 + self.internal_mirror_bond(&proposal.kind),
+            "ERR_MIN_BOND"
+        );
+        self.internal_add_proposal(&policy, proposer, proposal, env::attached_deposit())
+    }
+
+    /// Add proposal whose $NEAR spending is funded by the deposit attached on top of the bond
+    /// (and escrowed function call deposits). The funding is used if the proposal is executed,
+    /// otherwise it's returned to the proposer.
+    #[payable]
+    pub fn propose_with_deposit(&mut self, proposal: ProposalInput) -> u64 {
+        let policy = self.policy.get().unwrap().to_policy();
+        let fundable = proposal
+            .kind
+            .fundable_amount()
+            .expect("ERR_FUNDING_NOT_SUPPORTED");
+        let funding = env::attached_deposit()
+            .checked_sub(
+                policy.proposal_bond.0
+                    + proposal.kind.proposer_escrow()
+                    + self.internal_mirror_bond(&proposal.kind),
+            )
+            .expect("ERR_MIN_BOND");
+        assert!(
+            funding > 0 && funding <= fundable,
+            "ERR_INVALID_FUNDING_AMOUNT"
+        );
+        let id = self.add_proposal(proposal);
+        self.proposal_fundings.insert(&id, &funding);
+        id
+    }
+
+    /// Counts proposals in progress and open bounties with ids in given range, e.g. the ones
+    /// stored before `migrate`. Can be called by anyone, any number of times.
+    pub fn recount_active(&mut self, from_index: u64, limit: u64) {
+        let policy = self.policy.get().unwrap().to_policy();
+        for id in from_index..std::cmp::min(from_index + limit, self.last_proposal_id) {
+            let in_progress = matches!(
+                self.proposals.get(&id).map(Proposal::from),
+                Some(proposal) if proposal.status == ProposalStatus::InProgress
+            );
+            if in_progress && self.internal_is_proposal_pending(&policy, id) {
+                self.active_proposals.insert(&id);
+            } else {
+                self.active_proposals.remove(&id);
+            }
+        }
+        for id in from_index..std::cmp::min(from_index + limit, self.last_bounty_id) {
+            if self.bounties.contains_key(&id) {
+                self.open_bounties.insert(&id);
+            } else {
+                self.open_bounties.remove(&id);
+            }
+        }
+    }
+
+    /// Finalizes proposals in given id range that expired without being finalized, returning
+    /// their bonds the same way as `Finalize` does. Can be called by anyone.
+    /// Returns number of finalized proposals.
+    pub fn reclaim_expired_bonds(&mut self, from_index: u64, limit: u64) -> u64 {
+        let policy = self.policy.get().unwrap().to_policy();
+        let mut reclaimed = 0;
+        for id in from_index..std::cmp::min(from_index + limit, self.last_proposal_id) {
+            let mut proposal: Proposal = match self.proposals.get(&id) {
+                Some(proposal) => proposal.into(),
+                None => continue,
+            };
+            if proposal.status != ProposalStatus::InProgress {
+                continue;
+            }
+            let status = policy.proposal_status(
+                &proposal,
+                policy.roles.iter().map(|r| r.name.clone()).collect(),
+                self.total_delegation_amount,
+                self.internal_receiver_payouts(&proposal.kind),
+                &self.proposal_voter_counts.get(&id).unwrap_or_default(),
+                &self.internal_decayed_members(&policy, id, &proposal),
+            );
+            if status != ProposalStatus::Expired {
+                continue;
+            }
+            proposal.status = status;
+            let (token_id, amount) = self.internal_proposal_bond(&policy, id);
+            self.internal_reject_proposal(&policy, id, &proposal, true);
+            self.internal_emit_event(Event::ReclaimBond {
+                proposal_id: id,
+                proposer: proposal.proposer.clone(),
+                token_id,
+                amount: U128(amount),
+            });
+            self.active_proposals.remove(&id);
+            self.internal_publish_to_social(&policy, id, &proposal);
+            self.internal_notify_proposer(&policy, id, &proposal);
+            self.proposals
+                .insert(&id, &VersionedProposal::Current(proposal));
+            reclaimed += 1;
+        }
+        reclaimed
+    }
 
     /// Act on given proposal by id, if permissions allow.
     /// Memo is logged but not stored in the state. Can be used to leave notes or explain the action.
-    pub fn act_proposal(&mut self, id: u64, action: Action, memo: Option<String>) {
+    pub fn act_proposal(
+        &mut self,
+        id: u64,
+        action: Action,
+        memo: Option<String>,
+    ) -> ActProposalResult {
+        self.assert_no_vote_options(id, &action);
+        self.internal_act_proposal(id, env::predecessor_account_id(), action, memo)
+    }
+
+    /// Act on given proposal by id on behalf of `sender_id`, if permissions allow.
+    pub(crate) fn internal_act_proposal(
+        &mut self,
+        id: u64,
+        sender_id: AccountId,
+        action: Action,
+        memo: Option<String>,
+    ) -> ActProposalResult {
         let mut proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        let was_active = proposal.status == ProposalStatus::InProgress;
         let policy = self.policy.get().unwrap().to_policy();
         // Check permissions for the given action.
-        let (roles, allowed) =
-            policy.can_execute_action(self.internal_user_info(), &proposal.kind, &action);
+        let (roles, allowed) = policy.can_execute_action(
+            self.internal_account_info(sender_id.clone()),
+            &proposal.kind,
+            &action,
+        );
         assert!(allowed, "ERR_PERMISSION_DENIED");
-        let sender_id = env::predecessor_account_id();
+        self.internal_ping_factory(&policy);
+        let action_label = action.to_policy_label();
+        if matches!(
+            action,
+            Action::VoteApprove | Action::VoteReject | Action::VoteRemove | Action::FlagSpam
+        ) {
+            self.internal_count_vote_rate(&policy, &sender_id, &roles);
+        }
+        let mut counted_weights = HashMap::default();
+        // The outcome of an execution that returns a promise is reported from its callback.
+        let mut pending_execution = false;
         // Update proposal given action. Returns true if should be updated in storage.
         let update = match action {
             Action::AddProposal => env::panic_str("ERR_WRONG_ACTION"),
             Action::RemoveProposal => {
+                if matches!(
+                    proposal.status,
+                    ProposalStatus::InProgress | ProposalStatus::Failed
+                ) {
+                    // Same as removal as spam: bonds are forfeited, everything else is released.
+                    self.internal_reject_proposal(&policy, id, &proposal, false);
+                }
                 self.proposals.remove(&id);
+                self.proposal_translations.remove(&id);
+                self.bounty_proofs.remove(&id);
+                self.internal_refund_author_storage(StorageChargeKey::Proposal(id));
                 false
             }
-            Action::VoteApprove | Action::VoteReject | Action::VoteRemove => {
+            Action::VoteApprove | Action::VoteReject | Action::VoteRemove | Action::FlagSpam => {
+                let is_flag = matches!(action, Action::FlagSpam);
+                let vote = Vote::from(action);
                 assert!(
                     matches!(proposal.status, ProposalStatus::InProgress),
                     "ERR_PROPOSAL_NOT_READY_FOR_VOTE"
                 );
-                proposal.update_votes(
+                if matches!(vote, Vote::Approve) && policy.ragequit.is_some() {
+                    // Blocks ragequit of the voter while this proposal is active.
+                    self.internal_record_approve_vote(&policy, &sender_id, id);
+                }
+                let kind_counts = self.proposal_kind_counts.get(&id).unwrap_or_default();
+                let role_weight =
+                    if self.internal_is_vote_decayed(&policy, &sender_id, id, &kind_counts) {
+                        0
+                    } else {
+                        1
+                    };
+                if policy.vote_decay_after.is_some()
+                    && !matches!(self.member_last_votes.get(&sender_id), Some(last_vote) if last_vote >= id)
+                {
+                    self.member_last_votes.insert(&sender_id, &id);
+                }
+                counted_weights = proposal.update_votes(
                     &sender_id,
                     &roles,
-                    Vote::from(action),
+                    vote.clone(),
                     &policy,
                     self.get_user_weight(&sender_id),
+                    role_weight,
                 );
+                let mut voter_counts = self.proposal_voter_counts.get(&id).unwrap_or_default();
+                if !is_flag {
+                    for role in &roles {
+                        voter_counts.entry(role.clone()).or_insert([0; 3])
+                            [vote.clone() as usize] += 1;
+                    }
+                    self.proposal_voter_counts.insert(&id, &voter_counts);
+                }
+                let mut is_spam = false;
+                if is_flag {
+                    let mut spam_flags = self.spam_flags.get(&id).unwrap_or_default();
+                    for (role, weight) in &counted_weights {
+                        *spam_flags.entry(role.clone()).or_insert(0) += weight;
+                    }
+                    self.spam_flags.insert(&id, &spam_flags);
+                    is_spam = policy.is_spam(
+                        &proposal,
+                        &roles,
+                        self.total_delegation_amount,
+                        &spam_flags,
+                    );
+                }
                 // Updates proposal status with new votes using the policy.
-                proposal.status =
-                    policy.proposal_status(&proposal, roles, self.total_delegation_amount);
+                proposal.status = if is_spam {
+                    ProposalStatus::Removed
+                } else {
+                    policy.proposal_status(
+                        &proposal,
+                        roles,
+                        self.total_delegation_amount,
+                        self.internal_receiver_payouts(&proposal.kind),
+                        &voter_counts,
+                        &self.internal_decayed_members(&policy, id, &proposal),
+                    )
+                };
                 if proposal.status == ProposalStatus::Approved {
-                    self.internal_execute_proposal(&policy, &proposal, id);
+                    pending_execution = self.internal_execute_proposal(&policy, &proposal, id);
                     true
                 } else if proposal.status == ProposalStatus::Removed {
-                    self.internal_reject_proposal(&policy, &proposal, false);
+                    // Bonds are only forfeited for spam.
+                    self.internal_reject_proposal(&policy, id, &proposal, !is_spam);
                     self.proposals.remove(&id);
+                    self.proposal_translations.remove(&id);
+                    self.bounty_proofs.remove(&id);
+                    self.internal_refund_author_storage(StorageChargeKey::Proposal(id));
                     false
                 } else if proposal.status == ProposalStatus::Rejected {
-                    self.internal_reject_proposal(&policy, &proposal, true);
+                    self.internal_reject_proposal(&policy, id, &proposal, true);
                     true
                 } else {
                     // Still in progress or expired.
@@ -592,13 +2115,16 @@ impl Contract {
                     &proposal,
                     policy.roles.iter().map(|r| r.name.clone()).collect(),
                     self.total_delegation_amount,
+                    self.internal_receiver_payouts(&proposal.kind),
+                    &self.proposal_voter_counts.get(&id).unwrap_or_default(),
+                    &self.internal_decayed_members(&policy, id, &proposal),
                 );
                 match proposal.status {
                     ProposalStatus::Approved => {
-                        self.internal_execute_proposal(&policy, &proposal, id);
+                        pending_execution = self.internal_execute_proposal(&policy, &proposal, id);
                     }
                     ProposalStatus::Expired => {
-                        self.internal_reject_proposal(&policy, &proposal, true);
+                        self.internal_reject_proposal(&policy, id, &proposal, true);
                     }
                     _ => {
                         env::panic_str("ERR_PROPOSAL_NOT_EXPIRED_OR_FAILED");
@@ -608,19 +2134,54 @@ impl Contract {
             }
             Action::MoveToHub => false,
         };
+        self.internal_emit_event(Event::ActProposal {
+            proposal_id: id,
+            account_id: sender_id,
+            action: action_label,
+            status: proposal.status.clone(),
+        });
+        if was_active
+            && (proposal.status != ProposalStatus::InProgress || !self.proposals.contains_key(&id))
+        {
+            self.active_proposals.remove(&id);
+            if !pending_execution {
+                self.internal_publish_to_social(&policy, id, &proposal);
+                self.internal_notify_proposer(&policy, id, &proposal);
+            }
+        }
+        let result = ActProposalResult {
+            proposal_id: id,
+            counted_weights: counted_weights
+                .into_iter()
+                .map(|(role, weight)| (role, U128(weight)))
+                .collect(),
+            vote_counts: proposal
+                .vote_counts
+                .iter()
+                .map(|(role, counts)| {
+                    (
+                        role.clone(),
+                        [U128(counts[0]), U128(counts[1]), U128(counts[2])],
+                    )
+                })
+                .collect(),
+            status: proposal.status.clone(),
+        };
         if update {
             self.proposals
-                .insert(&id, &VersionedProposal::Default(proposal));
+                .insert(&id, &VersionedProposal::Current(proposal));
         }
         if let Some(memo) = memo {
             log!("Memo: {}", memo);
         }
+        result
     }
 
     /// Receiving callback after the proposal has been finalized.
     /// If successful, returns bond money to the proposal originator.
     /// If the proposal execution failed (funds didn't transfer or function call failure),
     /// move proposal to "Failed" state.
+    /// Publishes the outcome and notifies the proposer either way.
     #[private]
     pub fn on_proposal_callback(&mut self, proposal_id: u64) -> PromiseOrValue<()> {
         let mut proposal: Proposal = self
@@ -635,11 +2196,26 @@ impl Contract {
         );
         let result = match env::promise_result(0) {
             PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Successful(_) => self.internal_callback_proposal_success(&mut proposal),
-            PromiseResult::Failed => self.internal_callback_proposal_fail(&mut proposal),
+            PromiseResult::Successful(value) => {
+                if internal_is_valid_callback_result(&proposal.kind, &value) {
+                    self.internal_callback_proposal_success(proposal_id, &mut proposal)
+                } else {
+                    self.internal_callback_proposal_fail(proposal_id, &mut proposal)
+                }
+            }
+            PromiseResult::Failed => {
+                self.internal_callback_proposal_fail(proposal_id, &mut proposal)
+            }
         };
+        self.internal_emit_event(Event::ProposalCallback {
+            proposal_id,
+            status: proposal.status.clone(),
+        });
+        let policy = self.policy.get().unwrap().to_policy();
+        self.internal_publish_to_social(&policy, proposal_id, &proposal);
+        self.internal_notify_proposer(&policy, proposal_id, &proposal);
         self.proposals
-            .insert(&proposal_id, &VersionedProposal::Default(proposal.into()));
+            .insert(&proposal_id, &VersionedProposal::Current(proposal));
         result
     }
 }
@@ -0,0 +1,484 @@
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+use near_sdk::{env, near_bindgen, AccountId, Balance, Promise, PromiseOrValue, PromiseResult};
+
+use crate::events::Event;
+use crate::types::{convert_old_to_new_token, ONE_YOCTO_NEAR};
+use crate::*;
+
+mod u256 {
+    #![allow(clippy::all)]
+    uint::construct_uint! {
+        /// 256-bit unsigned integer.
+        pub struct U256(4);
+    }
+}
+
+use u256::U256;
+
+/// Returns `balance * amount / total` without overflow.
+fn pro_rata(balance: Balance, amount: Balance, total: Balance) -> Balance {
+    (U256::from(balance) * U256::from(amount) / U256::from(total)).as_u128()
+}
+
+/// Ragequit whose token payouts are in progress.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingRagequit {
+    pub account_id: AccountId,
+    /// Burned delegation.
+    pub amount: U128,
+    /// Total delegation before the ragequit.
+    pub total: U128,
+    /// $NEAR share, kept in `locked_amount` until the token payouts are done.
+    pub near_share: U128,
+}
+
+impl Contract {
+    /// Ids of given proposals that are still being voted on.
+    fn internal_active_proposals(&self, policy: &Policy, ids: Vec<u64>) -> Vec<u64> {
+        ids.into_iter()
+            .filter(|id| match self.proposals.get(id) {
+                Some(proposal) => {
+                    let proposal: Proposal = proposal.into();
+                    proposal.status == ProposalStatus::InProgress
+                        && proposal.submission_time.0 + policy.proposal_period.0
+                            >= env::block_timestamp()
+                }
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Records approve vote of the account, dropping the votes on proposals that ended.
+    pub(crate) fn internal_record_approve_vote(
+        &mut self,
+        policy: &Policy,
+        account_id: &AccountId,
+        proposal_id: u64,
+    ) {
+        let mut ids = self.internal_active_proposals(
+            policy,
+            self.approve_votes.get(account_id).unwrap_or_default(),
+        );
+        ids.push(proposal_id);
+        self.approve_votes.insert(account_id, &ids);
+    }
+
+    /// Fails if the account voted to approve a proposal that is still being voted on.
+    fn assert_no_active_approve_vote(&mut self, policy: &Policy, account_id: &AccountId) {
+        if let Some(ids) = self.approve_votes.get(account_id) {
+            assert!(
+                self.internal_active_proposals(policy, ids).is_empty(),
+                "ERR_RAGEQUIT_ACTIVE_VOTE"
+            );
+            self.approve_votes.remove(account_id);
+        }
+    }
+
+    /// Restores delegation burned by a ragequit that paid out nothing.
+    fn internal_revert_ragequit(&mut self, ragequit: &PendingRagequit) {
+        self.ragequit_pending = false;
+        let account_id = &ragequit.account_id;
+        let prev_amount = self.delegations.get(account_id).unwrap_or_default();
+        self.delegations
+            .insert(account_id, &(prev_amount + ragequit.amount.0));
+        self.total_delegation_amount += ragequit.amount.0;
+        self.locked_amount -= ragequit.near_share.0;
+        env::log_str(&format!("ERR_RAGEQUIT_PAYOUTS_FAILED:{}", account_id));
+    }
+
+    /// Counts ragequit payout in the period outflow of the spend limits, if the policy sets them.
+    /// Members can always exit, so it's not checked against the limits.
+    fn internal_count_ragequit_outflow(&mut self, token_id: &Option<AccountId>, amount: Balance) {
+        if self
+            .policy
+            .get()
+            .unwrap()
+            .to_policy()
+            .spend_limits
+            .is_some()
+        {
+            self.internal_count_outflow(token_id, amount);
+        }
+    }
+
+    /// Pays out the $NEAR share reserved by `ragequit`.
+    fn internal_ragequit_near(&mut self, ragequit: &PendingRagequit) {
+        self.ragequit_pending = false;
+        let near_share = ragequit.near_share.0;
+        if near_share > 0 {
+            self.locked_amount -= near_share;
+            self.internal_count_ragequit_outflow(&None, near_share);
+            Promise::new(ragequit.account_id.clone()).transfer(near_share);
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Burns given amount of account's delegation and pays out the pro-rata share
+    /// of the policy's ragequit tokens, measured against total delegated amount.
+    /// Shares of tokens don't include token bonds held in escrow.
+    /// Called by the staking contract, which burns the same amount of staked tokens if this
+    /// returns true once the payouts are done. Returns false and restores the delegation if
+    /// nothing could be paid out. Fails while the token payouts of another ragequit are pending.
+    pub fn ragequit(&mut self, account_id: &AccountId, amount: U128) -> PromiseOrValue<bool> {
+        let staking_id = self.staking_id.clone().expect("ERR_NO_STAKING");
+        assert_eq!(
+            env::predecessor_account_id(),
+            staking_id,
+            "ERR_INVALID_CALLER"
+        );
+        assert!(!self.ragequit_pending, "ERR_RAGEQUIT_PENDING");
+        let policy = self.policy.get().unwrap().to_policy();
+        let ragequit = policy.ragequit.clone().expect("ERR_RAGEQUIT_DISABLED");
+        let last_delegation = self
+            .delegation_timestamps
+            .get(account_id)
+            .unwrap_or_default();
+        assert!(
+            env::block_timestamp() >= last_delegation + ragequit.cooldown.0,
+            "ERR_RAGEQUIT_COOLDOWN"
+        );
+        self.assert_no_active_approve_vote(&policy, account_id);
+        let prev_amount = self.delegations.get(account_id).unwrap_or_default();
+        assert!(
+            amount.0 > 0 && prev_amount >= amount.0,
+            "ERR_RAGEQUIT_WRONG_AMOUNT"
+        );
+        let total = self.total_delegation_amount;
+        self.delegations
+            .insert(account_id, &(prev_amount - amount.0));
+        self.total_delegation_amount -= amount.0;
+        self.internal_emit_event(Event::Ragequit {
+            account_id: account_id.clone(),
+            amount,
+        });
+        let mut near_share = 0;
+        let mut tokens = vec![];
+        for token in ragequit.tokens.iter() {
+            match convert_old_to_new_token(token) {
+                None => near_share = pro_rata(self.internal_available_balance(), amount.0, total),
+                Some(token_id) => tokens.push(token_id),
+            }
+        }
+        // Reserved until the token payouts are done.
+        self.locked_amount += near_share;
+        let pending = PendingRagequit {
+            account_id: account_id.clone(),
+            amount,
+            total: U128(total),
+            near_share: U128(near_share),
+        };
+        if tokens.is_empty() {
+            self.internal_ragequit_near(&pending);
+            return PromiseOrValue::Value(true);
+        }
+        self.ragequit_pending = true;
+        let gas = self.gas_config.ragequit_callback * 2
+            + self.gas_config.ft_transfer * tokens.len() as u64;
+        tokens
+            .iter()
+            .map(|token_id| {
+                ext_fungible_token::ft_balance_of(
+                    env::current_account_id(),
+                    token_id.clone(),
+                    0,
+                    self.gas_config.ft_transfer,
+                )
+            })
+            .reduce(|promise, balance| promise.and(balance))
+            .unwrap()
+            .then(ext_self::on_ragequit_balances(
+                pending,
+                tokens,
+                env::current_account_id(),
+                0,
+                gas,
+            ))
+            .into()
+    }
+
+    /// Pays out `amount / total` share of the DAO's balance of each token.
+    /// Restores the delegation if any balance couldn't be fetched.
+    #[private]
+    pub fn on_ragequit_balances(
+        &mut self,
+        ragequit: PendingRagequit,
+        tokens: Vec<AccountId>,
+    ) -> PromiseOrValue<bool> {
+        assert_eq!(
+            env::promise_results_count(),
+            tokens.len() as u64,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        let mut shares = vec![];
+        for (index, token_id) in tokens.into_iter().enumerate() {
+            let balance: U128 = match env::promise_result(index as u64) {
+                PromiseResult::Successful(value) => {
+                    near_sdk::serde_json::from_slice(&value).expect("ERR_INVALID_BALANCE")
+                }
+                _ => {
+                    self.internal_revert_ragequit(&ragequit);
+                    return PromiseOrValue::Value(false);
+                }
+            };
+            let available = self.internal_available_token_balance(&token_id, balance.0);
+            let share = pro_rata(available, ragequit.amount.0, ragequit.total.0);
+            if share > 0 {
+                shares.push((token_id, U128(share)));
+            }
+        }
+        if shares.is_empty() {
+            self.internal_ragequit_near(&ragequit);
+            return PromiseOrValue::Value(true);
+        }
+        shares
+            .iter()
+            .map(|(token_id, share)| {
+                ext_fungible_token::ft_transfer(
+                    ragequit.account_id.clone(),
+                    *share,
+                    Some("Ragequit".to_string()),
+                    token_id.clone(),
+                    ONE_YOCTO_NEAR,
+                    self.gas_config.ft_transfer,
+                )
+            })
+            .reduce(|promise, transfer| promise.and(transfer))
+            .unwrap()
+            .then(ext_self::on_ragequit_payouts(
+                ragequit,
+                shares,
+                env::current_account_id(),
+                0,
+                self.gas_config.ragequit_callback,
+            ))
+            .into()
+    }
+
+    /// Pays out the $NEAR share once the token payouts are done.
+    /// Restores the delegation if none of the token payouts succeeded. Failed payouts of some
+    /// of the tokens are logged, their shares stay in the treasury.
+    #[private]
+    pub fn on_ragequit_payouts(
+        &mut self,
+        ragequit: PendingRagequit,
+        shares: Vec<(AccountId, U128)>,
+    ) -> bool {
+        assert_eq!(
+            env::promise_results_count(),
+            shares.len() as u64,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        let (paid, failed): (Vec<_>, Vec<_>) =
+            shares.into_iter().enumerate().partition(|(index, _)| {
+                matches!(
+                    env::promise_result(*index as u64),
+                    PromiseResult::Successful(_)
+                )
+            });
+        if paid.is_empty() {
+            self.internal_revert_ragequit(&ragequit);
+            return false;
+        }
+        for (_, (token_id, share)) in paid {
+            self.internal_count_ragequit_outflow(&Some(token_id), share.0);
+        }
+        for (_, (token_id, _)) in failed {
+            env::log_str(&format!("ERR_RAGEQUIT_PAYOUT_FAILED:{}", token_id));
+        }
+        self.internal_ragequit_near(&ragequit);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::U64;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::{testing_env, PromiseResult};
+    use near_sdk_sim::to_yocto;
+
+    use crate::delegation::REGISTER_DELEGATION_STORAGE;
+    use crate::policy::RagequitPolicy;
+    use crate::types::OLD_BASE_TOKEN;
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn setup_ragequit(context: &mut VMContextBuilder) -> Contract {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.ragequit = Some(RagequitPolicy {
+            tokens: vec![OLD_BASE_TOKEN.to_string()],
+            cooldown: U64::from(100),
+        });
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract.staking_id = Some(accounts(4));
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(REGISTER_DELEGATION_STORAGE as Balance * env::storage_byte_cost())
+            .build());
+        contract.register_delegation(&accounts(2));
+        contract.register_delegation(&accounts(3));
+        contract.delegate(&accounts(2), U128(100));
+        contract.delegate(&accounts(3), U128(300));
+        contract
+    }
+
+    #[test]
+    fn test_ragequit() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_ragequit(&mut context);
+        testing_env!(context.block_timestamp(100).build());
+        contract.ragequit(&accounts(2), U128(40));
+        assert_eq!(contract.delegation_total_supply().0, 360);
+        assert_eq!(contract.delegation_balance_of(accounts(2)).0, 60);
+        assert_eq!(pro_rata(u128::MAX, 40, 400), u128::MAX / 10);
+    }
+
+    #[test]
+    fn test_ragequit_token_payouts() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_ragequit(&mut context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.ragequit.as_mut().unwrap().tokens = vec![accounts(5).to_string()];
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract.internal_lock_tokens(&accounts(5), 100);
+        testing_env!(context.block_timestamp(100).build());
+        contract.ragequit(&accounts(2), U128(40));
+        assert_eq!(contract.delegation_balance_of(accounts(2)).0, 60);
+
+        // Token bonds held in escrow are not paid out.
+        let ragequit = || PendingRagequit {
+            account_id: accounts(2),
+            amount: U128(40),
+            total: U128(400),
+            near_share: U128(0),
+        };
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"100\"".to_vec())],
+        );
+        assert!(matches!(
+            contract.on_ragequit_balances(ragequit(), vec![accounts(5)]),
+            PromiseOrValue::Value(true)
+        ));
+
+        // Delegation is restored if nothing was paid out.
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!contract.on_ragequit_payouts(ragequit(), vec![(accounts(5), U128(30))]));
+        assert_eq!(contract.delegation_balance_of(accounts(2)).0, 100);
+        assert_eq!(contract.delegation_total_supply().0, 400);
+    }
+
+    fn setup_token_ragequit(context: &mut VMContextBuilder) -> Contract {
+        let mut contract = setup_ragequit(context);
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.ragequit.as_mut().unwrap().tokens = vec![accounts(5).to_string()];
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.block_timestamp(100).build());
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RAGEQUIT_PENDING")]
+    fn test_ragequit_pending() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_token_ragequit(&mut context);
+        contract.ragequit(&accounts(2), U128(100));
+        // Would read the same balance as the first ragequit, before its payouts.
+        contract.ragequit(&accounts(3), U128(100));
+    }
+
+    #[test]
+    fn test_ragequit_after_payouts() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_token_ragequit(&mut context);
+        contract.ragequit(&accounts(2), U128(100));
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![])],
+        );
+        let ragequit = PendingRagequit {
+            account_id: accounts(2),
+            amount: U128(100),
+            total: U128(400),
+            near_share: U128(0),
+        };
+        assert!(contract.on_ragequit_payouts(ragequit, vec![(accounts(5), U128(25))]));
+
+        testing_env!(context.predecessor_account_id(accounts(4)).build());
+        contract.ragequit(&accounts(3), U128(100));
+        assert_eq!(contract.delegation_total_supply().0, 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RAGEQUIT_ACTIVE_VOTE")]
+    fn test_ragequit_active_approve_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.ragequit = Some(RagequitPolicy {
+            tokens: vec![OLD_BASE_TOKEN.to_string()],
+            cooldown: U64::from(0),
+        });
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        contract.staking_id = Some(accounts(4));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let ids: Vec<u64> = (0..2)
+            .map(|_| {
+                contract.add_proposal(ProposalInput {
+                    description: "test".to_string(),
+                    kind: ProposalKind::Vote,
+                })
+            })
+            .collect();
+        testing_env!(context.attached_deposit(0).build());
+        contract.act_proposal(ids[0], Action::VoteApprove, None);
+        contract.act_proposal(ids[1], Action::VoteApprove, None);
+        // The last approved proposal ends, the first one is still active.
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(ids[1], Action::VoteApprove, None);
+
+        testing_env!(context
+            .predecessor_account_id(accounts(4))
+            .attached_deposit(REGISTER_DELEGATION_STORAGE as Balance * env::storage_byte_cost())
+            .build());
+        contract.register_delegation(&accounts(1));
+        contract.delegate(&accounts(1), U128(100));
+        contract.ragequit(&accounts(1), U128(40));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RAGEQUIT_COOLDOWN")]
+    fn test_ragequit_cooldown() {
+        let mut context = VMContextBuilder::new();
+        let mut contract = setup_ragequit(&mut context);
+        testing_env!(context.block_timestamp(50).build());
+        contract.ragequit(&accounts(2), U128(40));
+    }
+}
@@ -0,0 +1,82 @@
+//! Rate limits of voting actions, e.g. against accounts mass-removing proposals in large open DAOs.
+
+use crate::*;
+
+/// Period in which voting actions are counted: 1 hour.
+pub const VOTE_RATE_PERIOD: u64 = 1_000_000_000 * 60 * 60;
+
+impl Contract {
+    /// Counts a voting action of `account_id` acting with given roles.
+    /// Panics if the account already reached its limit in the current hour.
+    pub(crate) fn internal_count_vote_rate(
+        &mut self,
+        policy: &Policy,
+        account_id: &AccountId,
+        roles: &[String],
+    ) {
+        let limit = match policy.vote_rate_limit(roles) {
+            Some(limit) => limit,
+            None => return,
+        };
+        let period = env::block_timestamp() / VOTE_RATE_PERIOD;
+        let count = match self.vote_rates.get(account_id) {
+            Some((last_period, count)) if last_period == period => count,
+            _ => 0,
+        };
+        assert!(count < limit, "ERR_VOTE_RATE_LIMIT");
+        self.vote_rates.insert(account_id, &(period, count + 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn setup(context: &mut VMContextBuilder) -> (Contract, Vec<u64>) {
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.vote_rate_limits.insert("council".to_string(), 2);
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let ids = (0..3)
+            .map(|_| {
+                contract.add_proposal(ProposalInput {
+                    description: "test".to_string(),
+                    kind: ProposalKind::Vote,
+                })
+            })
+            .collect();
+        testing_env!(context.attached_deposit(0).build());
+        (contract, ids)
+    }
+
+    #[test]
+    fn test_vote_rate_limit() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, ids) = setup(&mut context);
+        contract.act_proposal(ids[0], Action::VoteApprove, None);
+        contract.act_proposal(ids[1], Action::VoteApprove, None);
+        testing_env!(context.block_timestamp(VOTE_RATE_PERIOD).build());
+        contract.act_proposal(ids[2], Action::VoteApprove, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_VOTE_RATE_LIMIT")]
+    fn test_vote_rate_limit_exceeded() {
+        let mut context = VMContextBuilder::new();
+        let (mut contract, ids) = setup(&mut context);
+        for id in ids {
+            contract.act_proposal(id, Action::VoteReject, None);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Receipts of finalized proposals for their proposers, e.g. contracts that add proposals
+//! and react to their outcome.
+
+use crate::*;
+
+impl Contract {
+    /// Emits `proposal_finalized` with the outcome of given proposal and, if the policy's
+    /// `notify_proposer` is set, calls `on_proposal_finalized` on the proposer.
+    /// The call fails without effect if the proposer doesn't implement it.
+    /// For approved proposals executed with a promise it's called from `on_proposal_callback`,
+    /// once the outcome is known.
+    pub(crate) fn internal_notify_proposer(
+        &mut self,
+        policy: &Policy,
+        proposal_id: u64,
+        proposal: &Proposal,
+    ) {
+        self.internal_emit_event(Event::ProposalFinalized {
+            proposal_id,
+            proposer: proposal.proposer.clone(),
+            status: proposal.status.clone(),
+        });
+        // The DAO itself adds recurring votes.
+        if !policy.notify_proposer || proposal.proposer == env::current_account_id() {
+            return;
+        }
+        ext_proposer::on_proposal_finalized(
+            proposal_id,
+            proposal.status.clone(),
+            proposal.proposer.clone(),
+            0,
+            self.gas_config.proposer_notify,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    #[test]
+    fn test_notify_proposer() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.notify_proposer = true;
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+        contract.act_proposal(id, Action::VoteReject, None);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("\"event\":\"proposal_finalized\"")
+                && log.contains("\"status\":\"Rejected\"")));
+        let notified = get_created_receipts().into_iter().any(|receipt| {
+            receipt.receiver_id == accounts(1)
+                && matches!(
+                    &receipt.actions[0],
+                    VmAction::FunctionCall { function_name, .. }
+                        if function_name == "on_proposal_finalized"
+                )
+        });
+        assert!(notified);
+    }
+
+    #[test]
+    fn test_notify_proposer_after_execution() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.notify_proposer = true;
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::new(),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        // Not reported before the transfer is done.
+        assert!(!get_logs()
+            .iter()
+            .any(|log| log.contains("\"event\":\"proposal_finalized\"")));
+
+        testing_env!(
+            context.attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_proposal_callback(id);
+        assert!(get_logs()
+            .iter()
+            .any(|log| log.contains("\"event\":\"proposal_finalized\"")
+                && log.contains("\"status\":\"Failed\"")));
+        let notified = get_created_receipts().into_iter().any(|receipt| {
+            receipt.receiver_id == accounts(1)
+                && matches!(
+                    &receipt.actions[0],
+                    VmAction::FunctionCall { function_name, args, .. }
+                        if function_name == "on_proposal_finalized"
+                            && String::from_utf8_lossy(args).contains("Failed")
+                )
+        });
+        assert!(notified);
+    }
+}
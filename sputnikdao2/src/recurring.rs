@@ -0,0 +1,215 @@
+//! Recurring signal votes, e.g. quarterly confidence votes, created on schedule by anyone.
+
+use near_sdk::json_types::{U128, U64};
+
+use crate::*;
+
+/// Template of a recurring `Vote` proposal, added by an `AddRecurringVote` proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecurringVote {
+    pub description: String,
+    /// Time between two instances.
+    pub interval: U64,
+    /// Time from which the next instance can be created.
+    pub next_time: U64,
+    /// $NEAR left for the bonds of the next instances, locked in the treasury.
+    pub pool: U128,
+    /// Number of instances created so far.
+    pub instances: u64,
+}
+
+impl Contract {
+    /// Adds recurring vote with given id, locking its pool.
+    pub(crate) fn internal_add_recurring_vote(
+        &mut self,
+        id: u64,
+        description: &str,
+        interval: U64,
+        first_time: U64,
+        pool: U128,
+    ) {
+        assert!(
+            self.internal_available_balance() >= pool.0,
+            "ERR_NOT_ENOUGH_BALANCE_FOR_POOL"
+        );
+        self.locked_amount += pool.0;
+        self.recurring_votes.insert(
+            &id,
+            &RecurringVote {
+                description: description.to_string(),
+                interval,
+                next_time: first_time,
+                pool,
+                instances: 0,
+            },
+        );
+    }
+
+    /// Removes given recurring vote, unlocking the rest of its pool.
+    /// Does nothing if it was removed since the proposal was added.
+    pub(crate) fn internal_remove_recurring_vote(&mut self, id: u64) {
+        if let Some(recurring) = self.recurring_votes.remove(&id) {
+            self.locked_amount -= recurring.pool.0;
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Creates the next instance of given recurring vote if it's due, with the bond paid from
+    /// its pool. The DAO itself is the proposer, so it must be allowed to add `vote` proposals.
+    /// Returns id of the new proposal.
+    pub fn trigger_recurring_vote(&mut self, id: u64) -> u64 {
+        let mut recurring = self
+            .recurring_votes
+            .get(&id)
+            .expect("ERR_NO_RECURRING_VOTE");
+        let now = env::block_timestamp();
+        assert!(now >= recurring.next_time.0, "ERR_RECURRING_VOTE_NOT_DUE");
+        let policy = self.policy.get().unwrap().to_policy();
+        let bond = policy.proposal_bond.0;
+        assert!(recurring.pool.0 >= bond, "ERR_RECURRING_POOL_EMPTY");
+        recurring.pool.0 -= bond;
+        recurring.instances += 1;
+        // Missed periods are skipped.
+        let intervals = (now - recurring.next_time.0) / recurring.interval.0 + 1;
+        recurring.next_time.0 = recurring
+            .next_time
+            .0
+            .saturating_add(intervals.saturating_mul(recurring.interval.0));
+        // The bond is locked again for the proposal.
+        self.locked_amount -= bond;
+        let description = format!("{} #{}", recurring.description, recurring.instances);
+        self.recurring_votes.insert(&id, &recurring);
+        self.internal_add_proposal(
+            &policy,
+            env::current_account_id(),
+            ProposalInput {
+                description,
+                kind: ProposalKind::Vote,
+            },
+            bond,
+        )
+    }
+
+    /// Recurring vote added by given proposal, if any.
+    pub fn get_recurring_vote(&self, id: u64) -> Option<RecurringVote> {
+        self.recurring_votes.get(&id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    #[test]
+    fn test_recurring_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::AddRecurringVote {
+                description: "Quarterly confidence vote".to_string(),
+                interval: U64(100),
+                first_time: U64(50),
+                pool: U128(to_yocto("2")),
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(contract.locked_amount, to_yocto("2"));
+
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .block_timestamp(60)
+            .build());
+        let instance = contract.trigger_recurring_vote(id);
+        let proposal = contract.get_proposal(instance).proposal;
+        assert_eq!(proposal.description, "Quarterly confidence vote #1");
+        assert_eq!(proposal.proposer, accounts(0));
+        let recurring = contract.get_recurring_vote(id).unwrap();
+        assert_eq!(recurring.next_time, U64(150));
+        assert_eq!(recurring.pool, U128(to_yocto("1")));
+        assert_eq!(contract.locked_amount, to_yocto("2"));
+    }
+
+    #[test]
+    fn test_recurring_vote_skip_missed() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.internal_add_recurring_vote(0, "test", U64(1), U64(50), U128(to_yocto("2")));
+        testing_env!(context.block_timestamp(1_000_000_000_050).build());
+        contract.trigger_recurring_vote(0);
+        assert_eq!(
+            contract.get_recurring_vote(0).unwrap().next_time,
+            U64(1_000_000_000_051)
+        );
+    }
+
+    #[test]
+    fn test_remove_recurring_vote() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.internal_add_recurring_vote(0, "test", U64(100), U64(50), U128(to_yocto("2")));
+        assert_eq!(contract.locked_amount, to_yocto("2"));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::RemoveRecurringVote {
+                recurring_vote_id: 0,
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert!(contract.get_recurring_vote(0).is_none());
+        assert_eq!(contract.locked_amount, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_RECURRING_VOTE_NOT_DUE")]
+    fn test_recurring_vote_not_due() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.internal_add_recurring_vote(0, "test", U64(100), U64(50), U128(to_yocto("1")));
+        contract.trigger_recurring_vote(0);
+    }
+}
@@ -0,0 +1,152 @@
+//! Publishing of proposal outcomes to a SocialDB contract.
+
+use near_sdk::serde_json::json;
+
+use crate::*;
+
+/// SocialDB key under the DAO account where proposals are published.
+pub const SOCIAL_PROPOSALS_KEY: &str = "sputnikdao";
+
+impl Contract {
+    /// Publishes summary and outcome of given proposal to the policy's `social_db_id`, if set.
+    /// The DAO must have storage deposited in the SocialDB contract.
+    /// For approved proposals executed with a promise it's called from `on_proposal_callback`.
+    pub(crate) fn internal_publish_to_social(
+        &self,
+        policy: &Policy,
+        proposal_id: u64,
+        proposal: &Proposal,
+    ) {
+        let social_db_id = match &policy.social_db_id {
+            Some(social_db_id) => social_db_id,
+            None => return,
+        };
+        let value = json!({
+            "proposer": proposal.proposer,
+            "description": proposal.description,
+            "kind": proposal.kind.to_policy_label(),
+            "summary": proposal.kind.summary(),
+            "status": proposal.status,
+            "votes": proposal.votes,
+        });
+        let data = json!({
+            env::current_account_id().to_string(): {
+                SOCIAL_PROPOSALS_KEY: {
+                    "proposal": {
+                        proposal_id.to_string(): value.to_string(),
+                    },
+                },
+            },
+        });
+        Promise::new(social_db_id.clone()).function_call(
+            "set".to_string(),
+            json!({ "data": data }).to_string().into_bytes(),
+            0,
+            self.gas_config.social_set,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    #[test]
+    fn test_publish_to_social() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.social_db_id = Some(accounts(4));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Vote,
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        let receipt = get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(4))
+            .unwrap();
+        match &receipt.actions[0] {
+            VmAction::FunctionCall {
+                function_name,
+                args,
+                ..
+            } => {
+                assert_eq!(function_name, "set");
+                let args: near_sdk::serde_json::Value =
+                    near_sdk::serde_json::from_slice(args).unwrap();
+                let value = args["data"][env::current_account_id().as_str()][SOCIAL_PROPOSALS_KEY]
+                    ["proposal"]["0"]
+                    .as_str()
+                    .unwrap();
+                assert!(value.contains("\"status\":\"Approved\""));
+            }
+            _ => panic!("Unexpected action"),
+        }
+    }
+
+    #[test]
+    fn test_publish_to_social_after_execution() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.social_db_id = Some(accounts(4));
+        contract.policy.set(&VersionedPolicy::Current(policy));
+
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::Transfer {
+                token_id: String::new(),
+                receiver_id: accounts(2),
+                amount: U128(to_yocto("1")),
+                msg: None,
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        // Not published before the transfer is done.
+        assert!(!get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+
+        testing_env!(
+            context.attached_deposit(0).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        contract.on_proposal_callback(id);
+        let receipt = get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(4))
+            .unwrap();
+        match &receipt.actions[0] {
+            VmAction::FunctionCall { args, .. } => {
+                assert!(String::from_utf8_lossy(args).contains("Failed"));
+            }
+            _ => panic!("Unexpected action"),
+        }
+    }
+}
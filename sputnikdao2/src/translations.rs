@@ -0,0 +1,193 @@
+//! Translations of proposal and bounty descriptions stored on-chain.
+
+use std::collections::HashMap;
+
+use crate::author_storage::StorageChargeKey;
+use crate::*;
+
+/// Maximum total length of the locales and translated descriptions.
+pub const MAX_TRANSLATIONS_LEN: usize = 8192;
+
+/// Descriptions in other locales than the primary one of the description itself.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Translations {
+    /// Locale of the proposal or bounty `description`, e.g. `en`.
+    pub primary_locale: String,
+    /// Translated descriptions by locale.
+    pub descriptions: HashMap<String, String>,
+}
+
+impl Translations {
+    fn assert_valid(&self) {
+        assert!(
+            !self.primary_locale.is_empty()
+                && !self.descriptions.contains_key(&self.primary_locale)
+                && self
+                    .descriptions
+                    .iter()
+                    .all(|(locale, description)| !locale.is_empty() && !description.is_empty()),
+            "ERR_INVALID_TRANSLATIONS"
+        );
+        assert!(
+            self.len() <= MAX_TRANSLATIONS_LEN,
+            "ERR_TRANSLATIONS_TOO_LONG"
+        );
+    }
+
+    fn len(&self) -> usize {
+        self.primary_locale.len()
+            + self
+                .descriptions
+                .iter()
+                .map(|(locale, description)| locale.len() + description.len())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Adds proposal like `add_proposal`, with translations of its description.
+    /// Translations of an `AddBounty` proposal are copied to the bounty.
+    /// Storage of the translations is charged to the caller's storage balance, see
+    /// `author_storage_deposit`.
+    #[payable]
+    pub fn add_localized_proposal(
+        &mut self,
+        proposal: ProposalInput,
+        translations: Translations,
+    ) -> u64 {
+        translations.assert_valid();
+        let id = self.add_proposal(proposal);
+        let initial_storage = env::storage_usage();
+        self.proposal_translations.insert(&id, &translations);
+        self.internal_charge_storage(
+            StorageChargeKey::Proposal(id),
+            &env::predecessor_account_id(),
+            initial_storage,
+            false,
+        );
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::json_types::{U128, U64};
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Bounty, Config, OLD_BASE_TOKEN};
+
+    use super::*;
+
+    fn translations() -> Translations {
+        Translations {
+            primary_locale: "en".to_string(),
+            descriptions: vec![("es".to_string(), "prueba".to_string())]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_localized_bounty() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.author_storage_deposit(None);
+        let id = contract.add_localized_proposal(
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::AddBounty {
+                    bounty: Bounty {
+                        description: "test".to_string(),
+                        token: String::from(OLD_BASE_TOKEN),
+                        amount: U128(to_yocto("10")),
+                        times: 1,
+                        max_deadline: U64::from(1_000),
+                    },
+                },
+            },
+            translations(),
+        );
+        assert_eq!(contract.get_proposal(id).translations, Some(translations()));
+        // Charged even though the policy doesn't charge authors for proposals.
+        let balance = contract.storage_balance_of_author(accounts(1)).0;
+        assert!(balance < to_yocto("1"));
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(contract.get_bounty(0).translations, Some(translations()));
+        assert!(contract.storage_balance_of_author(accounts(1)).0 < balance);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_AUTHOR_STORAGE")]
+    fn test_localized_proposal_without_storage_balance() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_localized_proposal(
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Vote,
+            },
+            translations(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TRANSLATIONS_TOO_LONG")]
+    fn test_translations_too_long() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut translations = translations();
+        translations
+            .descriptions
+            .insert("de".to_string(), "a".repeat(MAX_TRANSLATIONS_LEN));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.author_storage_deposit(None);
+        contract.add_localized_proposal(
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Vote,
+            },
+            translations,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_TRANSLATIONS")]
+    fn test_primary_locale_translated() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let mut translations = translations();
+        translations.primary_locale = "es".to_string();
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.add_localized_proposal(
+            ProposalInput {
+                description: "test".to_string(),
+                kind: ProposalKind::Vote,
+            },
+            translations,
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Chunked execution of `MigrateTreasury` proposals, sweeping the treasury to a recovery DAO.
+
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+
+use crate::types::ONE_YOCTO_NEAR;
+use crate::*;
+
+/// Number of tokens and NFTs swept per call.
+pub const TOKENS_PER_CHUNK: usize = 3;
+
+impl Contract {
+    /// Sweeps chunk of tokens, followed by NFTs, starting at `from_index` to `target_dao`,
+    /// and $NEAR not locked for bonds or storage with the first chunk.
+    /// Records the index of the next chunk if some tokens or NFTs are left.
+    pub(crate) fn internal_migrate_treasury_chunk(
+        &mut self,
+        proposal_id: u64,
+        target_dao: &AccountId,
+        tokens: &[AccountId],
+        nfts: &[(AccountId, String)],
+        from_index: usize,
+    ) {
+        if from_index == 0 {
+            let amount = self.internal_available_balance();
+            if amount > 0 {
+                Promise::new(target_dao.clone()).transfer(amount);
+            }
+        }
+        let total = tokens.len() + nfts.len();
+        let to_index = std::cmp::min(from_index + TOKENS_PER_CHUNK, total);
+        for index in from_index..to_index {
+            if let Some(token_id) = tokens.get(index) {
+                ext_fungible_token::ft_balance_of(
+                    env::current_account_id(),
+                    token_id.clone(),
+                    0,
+                    self.gas_config.ft_transfer,
+                )
+                .then(ext_self::on_migrate_treasury_balance(
+                    token_id.clone(),
+                    target_dao.clone(),
+                    env::current_account_id(),
+                    0,
+                    self.gas_config.treasury_callback,
+                ));
+            } else {
+                let (contract_id, token_id) = &nfts[index - tokens.len()];
+                ext_non_fungible_token::nft_transfer(
+                    target_dao.clone(),
+                    token_id.clone(),
+                    None,
+                    Some("Treasury migration".to_string()),
+                    contract_id.clone(),
+                    ONE_YOCTO_NEAR,
+                    self.gas_config.nft_transfer,
+                );
+            }
+        }
+        if to_index < total {
+            self.treasury_migrations
+                .insert(&proposal_id, &(to_index as u64));
+        } else {
+            self.treasury_migrations.remove(&proposal_id);
+        }
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Sweeps the next chunk of tokens and NFTs of an approved `MigrateTreasury` proposal.
+    pub fn continue_migrate_treasury(&mut self, id: u64) {
+        let from_index = self
+            .treasury_migrations
+            .get(&id)
+            .expect("ERR_NO_PENDING_MIGRATION");
+        let proposal: Proposal = self.proposals.get(&id).expect("ERR_NO_PROPOSAL").into();
+        match &proposal.kind {
+            ProposalKind::MigrateTreasury {
+                target_dao,
+                tokens,
+                nfts,
+            } => self.internal_migrate_treasury_chunk(
+                id,
+                target_dao,
+                tokens,
+                nfts,
+                from_index as usize,
+            ),
+            _ => env::panic_str("ERR_WRONG_KIND"),
+        }
+    }
+
+    /// Index of the next token or NFT to sweep for given `MigrateTreasury` proposal, counting
+    /// NFTs after the tokens, if any are left.
+    pub fn get_pending_migration_index(&self, id: u64) -> Option<u64> {
+        self.treasury_migrations.get(&id)
+    }
+
+    /// Transfers the DAO's balance of the token to the target DAO, except bonds held in escrow.
+    #[private]
+    pub fn on_migrate_treasury_balance(&mut self, token_id: AccountId, target_dao: AccountId) {
+        assert_eq!(
+            env::promise_results_count(),
+            1,
+            "ERR_UNEXPECTED_CALLBACK_PROMISES"
+        );
+        if let PromiseResult::Successful(value) = env::promise_result(0) {
+            let balance: U128 =
+                near_sdk::serde_json::from_slice(&value).expect("ERR_INVALID_BALANCE");
+            let amount = self.internal_available_token_balance(&token_id, balance.0);
+            if amount > 0 {
+                ext_fungible_token::ft_transfer(
+                    target_dao,
+                    U128(amount),
+                    Some("Treasury migration".to_string()),
+                    token_id,
+                    ONE_YOCTO_NEAR,
+                    self.gas_config.ft_transfer,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::mock::VmAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::proposals::ProposalStatus;
+    use crate::{Action, Config};
+
+    use super::*;
+
+    #[test]
+    fn test_migrate_treasury_in_chunks() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .account_balance(to_yocto("100"))
+            .build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        let tokens: Vec<AccountId> = (0..TOKENS_PER_CHUNK)
+            .map(|i| format!("token{}.near", i).parse().unwrap())
+            .collect();
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::MigrateTreasury {
+                target_dao: accounts(3),
+                tokens,
+                nfts: vec![(accounts(4), "1".to_string())],
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_pending_migration_index(id),
+            Some(TOKENS_PER_CHUNK as u64)
+        );
+        testing_env!(context.attached_deposit(0).build());
+        contract.continue_migrate_treasury(id);
+        assert_eq!(contract.get_pending_migration_index(id), None);
+        let receipt = get_created_receipts()
+            .into_iter()
+            .find(|receipt| receipt.receiver_id == accounts(4))
+            .unwrap();
+        match &receipt.actions[0] {
+            VmAction::FunctionCall {
+                function_name,
+                deposit,
+                ..
+            } => {
+                assert_eq!(function_name, "nft_transfer");
+                assert_eq!(*deposit, ONE_YOCTO_NEAR);
+            }
+            _ => panic!("expected function call"),
+        }
+    }
+
+    #[test]
+    fn test_migrate_treasury_keeps_escrow() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        contract.internal_lock_tokens(&accounts(4), 30);
+        testing_env!(
+            context.predecessor_account_id(accounts(0)).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(b"\"100\"".to_vec())],
+        );
+        contract.on_migrate_treasury_balance(accounts(4), accounts(3));
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        match &receipts[0].actions[0] {
+            VmAction::FunctionCall { args, .. } => {
+                let args: near_sdk::serde_json::Value =
+                    near_sdk::serde_json::from_slice(args).unwrap();
+                assert_eq!(args["receiver_id"], accounts(3).to_string());
+                assert_eq!(args["amount"], "70");
+            }
+            _ => panic!("expected function call"),
+        }
+    }
+
+    #[test]
+    fn test_migrate_treasury_min_threshold() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_proposal(ProposalInput {
+            description: "test".to_string(),
+            kind: ProposalKind::MigrateTreasury {
+                target_dao: accounts(4),
+                tokens: vec![],
+                nfts: vec![],
+            },
+        });
+        contract.act_proposal(id, Action::VoteApprove, None);
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::InProgress
+        );
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.act_proposal(id, Action::VoteApprove, None);
+        assert_eq!(
+            contract.get_proposal(id).proposal.status,
+            ProposalStatus::Approved
+        );
+    }
+}
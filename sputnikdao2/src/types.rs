@@ -17,6 +17,138 @@ pub const ONE_YOCTO_NEAR: Balance = 1;
 /// Gas for single ft_transfer call.
 pub const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
 
+/// Gas for ragequit callback, which does a single ft_transfer.
+pub const GAS_FOR_RAGEQUIT_CALLBACK: Gas = Gas(20_000_000_000_000);
+
+/// Gas for single nft_token view call.
+pub const GAS_FOR_NFT_TOKEN: Gas = Gas(10_000_000_000_000);
+
+/// Gas for the activity ping to the factory.
+pub const GAS_FOR_FACTORY_PING: Gas = Gas(5_000_000_000_000);
+
+/// Minimum time between two activity pings to the factory: 1 day.
+pub const FACTORY_PING_INTERVAL: u64 = 1_000_000_000 * 60 * 60 * 24;
+
+/// Gas for adding the advisory proposal to the parent DAO.
+pub const GAS_FOR_MIRROR_PROPOSAL: Gas = Gas(20_000_000_000_000);
+
+/// Gas for fetching the advisory proposal from the parent DAO.
+pub const GAS_FOR_MIRROR_STATUS: Gas = Gas(10_000_000_000_000);
+
+/// Gas for publishing a proposal to the SocialDB contract.
+pub const GAS_FOR_SOCIAL_SET: Gas = Gas(10_000_000_000_000);
+
+/// Gas for single nft_transfer call.
+pub const GAS_FOR_NFT_TRANSFER: Gas = Gas(20_000_000_000_000);
+
+/// Gas for treasury migration callback, which does a single ft_transfer.
+pub const GAS_FOR_TREASURY_CALLBACK: Gas = Gas(20_000_000_000_000);
+
+/// Gas for the callbacks recording the advisory proposal.
+pub const GAS_FOR_MIRROR_CALLBACK: Gas = Gas(10_000_000_000_000);
+
+/// Gas for notifying the proposer of a finalized proposal.
+pub const GAS_FOR_PROPOSER_NOTIFY: Gas = Gas(10_000_000_000_000);
+
+/// Gas for the executor to forward a single call, on top of the call's own gas.
+pub const GAS_FOR_PROXY_CALL: Gas = Gas(10_000_000_000_000);
+
+/// Gas for initializing a newly deployed executor.
+pub const GAS_FOR_EXECUTOR_INIT: Gas = Gas(5_000_000_000_000);
+
+/// Lower bound of each configurable gas amount.
+pub const MIN_CONFIG_GAS: Gas = Gas(2_000_000_000_000);
+
+/// Upper bound of each configurable gas amount.
+pub const MAX_CONFIG_GAS: Gas = Gas(100_000_000_000_000);
+
+/// Gas attached to cross-contract calls made by the DAO.
+/// Defaults to the `GAS_FOR_*` constants, can be raised with `SetGasConfig` if calls time out.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct GasConfig {
+    /// Single `ft_transfer` or `ft_transfer_call` payout.
+    pub ft_transfer: Gas,
+    /// Callback after executing a proposal.
+    pub proposal_callback: Gas,
+    /// Ragequit callback.
+    pub ragequit_callback: Gas,
+    /// `nft_token` view of the avatar NFT.
+    pub nft_token: Gas,
+    /// Activity ping to the factory.
+    pub factory_ping: Gas,
+    /// Adding the advisory proposal to the parent DAO.
+    pub mirror_proposal: Gas,
+    /// Fetching the advisory proposal from the parent DAO.
+    pub mirror_status: Gas,
+    /// Callbacks recording the advisory proposal.
+    pub mirror_callback: Gas,
+    /// Publishing a proposal to the SocialDB contract.
+    pub social_set: Gas,
+    /// `on_proposal_finalized` call notifying the proposer.
+    pub proposer_notify: Gas,
+    /// Single `nft_transfer` of a treasury migration.
+    pub nft_transfer: Gas,
+    /// Treasury migration callback.
+    pub treasury_callback: Gas,
+    /// Forwarding a single call by an executor, on top of the call's own gas.
+    pub proxy_call: Gas,
+    /// Initializing a newly deployed executor.
+    pub executor_init: Gas,
+}
+
+impl Default for GasConfig {
+    fn default() -> Self {
+        Self {
+            ft_transfer: GAS_FOR_FT_TRANSFER,
+            proposal_callback: GAS_FOR_FT_TRANSFER,
+            ragequit_callback: GAS_FOR_RAGEQUIT_CALLBACK,
+            nft_token: GAS_FOR_NFT_TOKEN,
+            factory_ping: GAS_FOR_FACTORY_PING,
+            mirror_proposal: GAS_FOR_MIRROR_PROPOSAL,
+            mirror_status: GAS_FOR_MIRROR_STATUS,
+            mirror_callback: GAS_FOR_MIRROR_CALLBACK,
+            social_set: GAS_FOR_SOCIAL_SET,
+            proposer_notify: GAS_FOR_PROPOSER_NOTIFY,
+            nft_transfer: GAS_FOR_NFT_TRANSFER,
+            treasury_callback: GAS_FOR_TREASURY_CALLBACK,
+            proxy_call: GAS_FOR_PROXY_CALL,
+            executor_init: GAS_FOR_EXECUTOR_INIT,
+        }
+    }
+}
+
+impl GasConfig {
+    /// Panics if any amount is outside of `MIN_CONFIG_GAS..=MAX_CONFIG_GAS`.
+    pub fn assert_valid(&self) {
+        for gas in [
+            self.ft_transfer,
+            self.proposal_callback,
+            self.ragequit_callback,
+            self.nft_token,
+            self.factory_ping,
+            self.mirror_proposal,
+            self.mirror_status,
+            self.mirror_callback,
+            self.social_set,
+            self.proposer_notify,
+            self.nft_transfer,
+            self.treasury_callback,
+            self.proxy_call,
+            self.executor_init,
+        ] {
+            assert!(
+                gas >= MIN_CONFIG_GAS && gas <= MAX_CONFIG_GAS,
+                "ERR_INVALID_GAS_CONFIG"
+            );
+        }
+    }
+}
+
+/// Key in the config metadata JSON object that holds the verified avatar NFT.
+pub const AVATAR_NFT_METADATA_KEY: &str = "avatar_nft";
+
 /// Configuration of the DAO.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
 #[serde(crate = "near_sdk::serde")]
@@ -30,6 +162,21 @@ pub struct Config {
     pub metadata: Base64VecU8,
 }
 
+impl Config {
+    /// Returns config metadata as JSON object. Empty metadata is an empty object.
+    pub fn metadata_object(
+        &self,
+    ) -> Option<near_sdk::serde_json::Map<String, near_sdk::serde_json::Value>> {
+        if self.metadata.0.is_empty() {
+            return Some(Default::default());
+        }
+        match near_sdk::serde_json::from_slice(&self.metadata.0) {
+            Ok(near_sdk::serde_json::Value::Object(map)) => Some(map),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 impl Config {
     pub fn test_config() -> Self {
@@ -53,13 +200,17 @@ pub enum Action {
     VoteApprove,
     /// Vote to reject given proposal or bounty.
     VoteReject,
-    /// Vote to remove given proposal or bounty (because it's spam).
+    /// Vote to remove given proposal or bounty, e.g. because it's invalid or a duplicate.
+    /// Bonds are returned.
     VoteRemove,
     /// Finalize proposal, called when it's expired to return the funds
     /// (or in the future can be used for early proposal closure).
     Finalize,
     /// Move a proposal to the hub to shift into another DAO.
     MoveToHub,
+    /// Flag given proposal as spam. Tallied separately from other votes, against the policy's
+    /// `spam_threshold`. Spam is removed with bonds forfeited.
+    FlagSpam,
 }
 
 impl Action {
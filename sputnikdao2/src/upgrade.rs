@@ -1,8 +1,14 @@
 //! Logic to upgrade Sputnik contracts.
 
+use std::collections::{HashMap, HashSet};
+
+use near_sdk::json_types::{Base64VecU8, U64};
 use near_sdk::serde_json::json;
 use near_sdk::Gas;
 
+use crate::policy::WeightKind;
+use crate::proposals::{PolicyParameters, Vote};
+use crate::types::OldAccountId;
 use crate::*;
 
 const FACTORY_KEY: &[u8; 7] = b"FACTORY";
@@ -16,6 +22,305 @@ pub const GAS_FOR_UPGRADE_SELF_DEPLOY: Gas = Gas(30_000_000_000_000);
 
 pub const GAS_FOR_UPGRADE_REMOTE_DEPLOY: Gas = Gas(10_000_000_000_000);
 
+/// State of the contract as of the previous release.
+/// Only used to deserialize the state in `migrate`, keep in sync with the deployed version.
+#[derive(BorshDeserialize)]
+pub(crate) struct OldContract {
+    pub config: LazyOption<Config>,
+    pub policy: LazyOption<OldVersionedPolicy>,
+    pub locked_amount: Balance,
+    pub staking_id: Option<AccountId>,
+    pub total_delegation_amount: Balance,
+    pub delegations: LookupMap<AccountId, Balance>,
+    pub last_proposal_id: u64,
+    pub proposals: LookupMap<u64, VersionedProposal>,
+    pub last_bounty_id: u64,
+    pub bounties: LookupMap<u64, VersionedBounty>,
+    pub bounty_claimers: LookupMap<AccountId, Vec<BountyClaim>>,
+    pub bounty_claims_count: LookupMap<u64, u32>,
+    pub blobs: LookupMap<CryptoHash, AccountId>,
+}
+
+/// Vote policy as of the previous release.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct OldVotePolicy {
+    pub weight_kind: WeightKind,
+    pub quorum: U128,
+    pub threshold: WeightOrRatio,
+}
+
+impl From<OldVotePolicy> for VotePolicy {
+    fn from(old: OldVotePolicy) -> Self {
+        VotePolicy {
+            weight_kind: old.weight_kind,
+            quorum: old.quorum,
+            threshold: old.threshold,
+            min_voters: 0,
+        }
+    }
+}
+
+/// Role as of the previous release.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct OldRolePermission {
+    pub name: String,
+    pub kind: RoleKind,
+    pub permissions: HashSet<String>,
+    pub vote_policy: HashMap<String, OldVotePolicy>,
+}
+
+impl From<OldRolePermission> for RolePermission {
+    fn from(old: OldRolePermission) -> Self {
+        RolePermission {
+            name: old.name,
+            kind: old.kind,
+            permissions: old.permissions,
+            vote_policy: old
+                .vote_policy
+                .into_iter()
+                .map(|(label, vote_policy)| (label, vote_policy.into()))
+                .collect(),
+        }
+    }
+}
+
+/// Policy as of the previous release.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct OldPolicy {
+    pub roles: Vec<OldRolePermission>,
+    pub default_vote_policy: OldVotePolicy,
+    pub proposal_bond: U128,
+    pub proposal_period: U64,
+    pub bounty_bond: U128,
+    pub bounty_forgiveness_period: U64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) enum OldVersionedPolicy {
+    Default(Vec<AccountId>),
+    Current(OldPolicy),
+}
+
+impl From<OldVersionedPolicy> for VersionedPolicy {
+    fn from(old: OldVersionedPolicy) -> Self {
+        match old {
+            OldVersionedPolicy::Default(accounts) => VersionedPolicy::Default(accounts).upgrade(),
+            OldVersionedPolicy::Current(policy) => VersionedPolicy::Current(Policy {
+                roles: policy.roles.into_iter().map(Into::into).collect(),
+                default_vote_policy: policy.default_vote_policy.into(),
+                proposal_bond: policy.proposal_bond,
+                proposal_period: policy.proposal_period,
+                bounty_bond: policy.bounty_bond,
+                bounty_forgiveness_period: policy.bounty_forgiveness_period,
+                allow_self_transfers: false,
+                trusted_transfer_rules: vec![],
+                bounty_token_bonds: Default::default(),
+                ragequit: None,
+                spend_limits: None,
+                vote_decay_after: None,
+                max_active_proposals: None,
+                max_open_bounties: None,
+                social_db_id: None,
+                proposal_token_bonds: Default::default(),
+                tally_oracle_role: None,
+                offchain_tally_kinds: vec![],
+                charge_author_storage: false,
+                spam_threshold: None,
+                notify_proposer: false,
+                transfer_tiers: vec![],
+                vote_rate_limits: Default::default(),
+                sponsor_role: None,
+                executors: Default::default(),
+            }),
+        }
+    }
+}
+
+/// Function call arguments as of the previous release.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub(crate) struct OldActionCall {
+    pub method_name: String,
+    pub args: Base64VecU8,
+    pub deposit: U128,
+    pub gas: U64,
+}
+
+impl From<OldActionCall> for ActionCall {
+    fn from(old: OldActionCall) -> Self {
+        ActionCall {
+            method_name: old.method_name,
+            args: old.args,
+            deposit: old.deposit,
+            gas: old.gas,
+            funding_source: FundingSource::Treasury,
+        }
+    }
+}
+
+/// Kinds of proposals as of the previous release.
+#[derive(BorshSerialize, BorshDeserialize)]
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum OldProposalKind {
+    ChangeConfig {
+        config: Config,
+    },
+    ChangePolicy {
+        policy: OldVersionedPolicy,
+    },
+    AddMemberToRole {
+        member_id: AccountId,
+        role: String,
+    },
+    RemoveMemberFromRole {
+        member_id: AccountId,
+        role: String,
+    },
+    FunctionCall {
+        receiver_id: AccountId,
+        actions: Vec<OldActionCall>,
+    },
+    UpgradeSelf {
+        hash: Base58CryptoHash,
+    },
+    UpgradeRemote {
+        receiver_id: AccountId,
+        method_name: String,
+        hash: Base58CryptoHash,
+    },
+    Transfer {
+        token_id: OldAccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        msg: Option<String>,
+    },
+    SetStakingContract {
+        staking_id: AccountId,
+    },
+    AddBounty {
+        bounty: Bounty,
+    },
+    BountyDone {
+        bounty_id: u64,
+        receiver_id: AccountId,
+    },
+    Vote,
+    FactoryInfoUpdate {
+        factory_info: FactoryInfo,
+    },
+    ChangePolicyAddOrUpdateRole {
+        role: OldRolePermission,
+    },
+    ChangePolicyRemoveRole {
+        role: String,
+    },
+    ChangePolicyUpdateDefaultVotePolicy {
+        vote_policy: OldVotePolicy,
+    },
+    ChangePolicyUpdateParameters {
+        parameters: PolicyParameters,
+    },
+}
+
+impl From<OldProposalKind> for ProposalKind {
+    fn from(old: OldProposalKind) -> Self {
+        match old {
+            OldProposalKind::ChangeConfig { config } => ProposalKind::ChangeConfig { config },
+            OldProposalKind::ChangePolicy { policy } => ProposalKind::ChangePolicy {
+                policy: policy.into(),
+            },
+            OldProposalKind::AddMemberToRole { member_id, role } => {
+                ProposalKind::AddMemberToRole { member_id, role }
+            }
+            OldProposalKind::RemoveMemberFromRole { member_id, role } => {
+                ProposalKind::RemoveMemberFromRole { member_id, role }
+            }
+            OldProposalKind::FunctionCall {
+                receiver_id,
+                actions,
+            } => ProposalKind::FunctionCall {
+                receiver_id,
+                actions: actions.into_iter().map(Into::into).collect(),
+            },
+            OldProposalKind::UpgradeSelf { hash } => ProposalKind::UpgradeSelf { hash },
+            OldProposalKind::UpgradeRemote {
+                receiver_id,
+                method_name,
+                hash,
+            } => ProposalKind::UpgradeRemote {
+                receiver_id,
+                method_name,
+                hash,
+            },
+            OldProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+            } => ProposalKind::Transfer {
+                token_id,
+                receiver_id,
+                amount,
+                msg,
+            },
+            OldProposalKind::SetStakingContract { staking_id } => {
+                ProposalKind::SetStakingContract { staking_id }
+            }
+            OldProposalKind::AddBounty { bounty } => ProposalKind::AddBounty { bounty },
+            OldProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+            } => ProposalKind::BountyDone {
+                bounty_id,
+                receiver_id,
+            },
+            OldProposalKind::Vote => ProposalKind::Vote,
+            OldProposalKind::FactoryInfoUpdate { factory_info } => {
+                ProposalKind::FactoryInfoUpdate { factory_info }
+            }
+            OldProposalKind::ChangePolicyAddOrUpdateRole { role } => {
+                ProposalKind::ChangePolicyAddOrUpdateRole { role: role.into() }
+            }
+            OldProposalKind::ChangePolicyRemoveRole { role } => {
+                ProposalKind::ChangePolicyRemoveRole { role }
+            }
+            OldProposalKind::ChangePolicyUpdateDefaultVotePolicy { vote_policy } => {
+                ProposalKind::ChangePolicyUpdateDefaultVotePolicy {
+                    vote_policy: vote_policy.into(),
+                }
+            }
+            OldProposalKind::ChangePolicyUpdateParameters { parameters } => {
+                ProposalKind::ChangePolicyUpdateParameters { parameters }
+            }
+        }
+    }
+}
+
+/// Proposal as of the previous release, stored as `VersionedProposal::Default`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct OldProposal {
+    pub proposer: AccountId,
+    pub description: String,
+    pub(crate) kind: OldProposalKind,
+    pub status: ProposalStatus,
+    pub vote_counts: HashMap<String, [Balance; 3]>,
+    pub votes: HashMap<AccountId, Vote>,
+    pub submission_time: U64,
+}
+
+impl From<OldProposal> for Proposal {
+    fn from(old: OldProposal) -> Self {
+        Proposal {
+            proposer: old.proposer,
+            description: old.description,
+            kind: old.kind.into(),
+            status: old.status,
+            vote_counts: old.vote_counts,
+            votes: old.votes,
+            submission_time: old.submission_time,
+        }
+    }
+}
+
 /// Info about factory that deployed this contract and if auto-update is allowed.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
@@ -57,6 +362,7 @@ pub(crate) fn internal_set_factory_info(factory_info: &FactoryInfo) {
 /// Two options who call it:
 ///  - current account, in case of fetching contract code from factory;
 ///  - factory, if this contract allows to factory-update;
+#[cfg(not(feature = "client"))]
 #[no_mangle]
 pub fn update() {
     env::setup_panic_hook();
@@ -12,6 +12,51 @@ pub struct ProposalOutput {
     pub id: u64,
     #[serde(flatten)]
     pub proposal: Proposal,
+    /// Proof of completion for `BountyDone` proposals, if submitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounty_proof: Option<BountyProof>,
+    /// $NEAR funded by the proposer via `propose_with_deposit`, while the proposal is pending.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding: Option<U128>,
+    /// Advisory proposal in the parent DAO, if this proposal is mirrored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror: Option<MirrorLink>,
+    /// Options of `Vote` proposals added with `add_vote_with_options`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vote_options: Option<VoteOptions>,
+    /// Number of distinct voters per role per decision: yes / no / remove, once anyone voted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voter_counts: Option<HashMap<String, [u32; 3]>>,
+    /// Weight of `FlagSpam` votes per role, once anyone flagged the proposal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spam_flags: Option<HashMap<String, U128>>,
+    /// Id of the original proposal, if added with `clone_proposal`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloned_from: Option<u64>,
+    /// Translations of the description, if added with `add_localized_proposal`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translations: Option<Translations>,
+}
+
+/// Labels used in policy permissions, see `consts`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PolicyVocabulary {
+    pub wildcard: String,
+    pub proposal_kinds: Vec<String>,
+    pub actions: Vec<String>,
+}
+
+/// Canonical hash and human readable summary of the proposal input.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalHashOutput {
+    /// Sha256 of the borsh serialized proposal input.
+    pub hash: Base58CryptoHash,
+    /// Label of the proposal kind.
+    pub kind: String,
+    /// Human readable summary of the proposal kind.
+    pub summary: String,
 }
 
 /// This is format of output via JSON for the bounty.
@@ -22,13 +67,77 @@ pub struct BountyOutput {
     pub id: u64,
     #[serde(flatten)]
     pub bounty: Bounty,
+    /// Translations of the description, copied from the `AddBounty` proposal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translations: Option<Translations>,
 }
 
+/// This is format of output via JSON for the grant.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GrantOutput {
+    /// Id of the grant.
+    pub id: u64,
+    #[serde(flatten)]
+    pub grant: Grant,
+}
+
+/// This is format of output via JSON for the delegation.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DelegationOutput {
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+/// Section of the state that can be exported via `export_state_chunk`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum StateSection {
+    Config,
+    Policy,
+    Proposals,
+    Bounties,
+    Grants,
+    Delegations,
+}
+
+/// Data of the exported section.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[allow(clippy::large_enum_variant)]
+pub enum StateChunkData {
+    Config(Config),
+    Policy(Policy),
+    Proposals(Vec<ProposalOutput>),
+    Bounties(Vec<BountyOutput>),
+    Grants(Vec<GrantOutput>),
+    Delegations(Vec<DelegationOutput>),
+}
+
+/// Chunk of the DAO state, used for off-chain backups and migration tooling.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StateChunk {
+    pub section: StateSection,
+    /// Index this chunk starts from.
+    pub from_index: u64,
+    /// Total number of entries in the section. Ids of proposals, bounties and grants are in `0..total`.
+    /// Config and policy are always exported as a single entry.
+    pub total: u64,
+    pub data: StateChunkData,
+}
+
+#[cfg(not(feature = "client"))]
 #[near_bindgen]
 impl Contract {
-    /// Returns semver of this contract.
+    /// Returns semver of this contract. If `GIT_HASH` was set at build time, the commit is
+    /// added as build metadata, e.g. `2.0.0+1a2b3c4`.
     pub fn version(&self) -> String {
-        env!("CARGO_PKG_VERSION").to_string()
+        match option_env!("GIT_HASH") {
+            Some(hash) => format!("{}+{}", env!("CARGO_PKG_VERSION"), hash),
+            None => env!("CARGO_PKG_VERSION").to_string(),
+        }
     }
 
     /// Returns config of this contract.
@@ -80,11 +189,73 @@ impl Contract {
         )
     }
 
+    /// Returns number of successful `Transfer` payouts of given token to given receiver.
+    /// `token_id` is "" for $NEAR.
+    pub fn get_receiver_payouts(&self, account_id: AccountId, token_id: OldAccountId) -> u32 {
+        self.receiver_payouts
+            .get(&(account_id, token_id))
+            .unwrap_or_default()
+    }
+
+    /// Returns proposal kind and action labels to build policy permissions from.
+    pub fn get_policy_vocabulary(&self) -> PolicyVocabulary {
+        PolicyVocabulary {
+            wildcard: consts::WILDCARD.to_string(),
+            proposal_kinds: consts::PROPOSAL_KIND_LABELS
+                .iter()
+                .map(|label| label.to_string())
+                .collect(),
+            actions: consts::ACTION_LABELS
+                .iter()
+                .map(|label| label.to_string())
+                .collect(),
+        }
+    }
+
     /// Last proposal's id.
     pub fn get_last_proposal_id(&self) -> u64 {
         self.last_proposal_id
     }
 
+    /// Number of proposals in progress, limited by the policy's `max_active_proposals`.
+    pub fn get_active_proposal_count(&self) -> u64 {
+        self.active_proposals.len()
+    }
+
+    /// Returns canonical hash and summary of the proposal input.
+    /// Allows signers to verify the proposal before submitting it with `add_proposal`.
+    pub fn hash_proposal(&self, proposal: ProposalInput) -> ProposalHashOutput {
+        let mut hash = CryptoHash::default();
+        hash.copy_from_slice(&env::sha256(&proposal.try_to_vec().unwrap()));
+        ProposalHashOutput {
+            hash: hash.into(),
+            kind: proposal.kind.to_policy_label().to_string(),
+            summary: proposal.kind.summary(),
+        }
+    }
+
+    /// Returns ids of upgrade proposals referencing given blob and if each is still pending.
+    /// Blob can't be removed while any of them is pending.
+    pub fn get_blob_references(&self, hash: Base58CryptoHash) -> Vec<(u64, bool)> {
+        let policy = self.policy.get().unwrap().to_policy();
+        self.blob_references
+            .get(&CryptoHash::from(hash))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|id| (id, self.internal_is_proposal_pending(&policy, id)))
+            .collect()
+    }
+
+    /// Returns alternate of given group member seat, if any.
+    pub fn get_alternate(&self, seat_id: AccountId) -> Option<AccountId> {
+        self.alternates.get(&seat_id)
+    }
+
+    /// Sequence number of the last emitted event. 0 if no events were emitted.
+    pub fn get_last_event_seq(&self) -> u64 {
+        self.last_event_seq
+    }
+
     /// Get proposals in paginated view.
     pub fn get_proposals(&self, from_index: u64, limit: u64) -> Vec<ProposalOutput> {
         (from_index..min(self.last_proposal_id, from_index + limit))
@@ -92,6 +263,14 @@ impl Contract {
                 self.proposals.get(&id).map(|proposal| ProposalOutput {
                     id,
                     proposal: proposal.into(),
+                    bounty_proof: self.bounty_proofs.get(&id),
+                    funding: self.proposal_fundings.get(&id).map(U128),
+                    mirror: self.mirror_links.get(&id),
+                    vote_options: self.vote_options.get(&id),
+                    voter_counts: self.proposal_voter_counts.get(&id),
+                    spam_flags: self.internal_spam_flags(id),
+                    cloned_from: self.cloned_from.get(&id),
+                    translations: self.proposal_translations.get(&id),
                 })
             })
             .collect()
@@ -103,6 +282,14 @@ impl Contract {
         ProposalOutput {
             id,
             proposal: proposal.into(),
+            bounty_proof: self.bounty_proofs.get(&id),
+            funding: self.proposal_fundings.get(&id).map(U128),
+            mirror: self.mirror_links.get(&id),
+            vote_options: self.vote_options.get(&id),
+            voter_counts: self.proposal_voter_counts.get(&id),
+            spam_flags: self.internal_spam_flags(id),
+            cloned_from: self.cloned_from.get(&id),
+            translations: self.proposal_translations.get(&id),
         }
     }
 
@@ -112,14 +299,53 @@ impl Contract {
         BountyOutput {
             id,
             bounty: bounty.into(),
+            translations: self.bounty_translations.get(&id),
         }
     }
 
+    /// Remaining pool budget of given renewable bounty, if it has a pool.
+    pub fn get_bounty_pool(&self, id: u64) -> Option<U128> {
+        self.bounty_pools.get(&id).map(U128)
+    }
+
     /// Get number of bounties.
     pub fn get_last_bounty_id(&self) -> u64 {
         self.last_bounty_id
     }
 
+    /// Number of open bounties, limited by the policy's `max_open_bounties`.
+    pub fn get_open_bounty_count(&self) -> u64 {
+        self.open_bounties.len()
+    }
+
+    /// Whether given account is blocked from adding proposals and claiming bounties.
+    pub fn is_blocked(&self, account_id: AccountId) -> bool {
+        self.blocklist.contains(&account_id)
+    }
+
+    /// Get `limit` of blocked accounts from given index.
+    pub fn get_blocklist(&self, from_index: u64, limit: u64) -> Vec<AccountId> {
+        let accounts = self.blocklist.as_vector();
+        (from_index..min(from_index + limit, accounts.len()))
+            .filter_map(|index| accounts.get(index))
+            .collect()
+    }
+
+    /// Option selected by given account on a `Vote` proposal with options.
+    pub fn get_vote_option_selection(&self, id: u64, account_id: AccountId) -> Option<u32> {
+        self.vote_option_selections.get(&(id, account_id))
+    }
+
+    /// Which proposals are mirrored as advisory votes in a parent DAO.
+    pub fn get_vote_mirror(&self) -> Option<VoteMirror> {
+        self.vote_mirror.clone()
+    }
+
+    /// Returns gas attached to cross-contract calls.
+    pub fn get_gas_config(&self) -> GasConfig {
+        self.gas_config.clone()
+    }
+
     /// Get `limit` of bounties from given index.
     pub fn get_bounties(&self, from_index: u64, limit: u64) -> Vec<BountyOutput> {
         (from_index..std::cmp::min(from_index + limit, self.last_bounty_id))
@@ -127,6 +353,7 @@ impl Contract {
                 self.bounties.get(&id).map(|bounty| BountyOutput {
                     id,
                     bounty: bounty.into(),
+                    translations: self.bounty_translations.get(&id),
                 })
             })
             .collect()
@@ -141,4 +368,76 @@ impl Contract {
     pub fn get_bounty_number_of_claims(&self, id: u64) -> u32 {
         self.bounty_claims_count.get(&id).unwrap_or_default()
     }
+
+    /// Get number of grant applications.
+    pub fn get_last_grant_id(&self) -> u64 {
+        self.last_grant_id
+    }
+
+    /// Get given grant application by id.
+    pub fn get_grant(&self, id: u64) -> GrantOutput {
+        GrantOutput {
+            id,
+            grant: self.internal_get_grant(id),
+        }
+    }
+
+    /// Get `limit` of grant applications from given index.
+    pub fn get_grants(&self, from_index: u64, limit: u64) -> Vec<GrantOutput> {
+        (from_index..std::cmp::min(from_index + limit, self.last_grant_id))
+            .filter_map(|id| {
+                self.grants.get(&id).map(|grant| GrantOutput {
+                    id,
+                    grant: grant.into(),
+                })
+            })
+            .collect()
+    }
+
+    /// Exports `limit` entries of given section of the state starting from `from_index`.
+    /// Entries are serialized in the same format as in the regular views, removed ones are skipped.
+    pub fn export_state_chunk(
+        &self,
+        section: StateSection,
+        from_index: u64,
+        limit: u64,
+    ) -> StateChunk {
+        let (total, data) = match section {
+            StateSection::Config => (1, StateChunkData::Config(self.get_config())),
+            StateSection::Policy => (1, StateChunkData::Policy(self.get_policy())),
+            StateSection::Proposals => (
+                self.last_proposal_id,
+                StateChunkData::Proposals(self.get_proposals(from_index, limit)),
+            ),
+            StateSection::Bounties => (
+                self.last_bounty_id,
+                StateChunkData::Bounties(self.get_bounties(from_index, limit)),
+            ),
+            StateSection::Grants => (
+                self.last_grant_id,
+                StateChunkData::Grants(self.get_grants(from_index, limit)),
+            ),
+            StateSection::Delegations => {
+                let accounts = self.delegation_accounts.as_vector();
+                (
+                    accounts.len(),
+                    StateChunkData::Delegations(
+                        (from_index..min(from_index + limit, accounts.len()))
+                            .filter_map(|index| accounts.get(index))
+                            .map(|account_id| DelegationOutput {
+                                amount: self.delegation_balance_of(account_id.clone()),
+                                account_id,
+                            })
+                            .collect(),
+                    ),
+                )
+            }
+        };
+        StateChunk {
+            section,
+            from_index,
+            total,
+            data,
+        }
+    }
 }
@@ -0,0 +1,225 @@
+//! Labeled options for `Vote` proposals.
+
+use std::collections::HashMap;
+
+use near_sdk::json_types::U128;
+
+use crate::*;
+
+/// Maximum number of options of a single `Vote` proposal.
+pub const MAX_VOTE_OPTIONS: usize = 32;
+
+/// Option that voters can select on a `Vote` proposal.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteOption {
+    pub label: String,
+    pub description: String,
+    /// Links to external resources describing the option.
+    #[serde(default)]
+    pub links: Vec<String>,
+}
+
+/// Options of a `Vote` proposal with the weight of the voters that selected each of them.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteOptions {
+    pub options: Vec<VoteOption>,
+    /// Weight of the selections of each option per role, counted like the proposal's `vote_counts`.
+    pub counts: HashMap<String, Vec<U128>>,
+}
+
+impl Contract {
+    /// Panics if `action` is a plain approve / reject of a proposal with options, those are voted
+    /// on with `act_proposal_with_option`.
+    pub(crate) fn assert_no_vote_options(&self, id: u64, action: &Action) {
+        assert!(
+            !matches!(action, Action::VoteApprove | Action::VoteReject)
+                || self.vote_options.get(&id).is_none(),
+            "ERR_USE_VOTE_OPTIONS"
+        );
+    }
+}
+
+#[cfg(not(feature = "client"))]
+#[near_bindgen]
+impl Contract {
+    /// Add `Vote` proposal with labeled options for voters to select from.
+    #[payable]
+    pub fn add_vote_with_options(&mut self, description: String, options: Vec<VoteOption>) -> u64 {
+        assert!(
+            options.len() >= 2 && options.len() <= MAX_VOTE_OPTIONS,
+            "ERR_INVALID_VOTE_OPTIONS"
+        );
+        let id = self.add_proposal(ProposalInput {
+            description,
+            kind: ProposalKind::Vote,
+        });
+        let initial_storage = env::storage_usage();
+        self.vote_options.insert(
+            &id,
+            &VoteOptions {
+                options,
+                counts: HashMap::default(),
+            },
+        );
+        let policy = self.policy.get().unwrap().to_policy();
+        self.internal_charge_author_storage(
+            &policy,
+            StorageChargeKey::Proposal(id),
+            &env::predecessor_account_id(),
+            initial_storage,
+            false,
+        );
+        id
+    }
+
+    /// Vote on `Vote` proposal by selecting one of its options.
+    /// Counts as approval of the proposal, so the proposal's vote policy decides when the vote is over.
+    /// Storage of the selection is charged to the voter like the storage of proposals.
+    pub fn act_proposal_with_option(
+        &mut self,
+        id: u64,
+        option: u32,
+        memo: Option<String>,
+    ) -> ActProposalResult {
+        let mut vote_options = self.vote_options.get(&id).expect("ERR_NO_VOTE_OPTIONS");
+        assert!(
+            (option as usize) < vote_options.options.len(),
+            "ERR_INVALID_VOTE_OPTION"
+        );
+        let sender_id = env::predecessor_account_id();
+        let result = self.internal_act_proposal(id, sender_id.clone(), Action::VoteApprove, memo);
+        let initial_storage = env::storage_usage();
+        let num_options = vote_options.options.len();
+        for (role, weight) in &result.counted_weights {
+            vote_options
+                .counts
+                .entry(role.clone())
+                .or_insert_with(|| vec![U128(0); num_options])[option as usize]
+                .0 += weight.0;
+        }
+        self.vote_options.insert(&id, &vote_options);
+        self.vote_option_selections
+            .insert(&(id, sender_id.clone()), &option);
+        let policy = self.policy.get().unwrap().to_policy();
+        self.internal_charge_author_storage(
+            &policy,
+            StorageChargeKey::VoteOptionSelection(id, sender_id.clone()),
+            &sender_id,
+            initial_storage,
+            false,
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+    use near_sdk_sim::to_yocto;
+
+    use crate::{Action, Config};
+
+    use super::*;
+
+    fn option(label: &str) -> VoteOption {
+        VoteOption {
+            label: label.to_string(),
+            description: format!("Option {}", label),
+            links: vec![],
+        }
+    }
+
+    #[test]
+    fn test_vote_with_options() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_vote_with_options("test".to_string(), vec![option("A"), option("B")]);
+        contract.act_proposal_with_option(id, 1, None);
+        assert_eq!(contract.get_vote_option_selection(id, accounts(1)), Some(1));
+        let output = contract.get_proposal(id);
+        assert_eq!(
+            output.vote_options.unwrap().counts["council"],
+            vec![U128(0), U128(1)]
+        );
+        assert_eq!(output.proposal.status, ProposalStatus::InProgress);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_USE_VOTE_OPTIONS")]
+    fn test_plain_vote_on_vote_with_options() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_vote_with_options("test".to_string(), vec![option("A"), option("B")]);
+        contract.act_proposal(id, Action::VoteApprove, None);
+    }
+
+    #[test]
+    fn test_vote_with_options_charges_storage() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.charge_author_storage = true;
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.author_storage_deposit(None);
+        let id = contract.add_vote_with_options("test".to_string(), vec![option("A"), option("B")]);
+        let proposal_balance = contract.storage_balance_of_author(accounts(1)).0;
+        contract.act_proposal_with_option(id, 0, None);
+        assert!(contract.storage_balance_of_author(accounts(1)).0 < proposal_balance);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_AUTHOR_STORAGE")]
+    fn test_vote_with_options_without_storage() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1), accounts(2), accounts(3)]),
+        );
+        let mut policy = contract.policy.get().unwrap().to_policy();
+        policy.charge_author_storage = true;
+        contract.policy.set(&VersionedPolicy::Current(policy));
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        contract.author_storage_deposit(None);
+        let id = contract.add_vote_with_options("test".to_string(), vec![option("A"), option("B")]);
+        testing_env!(context
+            .predecessor_account_id(accounts(2))
+            .attached_deposit(0)
+            .build());
+        contract.act_proposal_with_option(id, 0, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_VOTE_OPTION")]
+    fn test_vote_with_invalid_option() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        let mut contract = Contract::new(
+            Config::test_config(),
+            VersionedPolicy::Default(vec![accounts(1)]),
+        );
+        testing_env!(context.attached_deposit(to_yocto("1")).build());
+        let id = contract.add_vote_with_options("test".to_string(), vec![option("A"), option("B")]);
+        contract.act_proposal_with_option(id, 2, None);
+    }
+}
@@ -105,7 +105,7 @@ export async function setStakingId(
     });
 }
 
-export const regCost = STORAGE_PER_BYTE.mul(new BN(16));
+export const regCost = STORAGE_PER_BYTE.mul(new BN(16 + 2 * (64 + 8)));
 
 export async function registerAndDelegate(
     dao: NearAccount,
@@ -27,7 +27,7 @@ import * as fs from 'fs';
 workspace.test('View method version', async (test, { alice, root, dao }) => {
     test.log('Version:');
     test.log(await dao.view('version'));
-    test.is(await dao.view('version'), '2.0.0');
+    test.regex(await dao.view('version'), /^2\.0\.0(\+[0-9a-f]+)?$/);
 });
 
 workspace.test('View method get_config', async (test, { root }) => {
@@ -78,6 +78,7 @@ workspace.test('View method get_policy', async (test, { root }) => {
                     '*:VoteApprove',
                     '*:VoteReject',
                     '*:VoteRemove',
+                    '*:FlagSpam',
                 ],
                 vote_policy: {},
             },
@@ -86,11 +87,31 @@ workspace.test('View method get_policy', async (test, { root }) => {
             weight_kind: 'RoleWeight',
             quorum: '0',
             threshold: [1, 2],
+            min_voters: 0,
         },
         proposal_bond: '1000000000000000000000000',
         proposal_period: '604800000000000',
         bounty_bond: '1000000000000000000000000',
         bounty_forgiveness_period: '86400000000000',
+        allow_self_transfers: false,
+        trusted_transfer_rules: [],
+        bounty_token_bonds: {},
+        ragequit: null,
+        spend_limits: null,
+        vote_decay_after: null,
+        max_active_proposals: null,
+        max_open_bounties: null,
+        social_db_id: null,
+        proposal_token_bonds: {},
+        tally_oracle_role: null,
+        offchain_tally_kinds: [],
+        charge_author_storage: false,
+        spam_threshold: null,
+        notify_proposer: false,
+        transfer_tiers: [],
+        vote_rate_limits: {},
+        sponsor_role: null,
+        executors: {},
     };
     test.deepEqual(await bob.view('get_policy'), policy);
 });
@@ -7,8 +7,9 @@ use near_sdk_sim::{call, to_yocto, view};
 use crate::utils::*;
 use sputnik_staking::User;
 use sputnikdao2::{
-    Action, BountyClaim, BountyOutput, Policy, Proposal, ProposalInput, ProposalKind,
-    ProposalOutput, ProposalStatus, RoleKind, RolePermission, VersionedPolicy, VotePolicy,
+    Action, BountyClaim, BountyOutput, Policy, PolicyBuilder, Proposal, ProposalInput,
+    ProposalKind, ProposalOutput, ProposalStatus, RoleKind, RolePermission, VersionedPolicy,
+    VotePolicy,
 };
 
 mod utils;
@@ -49,6 +50,25 @@ fn test_multi_council() {
         proposal_period: U64::from(1_000_000_000 * 60 * 60 * 24 * 7),
         bounty_bond: U128(10u128.pow(24)),
         bounty_forgiveness_period: U64::from(1_000_000_000 * 60 * 60 * 24),
+        allow_self_transfers: false,
+        trusted_transfer_rules: vec![],
+        bounty_token_bonds: HashMap::default(),
+        ragequit: None,
+        spend_limits: None,
+        vote_decay_after: None,
+        max_active_proposals: None,
+        max_open_bounties: None,
+        social_db_id: None,
+        proposal_token_bonds: HashMap::default(),
+        tally_oracle_role: None,
+        offchain_tally_kinds: vec![],
+        charge_author_storage: false,
+        spam_threshold: None,
+        notify_proposer: false,
+        transfer_tiers: vec![],
+        vote_rate_limits: HashMap::default(),
+        sponsor_role: None,
+        executors: HashMap::default(),
     };
     add_proposal(
         &root,
@@ -75,6 +95,31 @@ fn test_multi_council() {
     assert_eq!(proposal.status, ProposalStatus::Approved);
 }
 
+#[test]
+fn test_policy_builder() {
+    let (root, dao) = setup_dao();
+    let new_policy = PolicyBuilder::new()
+        .add_role("all", RoleKind::Everyone, &["*:AddProposal".to_string()])
+        .add_group("council", &[root.account_id()], &["*:*".to_string()])
+        .add_group("community", &[user(1), user(2)], &["*:*".to_string()])
+        .build()
+        .unwrap()
+        .to_policy();
+    add_proposal(
+        &root,
+        &dao,
+        ProposalInput {
+            description: "new policy".to_string(),
+            kind: ProposalKind::ChangePolicy {
+                policy: VersionedPolicy::Current(new_policy.clone()),
+            },
+        },
+    )
+    .assert_success();
+    vote(vec![&root], &dao, 0);
+    assert_eq!(view!(dao.get_policy()).unwrap_json::<Policy>(), new_policy);
+}
+
 #[test]
 fn test_bounty_workflow() {
     let (root, dao) = setup_dao();
@@ -152,7 +197,7 @@ fn test_bounty_workflow() {
 
     call!(
         user2,
-        dao.bounty_done(bounty_id, None, "Bounty is done".to_string()),
+        dao.bounty_done(bounty_id, None, "Bounty is done".to_string(), None),
         deposit = to_yocto("1")
     )
     .assert_success();
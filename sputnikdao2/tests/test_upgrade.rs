@@ -43,7 +43,8 @@ fn test_upgrade_using_factory() {
         root,
         factory.create(
             AccountId::new_unchecked("testdao".to_string()),
-            Base64VecU8(params)
+            Base64VecU8(params),
+            None
         ),
         deposit = to_yocto("10")
     )